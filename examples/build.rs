@@ -18,5 +18,8 @@ fn main() {
     target.set_internal_name("Target");
     target.implement_with_doc("The target of an implement command.");
 
-    handle_all_implementations(&CodegenConfig::default());
+    if let Err(e) = handle_all_implementations(&CodegenConfig::default()) {
+        eprintln!("Code generation failed: {}", e);
+        std::process::exit(1);
+    }
 }
@@ -24,6 +24,27 @@ pub struct CodegenConfig {
     ///    without `build.rs` to do it
     ///  * A release branch will be created, ready for cargo publishing
     pub release: bool,
+    /// Whether or not to only plan out the generated files, without writing anything to disk.
+    ///
+    /// When this is true, no files will be written or removed, and neither Git nor the autogen
+    /// tracker will be touched.
+    pub dry_run: bool,
+    /// Whether or not to put attribute and flag accessors into a trait for each archetype, instead
+    /// of into an inherent `impl` on its struct.
+    ///
+    /// The trait gets implemented for the archetype and all of its descendants, so that generic
+    /// code can use the accessors polymorphically.
+    pub accessor_traits: bool,
+    /// Whether or not to also generate the crate's root `tao` module, along with every
+    /// intermediate module leading down to the newly defined concepts.
+    ///
+    /// The root module defines an `initialize_kb` function that initializes the crate being built
+    /// on, followed by the concepts of this crate. Has no effect when building Yin itself.
+    pub tao_root: bool,
+    /// Whether or not to generate a builder for each archetype with attributes, which takes
+    /// values for all of them and checks that the required ones were given before creating the
+    /// concept.
+    pub builders: bool,
 }
 
 impl Default for CodegenConfig {
@@ -34,6 +55,10 @@ impl Default for CodegenConfig {
             track_autogen: false,
             yin: false,
             release: false,
+            dry_run: false,
+            accessor_traits: false,
+            tao_root: false,
+            builders: false,
         }
     }
 }
@@ -0,0 +1,274 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+/// Everything that can go wrong while generating code.
+#[derive(Debug)]
+pub enum CodegenError {
+    /// A concept needs dual-purpose documentation for its accessors, but has none.
+    MissingDocumentation {
+        /// The concept missing documentation.
+        concept: String,
+    },
+    /// A data concept has no Rust primitive to represent it.
+    MissingRustPrimitive {
+        /// The data concept missing a primitive.
+        concept: String,
+    },
+    /// A data concept has no default value to initialize it with.
+    MissingDefaultValue {
+        /// The data concept missing a default value.
+        concept: String,
+    },
+    /// A concept has no internal name to generate Rust names from.
+    UnnamedConcept {
+        /// A description of the concept, for lack of a name.
+        concept: String,
+    },
+    /// No concept goes by the requested name.
+    UnknownConcept {
+        /// The name that was looked up.
+        name: String,
+    },
+    /// A concept is marked as both newly defined in this build and imported from elsewhere.
+    ConflictingOrigin {
+        /// The concept with the conflicting markings.
+        concept: String,
+    },
+    /// Code was requested for a concept imported from another crate, which generates that code
+    /// itself.
+    ImportedConcept {
+        /// The imported concept.
+        concept: String,
+    },
+    /// A concept is missing something that code generation needs in order to make sense of it.
+    MalformedConcept {
+        /// The malformed concept.
+        concept: String,
+        /// What is missing or wrong.
+        reason: String,
+    },
+    /// Several concepts would be output to the same file.
+    DuplicateFilePath {
+        /// The file that would be output more than once.
+        path: String,
+        /// The concepts competing for that file.
+        concepts: Vec<String>,
+    },
+    /// The newly defined concepts build on several crates that don't build on each other, so
+    /// there's no telling which crate's concept IDs to continue on from.
+    AmbiguousIdBase {
+        /// The crates that concept IDs could continue on from.
+        crates: Vec<String>,
+    },
+    /// The newly defined concepts build on a concept that was imported without saying which crate
+    /// defines it, so there's no telling which crate's concept IDs to continue on from.
+    UnknownIdBase {
+        /// The imported concept.
+        concept: String,
+    },
+    /// An individual's attribute points to another individual that isn't being generated, so
+    /// there's no ID to point to.
+    UnimplementedIndividual {
+        /// The individual that is pointed to.
+        individual: String,
+    },
+    /// An attribute wants an inverse accessor on its value type, but the value type isn't being
+    /// generated, so there's nowhere to put the accessor.
+    UngeneratedInverse {
+        /// The attribute with the inverse accessor.
+        attribute: String,
+        /// The value type that isn't being generated.
+        value: String,
+    },
+    /// Several problems were found at once.
+    Invalid(Vec<CodegenError>),
+    /// A build manifest could not be read or written.
+    InvalidManifest {
+        /// Why the manifest is invalid.
+        reason: String,
+    },
+    /// An ID lock file could not be read or written.
+    InvalidIdLock {
+        /// Why the lock file is invalid.
+        reason: String,
+    },
+    /// A declarative concept specification could not be read or loaded.
+    InvalidSpec {
+        /// Why the specification is invalid.
+        reason: String,
+    },
+    /// The hand-written regions of a file could not be made sense of.
+    InvalidUserRegion {
+        /// The file with the malformed regions.
+        path: String,
+        /// Why the regions are malformed.
+        reason: String,
+    },
+    /// Hand-written regions of a file no longer have a place in the regenerated file, and would be
+    /// lost if it were overwritten.
+    OrphanedUserRegions {
+        /// The file that the regions were found in.
+        path: String,
+        /// Anchors of the orphaned regions.
+        anchors: Vec<String>,
+    },
+    /// Reading or writing a file failed.
+    Io {
+        /// The file that could not be read or written.
+        path: String,
+        /// The underlying IO error.
+        source: io::Error,
+    },
+}
+
+impl CodegenError {
+    /// Wrap an IO error that happened while dealing with the given path.
+    pub fn io(path: &str, source: io::Error) -> Self {
+        CodegenError::Io {
+            path: path.to_owned(),
+            source,
+        }
+    }
+}
+
+impl Display for CodegenError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            CodegenError::MissingDocumentation { concept } => write!(
+                f,
+                "{} has no dual-purpose documentation for its accessors",
+                concept
+            ),
+            CodegenError::MissingRustPrimitive { concept } => {
+                write!(f, "Data type {} has no defined Rust primitive", concept)
+            }
+            CodegenError::MissingDefaultValue { concept } => {
+                write!(f, "Data type {} has no defined default value", concept)
+            }
+            CodegenError::UnnamedConcept { concept } => {
+                write!(f, "{} has no internal name", concept)
+            }
+            CodegenError::UnknownConcept { name } => write!(f, "No concept is named {}", name),
+            CodegenError::ConflictingOrigin { concept } => {
+                write!(f, "{} is both newly defined and imported", concept)
+            }
+            CodegenError::ImportedConcept { concept } => write!(
+                f,
+                "{} is imported, so its code belongs to the crate that defines it",
+                concept
+            ),
+            CodegenError::MalformedConcept { concept, reason } => {
+                write!(f, "{} is malformed: {}", concept, reason)
+            }
+            CodegenError::DuplicateFilePath { path, concepts } => {
+                write!(f, "{} would all be output to {}", concepts.join(", "), path)
+            }
+            CodegenError::AmbiguousIdBase { crates } => write!(
+                f,
+                "Concept IDs could continue on from any of {}, which don't build on each other",
+                crates.join(", ")
+            ),
+            CodegenError::UnknownIdBase { concept } => write!(
+                f,
+                "{} is imported without a crate name, so concept IDs have no crate to continue on \
+                 from. Import the build manifest of the crate that defines it, or set its crate name",
+                concept
+            ),
+            CodegenError::UnimplementedIndividual { individual } => write!(
+                f,
+                "{} is the attribute value of a generated individual, but isn't generated itself",
+                individual
+            ),
+            CodegenError::UngeneratedInverse { attribute, value } => write!(
+                f,
+                "{} has an inverse accessor, but its value type {} isn't generated",
+                attribute, value
+            ),
+            CodegenError::Invalid(problems) => {
+                write!(f, "Found {} problems:", problems.len())?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+            CodegenError::InvalidManifest { reason } => {
+                write!(f, "Invalid build manifest: {}", reason)
+            }
+            CodegenError::InvalidIdLock { reason } => {
+                write!(f, "Invalid ID lock file: {}", reason)
+            }
+            CodegenError::InvalidSpec { reason } => {
+                write!(f, "Invalid concept specification: {}", reason)
+            }
+            CodegenError::InvalidUserRegion { path, reason } => {
+                write!(f, "Invalid hand-written region in {}: {}", path, reason)
+            }
+            CodegenError::OrphanedUserRegions { path, anchors } => write!(
+                f,
+                "Hand-written regions {} in {} no longer exist in the generated code",
+                anchors.join(", "),
+                path
+            ),
+            CodegenError::Io { path, source } => write!(f, "Could not access {}: {}", path, source),
+        }
+    }
+}
+
+impl Error for CodegenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CodegenError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_names_concept() {
+        let error = CodegenError::MissingRustPrimitive {
+            concept: "my-data".to_owned(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Data type my-data has no defined Rust primitive"
+        );
+    }
+
+    #[test]
+    fn test_display_all_problems() {
+        let error = CodegenError::Invalid(vec![
+            CodegenError::UnnamedConcept {
+                concept: "unnamed concept #3".to_owned(),
+            },
+            CodegenError::DuplicateFilePath {
+                path: "src/tao/form/a_form.rs".to_owned(),
+                concepts: vec!["a".to_owned(), "A".to_owned()],
+            },
+        ]);
+        assert_eq!(
+            error.to_string(),
+            "Found 2 problems:\n  \
+                - unnamed concept #3 has no internal name\n  \
+                - a, A would all be output to src/tao/form/a_form.rs"
+        );
+    }
+
+    #[test]
+    fn test_io_source() {
+        let error = CodegenError::io(
+            "src/tao/auto_init.rs",
+            io::Error::new(io::ErrorKind::PermissionDenied, "denied"),
+        );
+        assert_eq!(
+            error.to_string(),
+            "Could not access src/tao/auto_init.rs: denied"
+        );
+        assert!(error.source().is_some());
+    }
+}
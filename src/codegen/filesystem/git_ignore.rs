@@ -1,19 +1,14 @@
-use crate::codegen::track_autogen::{add_to_file, track_autogen};
+use crate::codegen::track_autogen::{add_to_file, unignore};
 use path_abs::{PathAbs, PathInfo, PathOps};
 use std::io::Result;
 use std::process::Command;
 
 /// Remove a file from Git
-pub fn git_rm(filepath: &str) {
+pub fn git_rm(filepath: &str) -> Result<()> {
     Command::new("git")
         .args(&["rm", "-f", filepath])
         .output()
-        .unwrap_or_else(|_| {
-            panic!(
-                "Could not remove file from Git, please do so manually: {}",
-                filepath
-            )
-        });
+        .map(|_| ())
 }
 
 /// Add a file to gitignore in the same directory. Creates the .gitignore if it doesn't yet exist,
@@ -22,7 +17,11 @@ pub fn git_ignore(file: &PathAbs) -> Result<()> {
     let filename = file.file_name().unwrap().to_str().unwrap();
     let gitignore = file.with_file_name(".gitignore");
     // todo: cut down on the number of file reads we're doing here
-    track_autogen(gitignore.as_path().to_str().unwrap().to_owned());
     add_to_file(&gitignore, ".gitignore")?;
     add_to_file(&gitignore, filename)
 }
+
+/// Stop ignoring a file that a previous build ignored, so that it can be committed.
+pub fn git_unignore(file: &PathAbs) -> Result<()> {
+    unignore(file.as_path().to_str().unwrap())
+}
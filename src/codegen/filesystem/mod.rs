@@ -1,12 +1,36 @@
 /// Logic for ignoring autogenerated files in Git.
 mod git_ignore;
+/// Plans for file output that can be inspected without touching the filesystem.
+mod plan;
 
-use crate::codegen::track_autogen::track_autogen;
-use git_ignore::{git_ignore, git_rm};
-use path_abs::PathAbs;
+use crate::codegen::CodegenError;
+use git_ignore::{git_ignore, git_rm, git_unignore};
+use path_abs::{PathAbs, PathOps};
+pub use plan::{CodegenPlan, PlannedFile};
 use std::fs;
+use std::io;
 use std::path::Path;
 
+/// What happened to a file when generated code was output to it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OutputStatus {
+    /// The file did not exist before.
+    Created,
+    /// The file existed before, but with different contents.
+    Updated,
+    /// The file already had exactly the generated contents, and was left alone.
+    Unchanged,
+}
+
+/// Determine what outputting the given code to the given file would do.
+pub fn output_status(file_path: &str, code: &str) -> OutputStatus {
+    match fs::read_to_string(file_path) {
+        Ok(existing) if existing == code => OutputStatus::Unchanged,
+        Ok(_) => OutputStatus::Updated,
+        Err(_) => OutputStatus::Created,
+    }
+}
+
 /// Config options for writing out to a file.
 pub struct OutputConfig<'a> {
     /// The final generated code, to be output to a file verbatim.
@@ -17,35 +41,73 @@ pub struct OutputConfig<'a> {
     pub git_ignore: bool,
     /// Whether or not to track generated files in Cargo.
     pub cargo_track: bool,
+    /// Whether or not the file is generated code that should be cleaned up once nothing generates
+    /// it anymore. Files that are meant to be committed, such as the ID lock, should not be.
+    pub autogen: bool,
 }
 
-/// Output code to filename
-pub fn output_code_verbatim(cfg: &OutputConfig) {
-    let file_pathabs = PathAbs::new(Path::new(cfg.file_path))
-        .unwrap_or_else(|_| panic!("Could not get absolute path for {}", cfg.file_path));
-    let file_absolute = file_pathabs.as_path().to_str().unwrap();
-    let file_parent = file_pathabs
-        .as_path()
-        .parent()
-        .unwrap_or_else(|| panic!("Could not get parent directory for {}", file_absolute));
-    fs::create_dir_all(file_parent).unwrap_or_else(|_| {
-        panic!(
-            "Could not create intermediate directories for {}",
-            file_absolute
+/// Get the absolute version of a path.
+fn absolute_path(file_path: &str) -> Result<PathAbs, CodegenError> {
+    PathAbs::new(Path::new(file_path)).map_err(|e| CodegenError::io(file_path, e.into()))
+}
+
+/// Describe what `output_code_verbatim` would do with this config, without doing it.
+pub fn plan_output(cfg: &OutputConfig) -> Result<PlannedFile, CodegenError> {
+    let git_ignore = if cfg.git_ignore {
+        Some(
+            absolute_path(cfg.file_path)?
+                .with_file_name(".gitignore")
+                .as_path()
+                .to_str()
+                .unwrap()
+                .to_owned(),
         )
-    });
-    git_rm(&file_absolute);
-    fs::write(file_absolute, cfg.code)
-        .unwrap_or_else(|_| panic!("Couldn't output generated code to {}", file_absolute));
-    // track in .autogen for completeness, regardless of release options
-    track_autogen(file_pathabs.as_path().to_str().unwrap().to_owned());
+    } else {
+        None
+    };
+    Ok(PlannedFile {
+        path: cfg.file_path.to_owned(),
+        code: cfg.code.to_owned(),
+        git_ignore,
+        cargo_track: cfg.cargo_track,
+        autogen: cfg.autogen,
+        status: output_status(cfg.file_path, cfg.code),
+    })
+}
+
+/// Output code to filename. The file is left untouched if it already contains the exact same code,
+/// so that Cargo doesn't see a changed mtime and rebuild everything downstream.
+///
+/// The output is not tracked for stale file cleanup. That is up to the `CodegenSession` that the
+/// output is a part of.
+pub fn output_code_verbatim(cfg: &OutputConfig) -> Result<OutputStatus, CodegenError> {
+    let file_pathabs = absolute_path(cfg.file_path)?;
+    let file_absolute = file_pathabs.as_path().to_str().unwrap();
+    let io_error = |e: io::Error| CodegenError::io(file_absolute, e);
+    let file_parent = file_pathabs.as_path().parent().ok_or_else(|| {
+        io_error(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no parent directory",
+        ))
+    })?;
+    fs::create_dir_all(file_parent).map_err(io_error)?;
+    let status = output_status(file_absolute, cfg.code);
+    if status != OutputStatus::Unchanged {
+        if cfg.git_ignore {
+            git_rm(&file_absolute).map_err(io_error)?;
+        }
+        fs::write(file_absolute, cfg.code).map_err(io_error)?;
+    }
     if cfg.git_ignore {
+        git_ignore(&file_pathabs).map_err(io_error)?;
+    } else {
         // don't ignore so that files can be added to Git and compiled on docs.rs, because docs.rs
         // does not allow the yang binary to be downloaded
-        git_ignore(&file_pathabs).unwrap_or_else(|_| panic!("Could not ignore {}", file_absolute));
+        git_unignore(&file_pathabs).map_err(io_error)?;
     }
     if cfg.cargo_track {
         // tell cargo to regenerate autogenerated files when they're edited or removed
         println!("cargo:rerun-if-changed={}", cfg.file_path);
     }
+    Ok(status)
 }
@@ -0,0 +1,124 @@
+use super::OutputStatus;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// A single file that code generation would output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlannedFile {
+    /// The path the file would be written to.
+    pub path: String,
+    /// The final generated code for the file.
+    pub code: String,
+    /// The `.gitignore` file that this file would be added to, if any.
+    pub git_ignore: Option<String>,
+    /// Whether or not Cargo would be told to track this file.
+    pub cargo_track: bool,
+    /// Whether or not this file would be tracked as generated code, to be cleaned up once nothing
+    /// generates it anymore.
+    pub autogen: bool,
+    /// What outputting this file would do to the existing one on disk, as of planning time.
+    pub status: OutputStatus,
+}
+
+/// All the files that a single code generation run would output.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CodegenPlan {
+    /// Files in the order that they would be output.
+    pub files: Vec<PlannedFile>,
+}
+
+impl CodegenPlan {
+    /// Add a file to the plan.
+    pub fn add(&mut self, file: PlannedFile) {
+        self.files.push(file);
+    }
+
+    /// Retrieve the planned file at the given path, if there is one.
+    pub fn file(&self, path: &str) -> Option<&PlannedFile> {
+        self.files.iter().find(|f| f.path == path)
+    }
+}
+
+impl Display for CodegenPlan {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "Would generate {} files:", self.files.len())?;
+        for file in &self.files {
+            let status = match file.status {
+                OutputStatus::Created => "create",
+                OutputStatus::Updated => "update",
+                OutputStatus::Unchanged => "unchanged",
+            };
+            write!(
+                f,
+                "  [{}] {} ({} lines)",
+                status,
+                file.path,
+                file.code.lines().count()
+            )?;
+            if let Some(gitignore) = &file.git_ignore {
+                write!(f, ", ignored in {}", gitignore)?;
+            }
+            if file.cargo_track {
+                write!(f, ", tracked by Cargo")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_plan_lookup() {
+        let mut plan = CodegenPlan::default();
+        plan.add(PlannedFile {
+            path: "src/tao/auto_init.rs".to_owned(),
+            code: "// init".to_owned(),
+            git_ignore: None,
+            cargo_track: false,
+            autogen: true,
+            status: OutputStatus::Created,
+        });
+        assert_eq!(
+            plan.file("src/tao/auto_init.rs").map(|f| f.code.as_str()),
+            Some("// init")
+        );
+        assert_eq!(plan.file("src/lib.rs"), None);
+    }
+
+    #[test]
+    fn test_plan_display() {
+        let plan = CodegenPlan {
+            files: vec![
+                PlannedFile {
+                    path: "src/tao/auto_init.rs".to_owned(),
+                    code: "// one\n// two".to_owned(),
+                    git_ignore: Some("/project/src/tao/.gitignore".to_owned()),
+                    cargo_track: true,
+                    autogen: true,
+                    status: OutputStatus::Updated,
+                },
+                PlannedFile {
+                    path: "src/tao/mod.rs".to_owned(),
+                    code: "// one".to_owned(),
+                    git_ignore: None,
+                    cargo_track: false,
+                    autogen: true,
+                    status: OutputStatus::Unchanged,
+                },
+            ],
+        };
+        assert_eq!(
+            plan.to_string(),
+            indoc! {"
+                Would generate 2 files:
+                  [update] src/tao/auto_init.rs (2 lines), ignored in /project/src/tao/.gitignore, tracked by Cargo
+                  [unchanged] src/tao/mod.rs (1 lines)
+            "}
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::codegen::CodegenError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+
+/// Where the ID lock file gets saved, relative to the crate root.
+pub const ID_LOCK: &str = "yang.lock";
+
+/// Comment at the top of every lock file.
+const LOCK_HEADER: &str =
+    "# This file is automatically generated by Yang.\n# It is not intended for manual editing.\n";
+
+/// The ID that a single concept was previously assigned.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedId {
+    /// Internal name of the concept.
+    pub name: String,
+    /// The ID, relative to the start of this crate's IDs.
+    pub id: usize,
+}
+
+/// IDs assigned during previous builds, so that concept IDs stay the same from one build to the
+/// next even as concepts get added or removed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdLock {
+    /// IDs that used to belong to concepts which no longer exist. These are left as gaps instead
+    /// of being handed out again.
+    #[serde(default)]
+    pub retired: Vec<usize>,
+    /// IDs that currently belong to concepts, in order of ID.
+    #[serde(default, rename = "concept")]
+    pub concepts: Vec<LockedId>,
+}
+
+impl IdLock {
+    /// Serialize this lock as TOML.
+    pub fn to_toml(&self) -> Result<String, CodegenError> {
+        toml::to_string(self)
+            .map(|content| format!("{}{}", LOCK_HEADER, content))
+            .map_err(|e| CodegenError::InvalidIdLock {
+                reason: e.to_string(),
+            })
+    }
+
+    /// Read a lock back in from TOML.
+    pub fn from_toml(content: &str) -> Result<Self, CodegenError> {
+        toml::from_str(content).map_err(|e| CodegenError::InvalidIdLock {
+            reason: e.to_string(),
+        })
+    }
+
+    /// Read the lock at the given path. A missing lock file just means that no IDs have been
+    /// assigned yet.
+    pub fn read(path: &str) -> Result<Self, CodegenError> {
+        match fs::read_to_string(path) {
+            Ok(content) => Self::from_toml(&content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(CodegenError::io(path, e)),
+        }
+    }
+
+    /// The ID previously assigned to the concept with this name, if any.
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.concepts.iter().find(|c| c.name == name).map(|c| c.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_to_toml() {
+        let lock = IdLock {
+            retired: vec![2],
+            concepts: vec![
+                LockedId {
+                    name: "first".to_owned(),
+                    id: 1,
+                },
+                LockedId {
+                    name: "third".to_owned(),
+                    id: 3,
+                },
+            ],
+        };
+        assert_eq!(
+            lock.to_toml().unwrap(),
+            indoc! {r#"
+                # This file is automatically generated by Yang.
+                # It is not intended for manual editing.
+                retired = [2]
+
+                [[concept]]
+                name = "first"
+                id = 1
+
+                [[concept]]
+                name = "third"
+                id = 3
+            "#}
+        );
+        assert_eq!(IdLock::from_toml(&lock.to_toml().unwrap()).unwrap(), lock);
+        assert_eq!(lock.id("third"), Some(3));
+        assert_eq!(lock.id("second"), None);
+    }
+
+    #[test]
+    fn test_read_missing() {
+        assert_eq!(
+            IdLock::read("/nonexistent/yang.lock").unwrap(),
+            IdLock::default()
+        );
+    }
+}
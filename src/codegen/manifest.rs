@@ -0,0 +1,182 @@
+use crate::codegen::CodegenError;
+use serde::{Deserialize, Serialize};
+
+/// Where the build manifest gets saved, relative to the crate root.
+pub const BUILD_MANIFEST: &str = "build_info.toml";
+
+/// Build information for a single generated concept.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConceptBuildInfo {
+    /// Internal name of the concept.
+    pub name: String,
+    /// Internal name of the concept's primary parent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// Full import path of the generated struct, starting with the crate name.
+    pub import_path: String,
+    /// Name of the generated struct.
+    pub implementation_name: String,
+    /// Whether or not the concept lives inside its own module.
+    #[serde(default)]
+    pub own_module: bool,
+    /// Whether or not the concept's accessors live in a trait shared with its descendants.
+    #[serde(default)]
+    pub accessor_trait: bool,
+    /// Internal names of the variants that the concept can take on, if it is enumeration data.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<String>,
+    /// The accessor generated on the value type, if the concept is an attribute that has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inverse: Option<InverseBuildInfo>,
+}
+
+/// Build information for the inverse accessor of a single attribute.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InverseBuildInfo {
+    /// Name of the inverse accessor.
+    pub name: String,
+    /// Documentation for the inverse accessor.
+    pub doc: String,
+}
+
+/// Build information for a single generated module.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleBuildInfo {
+    /// Internal name of the concept that the module is named after.
+    pub concept: String,
+    /// Name of the generated module.
+    pub implementation_name: String,
+    /// Documentation for the module.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    /// Full paths of symbols that the module re-exports.
+    #[serde(default)]
+    pub re_exports: Vec<String>,
+}
+
+/// Build information for a single generated named individual.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndividualBuildInfo {
+    /// Internal name of the individual.
+    pub name: String,
+    /// Internal name of the archetype that the individual is a direct instance of.
+    pub archetype: String,
+    /// ID that the individual was initialized with.
+    pub id: usize,
+}
+
+/// Build information for everything generated as part of a single crate, so that downstream
+/// crates don't have to describe it all over again.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildManifest {
+    /// Name of the crate that everything here was built as a part of.
+    pub crate_name: String,
+    /// Paths of every file generated as part of the build. Unlike the autogen tracker, this gets
+    /// committed in release mode, so files that stop being generated can still be found on a
+    /// fresh checkout.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub generated_files: Vec<String>,
+    /// Build information for generated concepts.
+    #[serde(default, rename = "concept")]
+    pub concepts: Vec<ConceptBuildInfo>,
+    /// Build information for generated modules.
+    #[serde(default, rename = "module")]
+    pub modules: Vec<ModuleBuildInfo>,
+    /// Build information for generated named individuals.
+    #[serde(default, rename = "individual")]
+    pub individuals: Vec<IndividualBuildInfo>,
+}
+
+impl BuildManifest {
+    /// Serialize this manifest as TOML.
+    pub fn to_toml(&self) -> Result<String, CodegenError> {
+        toml::to_string(self).map_err(|e| CodegenError::InvalidManifest {
+            reason: e.to_string(),
+        })
+    }
+
+    /// Read a manifest back in from TOML.
+    pub fn from_toml(content: &str) -> Result<Self, CodegenError> {
+        toml::from_str(content).map_err(|e| CodegenError::InvalidManifest {
+            reason: e.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn example_manifest() -> BuildManifest {
+        BuildManifest {
+            crate_name: "zamm_yang".to_owned(),
+            generated_files: vec!["src/tao/perspective/build_info_form.rs".to_owned()],
+            concepts: vec![ConceptBuildInfo {
+                name: "build-info".to_owned(),
+                parent: Some("perspective".to_owned()),
+                import_path: "zamm_yang::tao::perspective::BuildInfo".to_owned(),
+                implementation_name: "BuildInfo".to_owned(),
+                own_module: false,
+                accessor_trait: false,
+                variants: vec![],
+                inverse: None,
+            }],
+            modules: vec![ModuleBuildInfo {
+                concept: "perspective".to_owned(),
+                implementation_name: "perspective".to_owned(),
+                doc: Some("Perspectives on the world.".to_owned()),
+                re_exports: vec!["zamm_yang::tao::perspective::BuildInfoExtension".to_owned()],
+            }],
+            individuals: vec![IndividualBuildInfo {
+                name: "zamm_yang".to_owned(),
+                archetype: "crate".to_owned(),
+                id: 42,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_to_toml() {
+        assert_eq!(
+            example_manifest().to_toml().unwrap(),
+            indoc! {r#"
+                crate_name = "zamm_yang"
+                generated_files = ["src/tao/perspective/build_info_form.rs"]
+
+                [[concept]]
+                name = "build-info"
+                parent = "perspective"
+                import_path = "zamm_yang::tao::perspective::BuildInfo"
+                implementation_name = "BuildInfo"
+                own_module = false
+                accessor_trait = false
+
+                [[module]]
+                concept = "perspective"
+                implementation_name = "perspective"
+                doc = "Perspectives on the world."
+                re_exports = ["zamm_yang::tao::perspective::BuildInfoExtension"]
+
+                [[individual]]
+                name = "zamm_yang"
+                archetype = "crate"
+                id = 42
+            "#}
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let manifest = example_manifest();
+        assert_eq!(
+            BuildManifest::from_toml(&manifest.to_toml().unwrap()).unwrap(),
+            manifest
+        );
+    }
+
+    #[test]
+    fn test_invalid_toml() {
+        assert!(BuildManifest::from_toml("[[concept]]\nname = 3").is_err());
+    }
+}
@@ -2,23 +2,42 @@
 mod configs;
 /// Format documentation as rustdoc.
 mod docstring;
+/// Errors that can occur during code generation.
+mod error;
 /// Actual changes to the filesystem.
 pub mod filesystem;
+/// Concept IDs saved for future builds.
+pub mod id_lock;
+/// Build information saved for downstream crates.
+pub mod manifest;
 /// High-level planning for what the final generation configs will look like. All logic that touch
 /// Yin concepts in the codegen module should live inside of this sub-module.
 pub mod planning;
 /// Finalized code generation.
 mod postprocessing;
+/// Scratch space on disk for tests that touch the filesystem.
+#[cfg(test)]
+mod scratch;
+/// Self-contained code generation runs, and the state that goes along with each one.
+pub mod session;
+/// Declarative concept specifications that can be loaded without writing Rust code.
+pub mod spec;
 /// Codegen templates.
 pub mod template;
 /// Track autogenerated files.
 pub mod track_autogen;
+/// Check that generated files on disk are still exactly what code generation would output.
+pub mod verify;
 
 pub use configs::{CodegenConfig, StructConfig};
-use filesystem::{output_code_verbatim, OutputConfig};
+pub use error::CodegenError;
+use filesystem::{output_code_verbatim, plan_output, OutputConfig, PlannedFile};
 pub use postprocessing::mark_autogen::{add_indent, count_indent};
 pub use postprocessing::mark_fmt::add_fmt_skips;
 use postprocessing::post_process_generation;
+use postprocessing::user_regions::{has_user_code, merge_user_regions};
+use std::fs;
+use std::io;
 
 /// How many characters per line each autogenerated document should have.
 const CODE_WIDTH: usize = 80;
@@ -26,13 +45,68 @@ const CODE_WIDTH: usize = 80;
 /// Default number of spaces for one level of indent.
 const INDENT_SIZE: usize = 4;
 
+/// Fill the hand-written regions of the generated code with whatever is in those regions in the
+/// existing file at the destination, if there is one.
+fn keep_user_regions(code: &str, destination: &str) -> Result<String, CodegenError> {
+    let existing = match fs::read_to_string(destination) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(code.to_owned()),
+        Err(e) => return Err(CodegenError::io(destination, e)),
+    };
+    let merged =
+        merge_user_regions(code, &existing).map_err(|reason| CodegenError::InvalidUserRegion {
+            path: destination.to_owned(),
+            reason,
+        })?;
+    if !merged.orphaned.is_empty() {
+        // refuse to overwrite hand-written code that would otherwise be silently dropped
+        return Err(CodegenError::OrphanedUserRegions {
+            path: destination.to_owned(),
+            anchors: merged.orphaned,
+        });
+    }
+    Ok(merged.code)
+}
+
 /// Perform post-processing on generated code given the options specified in `codegen_cfg`, and
-/// then output it to the given destination file path.
-pub fn output_code(generated_code: &str, destination: &str, codegen_cfg: &CodegenConfig) {
-    output_code_verbatim(&OutputConfig {
-        code: &post_process_generation(generated_code, codegen_cfg),
+/// then output it to the given destination file path. Nothing is written during a dry run.
+///
+/// Outside of release mode, the file gets ignored by Git unless it has hand-written code in any
+/// of its user regions.
+///
+/// Returns a description of the file that was (or would have been) output.
+pub fn output_code(
+    generated_code: &str,
+    destination: &str,
+    codegen_cfg: &CodegenConfig,
+) -> Result<PlannedFile, CodegenError> {
+    let code = keep_user_regions(
+        &post_process_generation(generated_code, codegen_cfg),
+        destination,
+    )?;
+    let output_cfg = OutputConfig {
+        code: &code,
         file_path: destination,
-        git_ignore: !codegen_cfg.release,
+        // hand-written code has to be committed, or else it only ever exists on one machine
+        git_ignore: !codegen_cfg.release && !has_user_code(&code),
         cargo_track: codegen_cfg.track_autogen,
-    });
+        autogen: true,
+    };
+    output_planned(&output_cfg, codegen_cfg)
+}
+
+/// Output a file exactly as specified by `output_cfg`, without any post-processing. Nothing is
+/// written during a dry run.
+///
+/// Returns a description of the file that was (or would have been) output.
+pub fn output_planned(
+    output_cfg: &OutputConfig,
+    codegen_cfg: &CodegenConfig,
+) -> Result<PlannedFile, CodegenError> {
+    // plan before writing, so that the plan reflects what the write is about to change
+    let planned = plan_output(output_cfg)?;
+    if !codegen_cfg.dry_run {
+        output_code_verbatim(output_cfg)?;
+    }
+    Ok(planned)
 }
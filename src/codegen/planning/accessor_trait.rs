@@ -0,0 +1,106 @@
+use super::concept_to_struct;
+use super::imports::in_own_submodule;
+use crate::codegen::{CodegenConfig, CodegenError, StructConfig};
+use crate::tao::perspective::KnowledgeGraphNode;
+use std::convert::TryFrom;
+use zamm_yin::node_wrappers::{BaseNodeTrait, CommonNodeTrait, FinalNode};
+use zamm_yin::tao::archetype::{Archetype, ArchetypeFormTrait, ArchetypeTrait};
+use zamm_yin::tao::relation::flag::Flag;
+
+/// Internal name of the flag that marks imported archetypes as having had their accessors put
+/// into a trait when they were built.
+const ACCESSOR_TRAIT_FLAG: &str = "accessor-trait";
+
+/// Retrieve the flag that marks archetypes as having an accessor trait, creating it if it doesn't
+/// exist yet.
+fn accessor_trait_flag() -> Archetype {
+    Archetype::try_from(ACCESSOR_TRAIT_FLAG).unwrap_or_else(|_| {
+        let mut flag = Flag::archetype().individuate_as_archetype();
+        flag.set_internal_name(ACCESSOR_TRAIT_FLAG);
+        flag
+    })
+}
+
+/// Mark an imported archetype as having had its accessors put into a trait when it was built.
+pub fn mark_accessor_trait(target: &Archetype) {
+    let mut node = FinalNode::from(target.id());
+    node.add_flag(accessor_trait_flag().id());
+}
+
+/// Whether or not accessors get generated for the attributes and flags that this archetype
+/// introduces.
+///
+/// Archetypes inside their own submodule normally get their accessors written by hand, as part of
+/// an extension trait. Accessor traits are meant to replace those.
+pub fn generates_accessors(target: &Archetype, codegen_cfg: &CodegenConfig) -> bool {
+    !in_own_submodule(target) || codegen_cfg.accessor_traits
+}
+
+/// Whether or not the archetype has an accessor trait, either because one is being generated for
+/// it right now or because one was generated when it was imported.
+pub fn has_accessor_trait(target: &Archetype, codegen_cfg: &CodegenConfig) -> bool {
+    if KnowledgeGraphNode::from(target.id()).is_newly_defined() {
+        codegen_cfg.accessor_traits
+            && (!target.added_flags().is_empty() || !target.added_attributes().is_empty())
+    } else {
+        // don't let the flag get inherited, descendants don't have their own traits
+        FinalNode::from(target.id())
+            .base_wrapper()
+            .has_flag(accessor_trait_flag().id())
+    }
+}
+
+/// The accessor trait for an archetype. It lives right next to the archetype's own struct.
+pub fn accessor_trait_struct(
+    target: &Archetype,
+    codegen_cfg: &CodegenConfig,
+) -> Result<StructConfig, CodegenError> {
+    let owner = concept_to_struct(target, codegen_cfg.yin)?;
+    Ok(StructConfig {
+        name: format!("{}Trait", owner.name),
+        import: format!("{}Trait", owner.import),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tao::initialize_kb;
+    use zamm_yin::tao::form::Form;
+
+    #[test]
+    fn test_newly_defined_trait() {
+        initialize_kb();
+        let mut target = Form::archetype().individuate_as_archetype();
+        target.set_internal_name("traitful");
+        KnowledgeGraphNode::from(target.id()).mark_newly_defined();
+        let cfg = CodegenConfig {
+            accessor_traits: true,
+            ..CodegenConfig::default()
+        };
+        // nothing to access yet
+        assert!(!has_accessor_trait(&target, &cfg));
+
+        target.add_flag(&Flag::archetype().individuate_as_archetype());
+        assert!(has_accessor_trait(&target, &cfg));
+        assert!(!has_accessor_trait(&target, &CodegenConfig::default()));
+        assert_eq!(
+            accessor_trait_struct(&target, &cfg).unwrap(),
+            StructConfig::new("crate::tao::form::TraitfulTrait".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_imported_trait() {
+        initialize_kb();
+        let mut target = Form::archetype().individuate_as_archetype();
+        target.set_internal_name("imported-traitful");
+        KnowledgeGraphNode::from(target.id()).mark_imported();
+        let child = target.individuate_as_archetype();
+        assert!(!has_accessor_trait(&target, &CodegenConfig::default()));
+
+        mark_accessor_trait(&target);
+        assert!(has_accessor_trait(&target, &CodegenConfig::default()));
+        assert!(!has_accessor_trait(&child, &CodegenConfig::default()));
+    }
+}
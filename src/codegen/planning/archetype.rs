@@ -1,23 +1,41 @@
+use super::accessor_trait::{accessor_trait_struct, generates_accessors, has_accessor_trait};
+use super::builder::builder_config;
+use super::cardinality::{max_values, validate_config};
 use super::concept_to_struct;
-use super::imports::{in_own_submodule, root_node_or_equivalent};
+use super::enum_data::{enum_struct, enum_variants, is_enum, variant_value};
+use super::id_offset::id_offset;
+use super::imports::{
+    current_crate_name, describe, internal_name, request_embodiment, request_target,
+    root_node_or_equivalent,
+};
+use super::individual::individual_accessors;
+use super::inverse::inverse_configs;
 use crate::codegen::docstring::into_docstring;
-use crate::codegen::template::basic::{FileFragment, ImplementationFragment};
+use crate::codegen::postprocessing::user_regions::user_region;
+use crate::codegen::template::basic::{AtomicFragment, FileFragment, ImplementationFragment};
+use crate::codegen::template::concept::accessor_trait::{
+    accessor_trait_fragment, accessor_trait_impl, AccessorTraitConfig,
+};
 use crate::codegen::template::concept::archetype::{add_archetype_fragment, ArchetypeFormatConfig};
 use crate::codegen::template::concept::attribute::{add_attr_fragments, AttributeFormatConfig};
 use crate::codegen::template::concept::attribute_property::{
-    add_attr_to_impl, AttributePropertyConfig,
+    add_attr_to_impl, add_attr_to_trait, AttributePropertyConfig,
 };
+use crate::codegen::template::concept::builder::add_builder_fragment;
 use crate::codegen::template::concept::data::{add_data_fragments, DataFormatConfig};
-use crate::codegen::template::concept::flag::{add_flag_to_impl, FlagConfig};
+use crate::codegen::template::concept::flag::{add_flag_to_impl, add_flag_to_trait, FlagConfig};
 use crate::codegen::template::concept::form::{add_form_fragment, FormFormatConfig};
+use crate::codegen::template::concept::individual::individual_accessors_fragment;
+use crate::codegen::template::concept::inverse::add_inverse_to_impl;
 use crate::codegen::template::concept::tao::{tao_file_fragment, TaoConfig};
+use crate::codegen::template::concept::validate::add_validate_fragment;
 use crate::codegen::CODE_WIDTH;
-use crate::codegen::{CodegenConfig, StructConfig};
+use crate::codegen::{CodegenConfig, CodegenError, StructConfig};
 use crate::tao::action::Implement;
 use crate::tao::archetype::rust_item_archetype::DataArchetype;
 use crate::tao::archetype::CreateImplementation;
 use crate::tao::form::rust_item::data::Data;
-use crate::tao::form::rust_item::{Concept, Crate, CrateExtension};
+use crate::tao::form::rust_item::Concept;
 use crate::tao::perspective::KnowledgeGraphNode;
 use heck::{KebabCase, SnakeCase};
 use std::cell::RefCell;
@@ -30,7 +48,15 @@ use zamm_yin::tao::archetype::{
 use zamm_yin::tao::form::{Form, FormTrait};
 use zamm_yin::tao::relation::attribute::{Attribute, MetaForm};
 
-fn or_form_default(archetype: Archetype) -> Archetype {
+/// Anchor of the hand-written region for extra items in a generated concept file.
+const USER_ITEMS_ANCHOR: &str = "items";
+
+/// Anchor of the hand-written region for extra tests in a generated concept file.
+const USER_TESTS_ANCHOR: &str = "tests";
+
+/// The archetype itself, or Form if it's the root node. Attributes whose owners or values are
+/// left unspecified end up pointing to the root node.
+pub fn or_form_default(archetype: Archetype) -> Archetype {
     if root_node_or_equivalent(&archetype) {
         Archetype::try_from(Form::TYPE_NAME).unwrap() // allow user to override Form
     } else {
@@ -38,23 +64,26 @@ fn or_form_default(archetype: Archetype) -> Archetype {
     }
 }
 
-fn activate_archetype(target: &Archetype) -> bool {
+/// Whether or not code generation treats the target as an archetype.
+pub fn activate_archetype(target: &Archetype) -> bool {
     target.has_ancestor(Archetype::archetype())
         || KnowledgeGraphNode::from(target.id()).is_archetype_analogue()
 }
 
-fn activate_attribute(target: &Archetype) -> bool {
+/// Whether or not code generation treats the target as an attribute.
+pub fn activate_attribute(target: &Archetype) -> bool {
     target == &Attribute::archetype().into()
         || target.has_ancestor(Attribute::archetype().into())
         || KnowledgeGraphNode::from(target.id()).is_attribute_analogue()
 }
 
-fn activate_data(target: &Archetype) -> bool {
+/// Whether or not code generation treats the target as data.
+pub fn activate_data(target: &Archetype) -> bool {
     target.has_ancestor(Data::archetype().into())
         || KnowledgeGraphNode::from(target.id()).is_data_analogue()
 }
 
-fn form_for(target: &Archetype, codegen_cfg: &CodegenConfig) -> StructConfig {
+fn form_for(target: &Archetype, codegen_cfg: &CodegenConfig) -> Result<StructConfig, CodegenError> {
     if KnowledgeGraphNode::from(target.id()).is_root_analogue() {
         // technically we should allow the user to customize this as well
         concept_to_struct(&Form::archetype(), codegen_cfg.yin)
@@ -63,15 +92,25 @@ fn form_for(target: &Archetype, codegen_cfg: &CodegenConfig) -> StructConfig {
     }
 }
 
+/// The Rust expression for a concept ID inside a generated concept file, where the max ID of the
+/// crate being built on is imported as `YIN_MAX_ID`.
+fn id_expression(id: usize, codegen_cfg: &CodegenConfig) -> String {
+    if codegen_cfg.yin {
+        format!("{}", id)
+    } else {
+        format!("YIN_MAX_ID + {}", id)
+    }
+}
+
 fn generic_config(
     request: &Implement,
     target: &Archetype,
     parent: &Archetype,
     codegen_cfg: &CodegenConfig,
-) -> TaoConfig {
-    let this = concept_to_struct(&target, codegen_cfg.yin);
+) -> Result<TaoConfig, CodegenError> {
+    let this = concept_to_struct(&target, codegen_cfg.yin)?;
     let internal_name = this.name.to_kebab_case();
-    let form = form_for(target, codegen_cfg);
+    let form = form_for(target, codegen_cfg)?;
 
     let doc = match &request.documentation() {
         Some(d) => format!("\n{}", into_docstring(&d, CODE_WIDTH)),
@@ -79,32 +118,25 @@ fn generic_config(
     };
 
     // allow a default, especially for tests
-    let concept = Concept::from(request.embodiment().unwrap().id());
+    let concept = Concept::from(request_embodiment(request)?.id());
     let initial_id = concept.concept_id().unwrap_or_else(|| Rc::new(0));
-    let id = if codegen_cfg.yin {
-        format!("{}", initial_id)
-    } else {
-        format!("YIN_MAX_ID + {}", initial_id)
-    };
+    let id = id_expression(*initial_id, codegen_cfg);
 
-    let imports = if codegen_cfg.yin {
-        None
-    } else {
-        Some("zamm_yin::tao::YIN_MAX_ID".to_owned())
-    };
+    // IDs continue on from those of whichever crate this one builds on
+    let imports = id_offset(codegen_cfg)?;
 
-    let parent_struct = concept_to_struct(parent, codegen_cfg.yin);
+    let parent_struct = concept_to_struct(parent, codegen_cfg.yin)?;
 
-    let all_attribute_structs: Vec<StructConfig> = target
+    let all_attribute_structs = target
         .attributes()
         .into_iter()
         .map(|a| concept_to_struct(&a.into(), codegen_cfg.yin))
-        .collect();
-    let introduced_attribute_structs: Vec<StructConfig> = target
+        .collect::<Result<Vec<StructConfig>, CodegenError>>()?;
+    let introduced_attribute_structs = target
         .added_attributes()
         .into_iter()
         .map(|a| concept_to_struct(&a.into(), codegen_cfg.yin))
-        .collect();
+        .collect::<Result<Vec<StructConfig>, CodegenError>>()?;
     let all_attributes = all_attribute_structs
         .iter()
         .map(|s| format!("{}::archetype()", s.name))
@@ -122,9 +154,9 @@ fn generic_config(
         .map(|s| s.import.clone())
         .collect();
 
-    let archetype = concept_to_struct(&target.meta_archetype(), codegen_cfg.yin);
+    let archetype = concept_to_struct(&target.meta_archetype(), codegen_cfg.yin)?;
 
-    TaoConfig {
+    Ok(TaoConfig {
         imports,
         this,
         internal_name,
@@ -138,7 +170,7 @@ fn generic_config(
         archetype,
         doc,
         id,
-    }
+    })
 }
 
 fn into_archetype_fn(archetype: &Archetype) -> String {
@@ -153,9 +185,9 @@ fn form_config(
     base_cfg: &TaoConfig,
     target: &mut Archetype,
     codegen_cfg: &CodegenConfig,
-) -> FormFormatConfig {
+) -> Result<FormFormatConfig, CodegenError> {
     let meta_archetype = if target.has_specific_meta() {
-        Some(concept_to_struct(&target.specific_meta(), codegen_cfg.yin))
+        Some(concept_to_struct(&target.specific_meta(), codegen_cfg.yin)?)
     } else {
         None
     };
@@ -173,34 +205,34 @@ fn form_config(
     let ancestors = initial_ancestors
         .into_iter()
         .map(|a| concept_to_struct(&a, codegen_cfg.yin))
-        .collect();
+        .collect::<Result<Vec<StructConfig>, CodegenError>>()?;
 
-    FormFormatConfig {
+    Ok(FormFormatConfig {
         tao_cfg: base_cfg.clone(),
         meta_archetype,
         ancestors,
-    }
+    })
 }
 
 fn attribute_config(
     base_cfg: &TaoConfig,
     target: &Archetype,
     codegen_cfg: &CodegenConfig,
-) -> AttributeFormatConfig {
+) -> Result<AttributeFormatConfig, CodegenError> {
     let target_attr = AttributeArchetype::from(target.id());
     let owner_type_concept = target_attr.owner_archetype();
     let value_type_concept = target_attr.value_archetype();
 
-    let owner_type = concept_to_struct(&owner_type_concept, codegen_cfg.yin);
-    let value_type = concept_to_struct(&value_type_concept, codegen_cfg.yin);
+    let owner_type = concept_to_struct(&owner_type_concept, codegen_cfg.yin)?;
+    let value_type = concept_to_struct(&value_type_concept, codegen_cfg.yin)?;
 
-    let owner_form = concept_to_struct(&or_form_default(owner_type_concept), codegen_cfg.yin);
-    let value_form = concept_to_struct(&or_form_default(value_type_concept), codegen_cfg.yin);
+    let owner_form = concept_to_struct(&or_form_default(owner_type_concept), codegen_cfg.yin)?;
+    let value_form = concept_to_struct(&or_form_default(value_type_concept), codegen_cfg.yin)?;
 
     let owner_into_archetype = into_archetype_fn(&owner_type_concept);
     let value_into_archetype = into_archetype_fn(&value_type_concept);
 
-    AttributeFormatConfig {
+    Ok(AttributeFormatConfig {
         tao_cfg: base_cfg.clone(),
         owner_type,
         owner_form,
@@ -208,39 +240,82 @@ fn attribute_config(
         value_form,
         owner_into_archetype,
         value_into_archetype,
-    }
+    })
 }
 
 fn archetype_config(
     base_cfg: &TaoConfig,
     target: &Archetype,
     codegen_cfg: &CodegenConfig,
-) -> ArchetypeFormatConfig {
+) -> Result<ArchetypeFormatConfig, CodegenError> {
     // todo: use Yin's ArchetypeFromTrait::infra_archetype function once that's available
-    let infra = Archetype::from(
-        target
-            .incoming_nodes(MetaForm::TYPE_ID)
-            .last()
-            .unwrap()
-            .id(),
-    );
-    ArchetypeFormatConfig {
+    let infra = match target.incoming_nodes(MetaForm::TYPE_ID).last() {
+        Some(infra) => Archetype::from(infra.id()),
+        None => {
+            return Err(CodegenError::MalformedConcept {
+                concept: describe(target),
+                reason: "it is not the meta archetype of anything".to_owned(),
+            })
+        }
+    };
+    Ok(ArchetypeFormatConfig {
         tao_cfg: base_cfg.clone(),
-        infra_archetype: form_for(&infra, codegen_cfg),
-    }
+        infra_archetype: form_for(&infra, codegen_cfg)?,
+    })
 }
 
-fn data_config(base_cfg: &TaoConfig, target: &DataArchetype) -> DataFormatConfig {
-    let rust_primitive_boxed_name = target.rust_primitive().unwrap();
+fn data_config(
+    base_cfg: &TaoConfig,
+    target: &DataArchetype,
+    codegen_cfg: &CodegenConfig,
+) -> Result<DataFormatConfig, CodegenError> {
+    let target_archetype = Archetype::from(target.id());
+    if is_enum(&target_archetype) {
+        let enum_value = enum_struct(&target_archetype, codegen_cfg)?;
+        let enum_variants = enum_variants(&target_archetype)?;
+        let default_variant = enum_variants
+            .first()
+            .ok_or_else(|| no_variants(&target_archetype))?;
+        return Ok(DataFormatConfig {
+            tao_cfg: base_cfg.clone(),
+            rust_primitive_unboxed_name: Rc::from(enum_value.name.as_str()),
+            rust_primitive_boxed_name: Rc::from(enum_value.name.as_str()),
+            default_value: variant_value(&enum_value, default_variant),
+            enum_variants,
+        });
+    }
+
+    let describe_target = || describe(&target_archetype);
+    let rust_primitive_boxed_name =
+        target
+            .rust_primitive()
+            .ok_or_else(|| CodegenError::MissingRustPrimitive {
+                concept: describe_target(),
+            })?;
     let rust_primitive_unboxed_name = match target.unboxed_representation() {
         Some(custom_name) => custom_name,
         None => rust_primitive_boxed_name.clone(),
     };
-    DataFormatConfig {
+    let default_value =
+        target
+            .default_value()
+            .ok_or_else(|| CodegenError::MissingDefaultValue {
+                concept: describe_target(),
+            })?;
+    Ok(DataFormatConfig {
         tao_cfg: base_cfg.clone(),
         rust_primitive_unboxed_name,
         rust_primitive_boxed_name,
-        default_value: target.default_value().unwrap(),
+        default_value,
+        enum_variants: vec![],
+    })
+}
+
+/// Error for an enum that turns out to have no variants after all.
+fn no_variants(target: &Archetype) -> CodegenError {
+    CodegenError::MalformedConcept {
+        concept: describe(target),
+        reason: "it has no enum variants".to_owned(),
     }
 }
 
@@ -249,16 +324,20 @@ fn flag_config(
     implement: &Implement,
     target: &Archetype,
     flag: &Archetype,
-) -> FlagConfig {
-    let doc = implement.dual_purpose_documentation().unwrap();
-    FlagConfig {
+) -> Result<FlagConfig, CodegenError> {
+    let doc = implement.dual_purpose_documentation().ok_or_else(|| {
+        CodegenError::MissingDocumentation {
+            concept: describe(flag),
+        }
+    })?;
+    Ok(FlagConfig {
         public: true,
-        property_name: Rc::from(flag.internal_name().unwrap().to_snake_case()),
+        property_name: Rc::from(internal_name(flag)?.to_snake_case()),
         doc,
-        flag: concept_to_struct(flag, codegen_cfg.yin),
-        owner_type: concept_to_struct(target, codegen_cfg.yin),
+        flag: concept_to_struct(flag, codegen_cfg.yin)?,
+        owner_type: concept_to_struct(target, codegen_cfg.yin)?,
         hereditary: !AttributeArchetype::from(flag.id()).is_nonhereditary_attr(),
-    }
+    })
 }
 
 fn attr_config(
@@ -266,150 +345,304 @@ fn attr_config(
     attr_implement: &Implement,
     target: &Archetype,
     attr: &AttributeArchetype,
-) -> AttributePropertyConfig {
+) -> Result<AttributePropertyConfig, CodegenError> {
+    // this must not be the target's implement, but rather the attribute's implement
+    let implemented = request_target(attr_implement)?;
+    if implemented.id() != attr.id() {
+        return Err(CodegenError::MalformedConcept {
+            concept: describe(&(*attr).into()),
+            reason: format!(
+                "its accessors are documented by the implementation of {}",
+                describe(&implemented)
+            ),
+        });
+    }
+    let doc = attr_implement.dual_purpose_documentation().ok_or_else(|| {
+        CodegenError::MissingDocumentation {
+            concept: describe(&(*attr).into()),
+        }
+    })?;
+    attr_property_config(codegen_cfg, target, attr, doc)
+}
+
+/// Config for the attribute as a property of the target, with the given documentation for its
+/// accessors.
+pub fn attr_property_config(
+    codegen_cfg: &CodegenConfig,
+    target: &Archetype,
+    attr: &AttributeArchetype,
+    doc: Rc<str>,
+) -> Result<AttributePropertyConfig, CodegenError> {
     let value_type = or_form_default(attr.value_archetype());
     let value_as_data = DataArchetype::from(value_type.id());
-    let rust_primitive = value_as_data.rust_primitive();
-    if activate_data(&value_type) {
-        assert!(
-            rust_primitive.is_some(),
-            "Data type {:?} has no defined Rust primitive.",
-            value_type
-        );
+    let mut rust_primitive = value_as_data.rust_primitive();
+    let mut rust_primitive_import = None;
+    let mut primitive_test_value = value_as_data.default_value();
+    let mut dummy_test_value = value_as_data.dummy_value();
+    if is_enum(&value_type) {
+        // enums are generated, so they get imported like any other generated struct
+        let enum_value = enum_struct(&value_type, codegen_cfg)?;
+        let variants = enum_variants(&value_type)?;
+        let (first, last) = match (variants.first(), variants.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(no_variants(&value_type)),
+        };
+        rust_primitive = Some(Rc::from(enum_value.name.as_str()));
+        primitive_test_value = Some(variant_value(&enum_value, first));
+        dummy_test_value = Some(variant_value(&enum_value, last));
+        rust_primitive_import = Some(enum_value.import);
+    } else if activate_data(&value_type) && rust_primitive.is_none() {
+        return Err(CodegenError::MissingRustPrimitive {
+            concept: describe(&value_type),
+        });
     }
     let rust_primitive_unboxed = match value_as_data.unboxed_representation() {
         Some(unboxed) => Some(unboxed),
         None => rust_primitive.clone(),
     };
-    // this must not be the target's implement, but rather the attribute's implement
-    assert_eq!(attr_implement.target().unwrap(), Form::from(attr.id()));
-    let doc = attr_implement
-        .dual_purpose_documentation()
-        .unwrap_or_else(|| {
-            panic!(
-                "No dual documentation for implement {:?} on attribute {:?}",
-                attr_implement, attr
-            )
-        });
-
-    AttributePropertyConfig {
+    let attr_archetype: Archetype = (*attr).into();
+    Ok(AttributePropertyConfig {
         public: true,
-        property_name: Rc::from(attr.internal_name().unwrap().to_snake_case()),
+        property_name: Rc::from(internal_name(&attr_archetype)?.to_snake_case()),
         doc,
-        attr: concept_to_struct(&(*attr).into(), codegen_cfg.yin),
-        owner_type: concept_to_struct(target, codegen_cfg.yin),
-        value_type: concept_to_struct(&value_type, codegen_cfg.yin),
+        attr: concept_to_struct(&attr_archetype, codegen_cfg.yin)?,
+        owner_type: concept_to_struct(target, codegen_cfg.yin)?,
+        value_type: concept_to_struct(&value_type, codegen_cfg.yin)?,
         rust_primitive,
         rust_primitive_unboxed,
-        primitive_test_value: value_as_data.default_value(),
-        dummy_test_value: value_as_data.dummy_value(),
+        rust_primitive_import,
+        primitive_test_value,
+        dummy_test_value,
         hereditary: !attr.is_nonhereditary_attr(),
         multi_valued: attr.is_multi_valued_attr(),
-    }
+        max_values: max_values(attr),
+    })
 }
 
-fn primary_parent(target: &Archetype) -> Archetype {
+/// The parent that the archetype's generated code treats as its parent.
+pub fn primary_parent(target: &Archetype) -> Archetype {
     *target.parents().first().unwrap()
 }
 
-fn add_struct_flag_fragments(
+fn flag_configs(
+    target: &Archetype,
+    cfg: &CodegenConfig,
+    public: bool,
+) -> Result<Vec<FlagConfig>, CodegenError> {
+    target
+        .added_flags()
+        .iter()
+        .map(|flag| {
+            let implement = flag.accessor_implementation().ok_or_else(|| {
+                CodegenError::MissingDocumentation {
+                    concept: describe(flag),
+                }
+            })?;
+            Ok(FlagConfig {
+                public,
+                ..flag_config(cfg, &implement, &target, &flag)?
+            })
+        })
+        .collect()
+}
+
+fn attr_configs(
+    target: &Archetype,
+    cfg: &CodegenConfig,
+    public: bool,
+) -> Result<Vec<AttributePropertyConfig>, CodegenError> {
+    target
+        .added_attributes()
+        .iter()
+        .map(|attr| {
+            let implement = attr.accessor_implementation().ok_or_else(|| {
+                CodegenError::MissingDocumentation {
+                    concept: describe(&(*attr).into()),
+                }
+            })?;
+            Ok(AttributePropertyConfig {
+                public,
+                ..attr_config(cfg, &implement, &target, &attr)?
+            })
+        })
+        .collect()
+}
+
+/// Put accessors for the target's own attributes and flags into an inherent impl on its struct.
+fn add_struct_accessors(
     target: &Archetype,
     cfg: &CodegenConfig,
-    implementation: &mut ImplementationFragment,
     file: &mut FileFragment,
-) {
-    for flag in target.added_flags() {
-        add_flag_to_impl(
-            &flag_config(
-                cfg,
-                &flag.accessor_implementation().unwrap(),
-                &target,
-                &flag,
-            ),
-            implementation,
-            file,
-        );
+) -> Result<(), CodegenError> {
+    let mut implementation =
+        ImplementationFragment::new_struct_impl(concept_to_struct(&target, cfg.yin)?);
+    for flag_cfg in flag_configs(target, cfg, true)? {
+        add_flag_to_impl(&flag_cfg, &mut implementation, file);
+    }
+    for attr_cfg in attr_configs(target, cfg, true)? {
+        add_attr_to_impl(&attr_cfg, &mut implementation, file);
     }
+    if !implementation.content.borrow().appendages.is_empty() {
+        file.append(Rc::new(RefCell::new(implementation)));
+    }
+    Ok(())
 }
 
-fn add_struct_attr_fragments(
+/// Put accessors for the target's own attributes and flags into a trait of their own.
+fn add_trait_accessors(
     target: &Archetype,
     cfg: &CodegenConfig,
-    implementation: &mut ImplementationFragment,
     file: &mut FileFragment,
-) {
-    for attr in target.added_attributes() {
-        add_attr_to_impl(
-            &attr_config(
-                cfg,
-                &attr.accessor_implementation().unwrap(),
-                &target,
-                &attr,
-            ),
-            implementation,
-            file,
-        );
+) -> Result<(), CodegenError> {
+    let trait_cfg = AccessorTraitConfig {
+        accessor_trait: accessor_trait_struct(target, cfg)?,
+        owner_type: concept_to_struct(target, cfg.yin)?,
+    };
+    let mut accessor_trait = accessor_trait_fragment(&trait_cfg);
+    for flag_cfg in flag_configs(target, cfg, false)? {
+        add_flag_to_trait(&flag_cfg, &mut accessor_trait, file);
+    }
+    for attr_cfg in attr_configs(target, cfg, false)? {
+        add_attr_to_trait(&attr_cfg, &mut accessor_trait, file);
     }
+    file.append(Rc::new(RefCell::new(accessor_trait)));
+    file.append(Rc::new(RefCell::new(accessor_trait_impl(
+        &trait_cfg.accessor_trait,
+        &trait_cfg.owner_type,
+        true,
+    ))));
+    Ok(())
+}
+
+/// Implement the accessor traits of all the target's ancestors for the target too.
+fn add_inherited_accessor_traits(
+    target: &Archetype,
+    cfg: &CodegenConfig,
+    file: &mut FileFragment,
+) -> Result<(), CodegenError> {
+    let this = concept_to_struct(target, cfg.yin)?;
+    for ancestor in target.ancestry() {
+        if has_accessor_trait(&ancestor, cfg) {
+            file.append(Rc::new(RefCell::new(accessor_trait_impl(
+                &accessor_trait_struct(&ancestor, cfg)?,
+                &this,
+                false,
+            ))));
+        }
+    }
+    Ok(())
+}
+
+/// Put accessors for the owners of attributes that point to the target into an inherent impl on
+/// its struct.
+fn add_inverse_accessors(
+    target: &Archetype,
+    cfg: &CodegenConfig,
+    file: &mut FileFragment,
+) -> Result<(), CodegenError> {
+    let inverses = inverse_configs(target, cfg)?;
+    if inverses.is_empty() {
+        return Ok(());
+    }
+    let mut implementation =
+        ImplementationFragment::new_struct_impl(concept_to_struct(target, cfg.yin)?);
+    for inverse_cfg in inverses {
+        add_inverse_to_impl(&inverse_cfg, &mut implementation, file);
+    }
+    file.append(Rc::new(RefCell::new(implementation)));
+    Ok(())
 }
 
 /// Generate code for a given concept. Post-processing still needed.
-pub fn code_archetype(request: Implement, codegen_cfg: &CodegenConfig) -> String {
-    let mut target = Archetype::from(request.target().unwrap().id());
+pub fn code_archetype(
+    request: Implement,
+    codegen_cfg: &CodegenConfig,
+) -> Result<String, CodegenError> {
+    let mut target = request_target(&request)?;
+    let kgn = KnowledgeGraphNode::from(target.id());
+    if kgn.is_imported() {
+        return Err(CodegenError::ImportedConcept {
+            concept: describe(&target),
+        });
+    }
     let parent = primary_parent(&target);
 
-    let base_cfg = generic_config(&request, &target, &parent, codegen_cfg);
+    let base_cfg = generic_config(&request, &target, &parent, codegen_cfg)?;
 
     let mut file = tao_file_fragment(&base_cfg);
 
-    let kgn = KnowledgeGraphNode::from(target.id());
-    assert!(!kgn.is_imported(), "Coding an imported archetype {:?}", kgn);
     if !kgn.is_root_analogue() {
         add_form_fragment(
-            &form_config(&base_cfg, &mut target, &codegen_cfg),
+            &form_config(&base_cfg, &mut target, &codegen_cfg)?,
             &mut file,
         );
     }
 
     if activate_archetype(&target) {
         add_archetype_fragment(
-            &archetype_config(&base_cfg, &target, &codegen_cfg),
+            &archetype_config(&base_cfg, &target, &codegen_cfg)?,
             &mut file,
         );
     } else if activate_attribute(&target) {
         add_attr_fragments(
-            &attribute_config(&base_cfg, &target, codegen_cfg),
+            &attribute_config(&base_cfg, &target, codegen_cfg)?,
             &mut file,
         );
     } else if activate_data(&target) {
         add_data_fragments(
-            &data_config(&base_cfg, &DataArchetype::from(target.id())),
+            &data_config(&base_cfg, &DataArchetype::from(target.id()), codegen_cfg)?,
             &mut file,
         );
     }
 
-    if !in_own_submodule(&target) {
-        let mut implementation =
-            ImplementationFragment::new_struct_impl(concept_to_struct(&target, codegen_cfg.yin));
-        if !target.added_flags().is_empty() {
-            add_struct_flag_fragments(&target, codegen_cfg, &mut implementation, &mut file);
-        }
-        if !target.added_attributes().is_empty() {
-            add_struct_attr_fragments(&target, codegen_cfg, &mut implementation, &mut file);
+    if has_accessor_trait(&target, codegen_cfg) {
+        add_trait_accessors(&target, codegen_cfg, &mut file)?;
+    } else if generates_accessors(&target, codegen_cfg) {
+        add_struct_accessors(&target, codegen_cfg, &mut file)?;
+    }
+    add_inherited_accessor_traits(&target, codegen_cfg, &mut file)?;
+    add_inverse_accessors(&target, codegen_cfg, &mut file)?;
+    if codegen_cfg.builders && !kgn.is_root_analogue() {
+        if let Some(builder_cfg) = builder_config(&target, codegen_cfg)? {
+            add_builder_fragment(&builder_cfg, &mut file);
         }
-        if !implementation.content.borrow().appendages.is_empty() {
-            file.append(Rc::new(RefCell::new(implementation)));
+    }
+    if !kgn.is_root_analogue() {
+        if let Some(validate_cfg) = validate_config(&target, codegen_cfg)? {
+            add_validate_fragment(&validate_cfg, &mut file);
         }
     }
 
-    file.set_current_crate(Crate::current().implementation_name().unwrap());
-    file.generate_code()
+    let individuals = individual_accessors(&target, |id| id_expression(id, codegen_cfg))?;
+    if !individuals.is_empty() {
+        file.append(Rc::new(RefCell::new(individual_accessors_fragment(
+            &base_cfg.this,
+            &individuals,
+        ))));
+    }
+
+    // places for hand-written code that survive regeneration
+    file.append(Rc::new(RefCell::new(AtomicFragment::new(user_region(
+        USER_ITEMS_ANCHOR,
+    )))));
+    file.append_test(Rc::new(RefCell::new(AtomicFragment::new(user_region(
+        USER_TESTS_ANCHOR,
+    )))));
+
+    file.set_current_crate(current_crate_name()?);
+    Ok(file.generate_code())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codegen::planning::accessor_trait::mark_accessor_trait;
+    use crate::codegen::planning::set_inverse_accessor;
+    use crate::codegen::planning::{mark_required_attr, set_max_values, set_min_values};
+    use crate::helper::{add_variant, define_individual};
+    use crate::tao::form::rust_item::{Crate, CrateExtension};
     use crate::tao::initialize_kb;
-    use crate::tao::perspective::KnowledgeGraphNode;
+    use crate::tao::perspective::{BuildInfo, BuildInfoExtension, KnowledgeGraphNode};
     use indoc::indoc;
     use zamm_yin::tao::Tao;
 
@@ -428,7 +661,8 @@ mod tests {
             &target,
             &primary_parent(&target),
             &CodegenConfig::default(),
-        );
+        )
+        .unwrap();
 
         assert!(!target_kgn.is_root_analogue());
         assert!(!activate_attribute(&target));
@@ -437,6 +671,31 @@ mod tests {
         assert!(cfg.id.contains("YIN_MAX_ID"));
     }
 
+    #[test]
+    fn test_imported_archetype_not_coded() {
+        initialize_kb();
+        Crate::current().set_implementation_name("imported_crate");
+        let mut target = Tao::archetype().individuate_as_archetype();
+        target.set_internal_name("imported-archetype");
+        KnowledgeGraphNode::from(target.id()).mark_imported();
+        let request = target.implement_with_doc("Defined elsewhere.");
+        assert!(matches!(
+            code_archetype(request, &CodegenConfig::default()),
+            Err(CodegenError::ImportedConcept { concept }) if concept == "imported-archetype"
+        ));
+    }
+
+    #[test]
+    fn test_request_without_target() {
+        initialize_kb();
+        let mut implement = Implement::new();
+        implement.set_embodiment(&Concept::new().into());
+        assert!(matches!(
+            code_archetype(implement, &CodegenConfig::default()),
+            Err(CodegenError::MalformedConcept { .. })
+        ));
+    }
+
     #[test]
     fn code_cfg_for_yin() {
         initialize_kb();
@@ -454,7 +713,8 @@ mod tests {
                 yin: true,
                 ..CodegenConfig::default()
             },
-        );
+        )
+        .unwrap();
 
         assert!(!cfg.id.contains("YIN_MAX_ID"));
     }
@@ -474,7 +734,8 @@ mod tests {
             &target,
             &primary_parent(&target),
             &CodegenConfig::default(),
-        );
+        )
+        .unwrap();
 
         assert_eq!(
             cfg.doc.trim(),
@@ -487,6 +748,135 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_inherited_accessor_trait() {
+        initialize_kb();
+        let mut parent = Form::archetype().individuate_as_archetype();
+        parent.set_internal_name("trait-parent");
+        KnowledgeGraphNode::from(parent.id()).mark_imported();
+        BuildInfo::from(parent.id()).set_import_path("upstream::tao::form::TraitParent");
+        mark_accessor_trait(&parent);
+        let mut child = parent.individuate_as_archetype();
+        child.set_internal_name("trait-child");
+        KnowledgeGraphNode::from(child.id()).mark_newly_defined();
+        let mut implement = Implement::new();
+        implement.set_target(&child.as_form());
+        implement.set_embodiment(&Concept::new().into());
+        implement.set_documentation("Child of a parent with an accessor trait.");
+
+        let code = code_archetype(implement, &CodegenConfig::default()).unwrap();
+        assert!(code.contains("use upstream::tao::form::TraitParentTrait;"));
+        assert!(code.contains("impl TraitParentTrait for TraitChild {}"));
+        assert!(code.contains("// BEGIN USER CODE: items\n// END USER CODE: items"));
+        assert!(code.contains("    // BEGIN USER CODE: tests\n    // END USER CODE: tests"));
+    }
+
+    #[test]
+    fn test_individual_accessors() {
+        initialize_kb();
+        Crate::current().set_implementation_name("lamp_crate");
+        let mut lamp = Form::archetype().individuate_as_archetype();
+        lamp.set_internal_name("accessor-lamp");
+        KnowledgeGraphNode::from(lamp.id()).mark_newly_defined();
+        let implement = lamp.implement_with_doc("A source of light.");
+        let desk = define_individual(lamp, "desk-lamp", "The lamp on the desk.");
+        let desk_request = desk.concept_implementation().unwrap();
+        Concept::from(desk_request.embodiment().unwrap().id()).set_concept_id(2);
+
+        let code = code_archetype(implement, &CodegenConfig::default()).unwrap();
+        assert!(code.contains("impl AccessorLamp {"));
+        assert!(code.contains("/// The lamp on the desk."));
+        assert!(code.contains("pub fn desk_lamp() -> Self {"));
+        assert!(code.contains("Self::from(YIN_MAX_ID + 2)"));
+    }
+
+    #[test]
+    fn test_inverse_accessors() {
+        initialize_kb();
+        Crate::current().set_implementation_name("shelf_crate");
+        let mut shelf = Form::archetype().individuate_as_archetype();
+        shelf.set_internal_name("coded-shelf");
+        let mut book = Form::archetype().individuate_as_archetype();
+        book.set_internal_name("coded-book");
+        KnowledgeGraphNode::from(book.id()).mark_newly_defined();
+        let implement = book.implement_with_doc("Something to read.");
+        let mut holds = Attribute::archetype().individuate_as_archetype();
+        holds.set_internal_name("coded-holds");
+        holds.set_owner_archetype(&shelf);
+        holds.set_value_archetype(&book);
+        shelf.add_attribute(&holds);
+        set_inverse_accessor(&holds, "held_by", "the shelves that hold this book.");
+
+        let code = code_archetype(implement, &CodegenConfig::default()).unwrap();
+        assert!(code.contains("impl CodedBook {"));
+        assert!(code.contains("/// Get the shelves that hold this book."));
+        assert!(code.contains("pub fn held_by(&self) -> Vec<CodedShelf> {"));
+        assert!(code.contains("fn test_get_held_by() {"));
+        assert!(code.contains("fn test_held_by_inheritance() {"));
+    }
+
+    #[test]
+    fn test_builder() {
+        initialize_kb();
+        Crate::current().set_implementation_name("builder_crate");
+        let mut shelf = Form::archetype().individuate_as_archetype();
+        shelf.set_internal_name("built-shelf");
+        KnowledgeGraphNode::from(shelf.id()).mark_newly_defined();
+        let implement = shelf.implement_with_doc("Somewhere to put books.");
+        let mut holds = Attribute::archetype().individuate_as_archetype();
+        holds.set_internal_name("built-holds");
+        holds.set_owner_archetype(&shelf);
+        holds
+            .implement_with_doc("The book on a shelf.")
+            .set_dual_purpose_documentation("the book on this shelf.");
+        shelf.add_attribute(&holds);
+        mark_required_attr(&holds);
+
+        let without_builders = code_archetype(implement, &CodegenConfig::default()).unwrap();
+        assert!(!without_builders.contains("BuiltShelfBuilder"));
+
+        let code = code_archetype(
+            implement,
+            &CodegenConfig {
+                builders: true,
+                ..CodegenConfig::default()
+            },
+        )
+        .unwrap();
+        assert!(code.contains("pub struct BuiltShelfBuilder {"));
+        assert!(code.contains("pub fn built_holds(mut self, built_holds: &Form) -> Self {"));
+        assert!(code.contains("if self.built_holds.is_none() {"));
+        assert!(code.contains("fn test_builder() {"));
+    }
+
+    #[test]
+    fn test_cardinality() {
+        initialize_kb();
+        Crate::current().set_implementation_name("cardinality_crate");
+        let mut team = Form::archetype().individuate_as_archetype();
+        team.set_internal_name("cardinal-team");
+        KnowledgeGraphNode::from(team.id()).mark_newly_defined();
+        let implement = team.implement_with_doc("A group of players.");
+        let mut player = Attribute::archetype().individuate_as_archetype();
+        player.set_internal_name("cardinal-player");
+        player.set_owner_archetype(&team);
+        player.mark_multi_valued_attr();
+        player
+            .implement_with_doc("The players on a team.")
+            .set_dual_purpose_documentation("the players on this team.");
+        team.add_attribute(&player);
+        set_min_values(&player, 2);
+        set_max_values(&player, 11);
+
+        let code = code_archetype(implement, &CodegenConfig::default()).unwrap();
+        assert!(code.contains("Only the latest 11 are kept."));
+        assert!(code.contains(".take(11)"));
+        assert!(code.contains("fn test_cardinal_player_max_values() {"));
+        assert!(code.contains("-> Result<(), Vec<MissingAttributeError>> {"));
+        assert!(code.contains(".outgoing_nodes(CardinalPlayer::TYPE_ID).len() < 2 {"));
+        assert!(code.contains("fn test_validate() {"));
+    }
+
     #[test]
     fn code_cfg_for_root_node_activated() {
         initialize_kb();
@@ -523,7 +913,8 @@ mod tests {
             &generic_config(&implement, &target.into(), &parent, &codegen_cfg),
             &target.into(),
             &codegen_cfg,
-        );
+        )
+        .unwrap();
 
         assert!(!kgn.is_root_analogue());
         assert!(activate_attribute(&target.into()));
@@ -552,7 +943,7 @@ mod tests {
         Tao::archetype().set_meta_archetype(&Archetype::archetype());
         Crate::yang().set_version("0.1.8");
 
-        let cfg = generic_config(&implement, &target, &parent, &codegen_cfg);
+        let cfg = generic_config(&implement, &target, &parent, &codegen_cfg).unwrap();
         assert_eq!(cfg.archetype.name, "Archetype");
     }
 
@@ -577,7 +968,7 @@ mod tests {
             .specific_meta()
             .set_internal_name("my-data-type-archetype");
 
-        let cfg = generic_config(&implement, &target, &parent, &codegen_cfg);
+        let cfg = generic_config(&implement, &target, &parent, &codegen_cfg).unwrap();
         assert_eq!(cfg.archetype.name, "MyDataTypeArchetype");
     }
 
@@ -605,7 +996,45 @@ mod tests {
         let mut i = Implement::new();
         i.set_target(&my_root.as_form());
         i.set_embodiment(&Concept::new().into());
-        let code = code_archetype(i, &CodegenConfig::default());
+        let code = code_archetype(i, &CodegenConfig::default()).unwrap();
         assert!(!code.contains("impl FormTrait"));
     }
+
+    #[test]
+    fn data_config_missing_primitive() {
+        initialize_kb();
+        let mut target = Tao::archetype().individuate_as_archetype();
+        target.set_internal_name("my-data");
+        KnowledgeGraphNode::from(target.id()).mark_data_analogue();
+        let error = data_config(
+            &TaoConfig::default(),
+            &DataArchetype::from(target.id()),
+            &CodegenConfig::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Data type my-data has no defined Rust primitive"
+        );
+    }
+
+    #[test]
+    fn data_config_enum() {
+        initialize_kb();
+        let mut target = Tao::archetype().individuate_as_archetype();
+        target.set_internal_name("my-enum");
+        KnowledgeGraphNode::from(target.id()).mark_data_analogue();
+        add_variant(target, "first-choice", "The first choice.");
+        add_variant(target, "second-choice", "The second choice.");
+        let cfg = data_config(
+            &TaoConfig::default(),
+            &DataArchetype::from(target.id()),
+            &CodegenConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(&*cfg.rust_primitive_boxed_name, "MyEnumValue");
+        assert_eq!(&*cfg.default_value, "MyEnumValue::FirstChoice");
+        assert_eq!(cfg.enum_variants.len(), 2);
+    }
 }
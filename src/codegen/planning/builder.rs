@@ -0,0 +1,148 @@
+use super::archetype::attr_property_config;
+use super::cardinality::min_values;
+use super::concept_to_struct;
+use super::imports::internal_name;
+use crate::codegen::template::concept::builder::{BuilderAttributeConfig, BuilderConfig};
+use crate::codegen::{CodegenConfig, CodegenError};
+use crate::tao::archetype::CreateImplementation;
+use heck::SnakeCase;
+use std::convert::TryFrom;
+use std::rc::Rc;
+use zamm_yin::node_wrappers::{BaseNodeTrait, CommonNodeTrait, FinalNode};
+use zamm_yin::tao::archetype::{Archetype, ArchetypeFormTrait, ArchetypeTrait, AttributeArchetype};
+use zamm_yin::tao::relation::flag::Flag;
+
+/// Internal name of the flag that marks an attribute as required by builders.
+const REQUIRED_FLAG: &str = "required-attribute";
+
+/// Mark the attribute as one that generated builders refuse to build without.
+pub fn mark_required_attr(attr: &AttributeArchetype) {
+    let flag = Archetype::try_from(REQUIRED_FLAG).unwrap_or_else(|_| {
+        let mut flag = Flag::archetype().individuate_as_archetype();
+        flag.set_internal_name(REQUIRED_FLAG);
+        flag.into()
+    });
+    FinalNode::from(attr.id()).add_flag(flag.id());
+}
+
+/// Whether or not the attribute was marked as required. Child attributes don't inherit this, so
+/// that setting a more general attribute doesn't have to satisfy every specialization of it.
+pub fn is_required_attr(attr: &AttributeArchetype) -> bool {
+    match Archetype::try_from(REQUIRED_FLAG) {
+        Ok(flag) => FinalNode::from(attr.id())
+            .base_wrapper()
+            .has_flag(flag.id()),
+        Err(_) => false,
+    }
+}
+
+/// Config for the target's builder, if it has any attributes to build with.
+pub fn builder_config(
+    target: &Archetype,
+    codegen_cfg: &CodegenConfig,
+) -> Result<Option<BuilderConfig>, CodegenError> {
+    let attributes = target.attributes();
+    if attributes.is_empty() {
+        return Ok(None);
+    }
+    let this = concept_to_struct(target, codegen_cfg.yin)?;
+    let attributes = attributes
+        .iter()
+        .map(|attr| -> Result<BuilderAttributeConfig, CodegenError> {
+            // attributes inherited from other crates have no documentation here to go by
+            let doc = match attr
+                .accessor_implementation()
+                .and_then(|i| i.dual_purpose_documentation())
+            {
+                Some(doc) => doc,
+                None => Rc::from(format!(
+                    "the {} of the {}.",
+                    internal_name(&(*attr).into())?
+                        .to_snake_case()
+                        .replace('_', " "),
+                    internal_name(target)?.to_snake_case().replace('_', " ")
+                )),
+            };
+            Ok(BuilderAttributeConfig {
+                property: attr_property_config(codegen_cfg, target, attr, doc)?,
+                required: min_values(attr) > 0,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some(BuilderConfig {
+        name: format!("{}Builder", this.name),
+        this,
+        attributes,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tao::initialize_kb;
+    use zamm_yin::tao::archetype::AttributeArchetypeFormTrait;
+    use zamm_yin::tao::form::Form;
+    use zamm_yin::tao::relation::attribute::Attribute;
+
+    #[test]
+    fn test_required_not_inherited() {
+        initialize_kb();
+        let mut name = Attribute::archetype().individuate_as_archetype();
+        name.set_internal_name("builder-name");
+        mark_required_attr(&name);
+        let nickname = name.individuate_as_archetype();
+
+        assert!(is_required_attr(&name));
+        assert!(!is_required_attr(&nickname));
+    }
+
+    #[test]
+    fn test_builder_config() {
+        initialize_kb();
+        let mut parent = Form::archetype().individuate_as_archetype();
+        parent.set_internal_name("builder-parent");
+        let mut child = parent.individuate_as_archetype();
+        child.set_internal_name("builder-child");
+        let mut inherited = Attribute::archetype().individuate_as_archetype();
+        inherited.set_internal_name("builder-inherited");
+        inherited.set_owner_archetype(&parent);
+        parent.add_attribute(&inherited);
+        mark_required_attr(&inherited);
+        let mut introduced = Attribute::archetype().individuate_as_archetype();
+        introduced.set_internal_name("builder-introduced");
+        introduced.set_owner_archetype(&child);
+        introduced.mark_multi_valued_attr();
+        child.add_attribute(&introduced);
+
+        let cfg = builder_config(&child, &CodegenConfig::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(cfg.name, "BuilderChildBuilder");
+        let mut attributes = cfg
+            .attributes
+            .iter()
+            .map(|a| (&*a.property.property_name, a.required, &*a.property.doc))
+            .collect::<Vec<(&str, bool, &str)>>();
+        attributes.sort_unstable();
+        assert_eq!(
+            attributes,
+            vec![
+                (
+                    "builder_inherited",
+                    true,
+                    "the builder inherited of the builder child."
+                ),
+                (
+                    "builder_introduced",
+                    false,
+                    "the builder introduced of the builder child."
+                ),
+            ]
+        );
+
+        let lonely = Form::archetype().individuate_as_archetype();
+        assert!(builder_config(&lonely, &CodegenConfig::default())
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,150 @@
+use super::archetype::or_form_default;
+use super::builder::is_required_attr;
+use super::concept_to_struct;
+use crate::codegen::template::concept::validate::{ValidateAttributeConfig, ValidateConfig};
+use crate::codegen::{CodegenConfig, CodegenError};
+use crate::tao::perspective::BuildInfo;
+use zamm_yin::node_wrappers::CommonNodeTrait;
+use zamm_yin::tao::archetype::{
+    Archetype, ArchetypeFormTrait, AttributeArchetype, AttributeArchetypeFormTrait,
+};
+
+/// Have the generated `validate` of the attribute's owners report owners with fewer than this
+/// many values for the attribute.
+///
+/// Each attribute with too few values is reported as a `MissingAttributeError`. Required
+/// attributes already need at least one value, even without a minimum.
+pub fn set_min_values(attr: &AttributeArchetype, min: usize) {
+    BuildInfo::from(attr.id()).set_minimum_values(min);
+}
+
+/// Have the generated setters of the attribute replace old values instead of adding new ones, once
+/// the owner has this many values for the attribute.
+///
+/// The oldest value is dropped to make room for a new primitive value. Any other kind of value is
+/// ignored once the cap is reached, because the edge to an existing concept can't be removed. The
+/// getters only return the latest values. Single-valued attributes can only be capped at one.
+pub fn set_max_values(attr: &AttributeArchetype, max: usize) {
+    BuildInfo::from(attr.id()).set_maximum_values(max);
+}
+
+/// The fewest values that the attribute needs. Required attributes need at least one. Like the
+/// required flag, this isn't inherited by child attributes.
+pub fn min_values(attr: &AttributeArchetype) -> usize {
+    let min = BuildInfo::from(attr.id())
+        .minimum_values()
+        .map_or(0, |n| *n);
+    if is_required_attr(attr) {
+        min.max(1)
+    } else {
+        min
+    }
+}
+
+/// The most values that the attribute keeps, if it's capped at all. Single-valued attributes
+/// can't be capped at more than one value, because their getters only ever return one anyways.
+pub fn max_values(attr: &AttributeArchetype) -> Option<usize> {
+    let max = *BuildInfo::from(attr.id()).maximum_values()?;
+    if attr.is_multi_valued_attr() {
+        Some(max)
+    } else {
+        Some(max.min(1))
+    }
+}
+
+/// Config for the target's `validate`, if any of its attributes need a minimum number of values.
+pub fn validate_config(
+    target: &Archetype,
+    codegen_cfg: &CodegenConfig,
+) -> Result<Option<ValidateConfig>, CodegenError> {
+    let mut attributes = vec![];
+    for attr in target.attributes() {
+        let min = min_values(&attr);
+        if min == 0 {
+            continue;
+        }
+        attributes.push(ValidateAttributeConfig {
+            attr: concept_to_struct(&attr.into(), codegen_cfg.yin)?,
+            value_type: concept_to_struct(
+                &or_form_default(attr.value_archetype()),
+                codegen_cfg.yin,
+            )?,
+            hereditary: !attr.is_nonhereditary_attr(),
+            min_values: min,
+        });
+    }
+    if attributes.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(ValidateConfig {
+        this: concept_to_struct(target, codegen_cfg.yin)?,
+        attributes,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::planning::mark_required_attr;
+    use crate::tao::initialize_kb;
+    use zamm_yin::tao::archetype::ArchetypeTrait;
+    use zamm_yin::tao::form::Form;
+    use zamm_yin::tao::relation::attribute::Attribute;
+
+    #[test]
+    fn test_cardinality() {
+        initialize_kb();
+        let mut tags = Attribute::archetype().individuate_as_archetype();
+        tags.set_internal_name("cardinality-tags");
+        tags.mark_multi_valued_attr();
+        set_min_values(&tags, 2);
+        set_max_values(&tags, 3);
+        let mut name = Attribute::archetype().individuate_as_archetype();
+        name.set_internal_name("cardinality-name");
+        mark_required_attr(&name);
+        set_max_values(&name, 5);
+        let nickname = name.individuate_as_archetype();
+
+        assert_eq!(min_values(&tags), 2);
+        assert_eq!(max_values(&tags), Some(3));
+        assert_eq!(min_values(&name), 1);
+        assert_eq!(max_values(&name), Some(1));
+        assert_eq!(min_values(&nickname), 0);
+        assert_eq!(max_values(&nickname), None);
+    }
+
+    #[test]
+    fn test_validate_config() {
+        initialize_kb();
+        let mut parent = Form::archetype().individuate_as_archetype();
+        parent.set_internal_name("cardinality-parent");
+        let mut child = parent.individuate_as_archetype();
+        child.set_internal_name("cardinality-child");
+        let mut inherited = Attribute::archetype().individuate_as_archetype();
+        inherited.set_internal_name("cardinality-inherited");
+        inherited.set_owner_archetype(&parent);
+        parent.add_attribute(&inherited);
+        mark_required_attr(&inherited);
+        let mut optional = Attribute::archetype().individuate_as_archetype();
+        optional.set_internal_name("cardinality-optional");
+        optional.set_owner_archetype(&child);
+        child.add_attribute(&optional);
+
+        let cfg = validate_config(&child, &CodegenConfig::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(cfg.this.name, "CardinalityChild");
+        assert_eq!(
+            cfg.attributes
+                .iter()
+                .map(|a| (a.attr.name.as_str(), a.min_values))
+                .collect::<Vec<(&str, usize)>>(),
+            vec![("CardinalityInherited", 1)]
+        );
+
+        let lonely = Form::archetype().individuate_as_archetype();
+        assert!(validate_config(&lonely, &CodegenConfig::default())
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,173 @@
+use super::imports::describe;
+use crate::codegen::template::dot::{DotConfig, DotEdge, DotNode, DotNodeKind};
+use crate::tao::form::rust_item::{Module, ModuleExtension};
+use crate::tao::perspective::KnowledgeGraphNode;
+use crate::tao::relation::attribute::{Member, MostProminentMember};
+use std::collections::{BTreeMap, HashSet};
+use zamm_yin::node_wrappers::{BaseNodeTrait, CommonNodeTrait};
+use zamm_yin::tao::archetype::{Archetype, ArchetypeFormTrait, ArchetypeTrait};
+use zamm_yin::tao::relation::attribute::has_property::{HasAttribute, HasFlag};
+use zamm_yin::tao::relation::attribute::{Inherits, OwnerArchetype, ValueArchetype};
+use zamm_yin::tao::Tao;
+
+/// Names of the knowledge graph flags that are set on a concept.
+fn kgn_flags(kgn: &KnowledgeGraphNode) -> Vec<String> {
+    vec![
+        (kgn.is_newly_defined(), "newly-defined"),
+        (kgn.is_imported(), "imported"),
+        (kgn.is_root_analogue(), "root-analogue"),
+        (kgn.is_root_archetype_analogue(), "root-archetype-analogue"),
+        (kgn.is_archetype_analogue(), "archetype-analogue"),
+        (kgn.is_attribute_analogue(), "attribute-analogue"),
+        (kgn.is_data_analogue(), "data-analogue"),
+    ]
+    .into_iter()
+    .filter(|(set, _)| *set)
+    .map(|(_, name)| name.to_owned())
+    .collect()
+}
+
+/// Describe a concept as a node in the exported graph.
+fn concept_node(target: &Archetype) -> DotNode {
+    let kgn = KnowledgeGraphNode::from(target.id());
+    let kind = if kgn.is_newly_defined() {
+        DotNodeKind::NewlyDefined
+    } else if kgn.is_imported() {
+        DotNodeKind::Imported
+    } else {
+        DotNodeKind::Existing
+    };
+    DotNode {
+        id: target.id(),
+        label: describe(target),
+        kind,
+        flags: kgn_flags(&kgn),
+    }
+}
+
+/// Describe a module as a node in the exported graph.
+fn module_node(module: &Module) -> DotNode {
+    let name = match ModuleExtension::implementation_name(module) {
+        Some(name) => (*name).to_owned(),
+        None => format!("#{}", module.id()),
+    };
+    DotNode {
+        id: module.id(),
+        label: format!("mod {}", name),
+        kind: DotNodeKind::Module,
+        flags: vec![],
+    }
+}
+
+/// All archetypes that descend from the root node.
+fn all_archetypes() -> Vec<Archetype> {
+    let mut visited = HashSet::new();
+    let mut to_visit = vec![Tao::archetype()];
+    let mut archetypes = vec![];
+    while let Some(next) = to_visit.pop() {
+        if visited.insert(next.id()) {
+            to_visit.extend(next.child_archetypes());
+            archetypes.push(next);
+        }
+    }
+    archetypes.sort_by_key(|a| a.id());
+    archetypes
+}
+
+/// Collect the graph of every concept that was newly defined or imported, along with every
+/// concept that they're directly related to. Only the given modules, which should be the ones
+/// that this crate implements, are included along with their members.
+pub fn dot_config(modules: &[Module]) -> DotConfig {
+    let concept_relations = [
+        Inherits::TYPE_ID,
+        OwnerArchetype::TYPE_ID,
+        ValueArchetype::TYPE_ID,
+        HasAttribute::TYPE_ID,
+        HasFlag::TYPE_ID,
+    ];
+    let module_relations = [Member::TYPE_ID, MostProminentMember::TYPE_ID];
+
+    let mut nodes = BTreeMap::new();
+    let mut edges = vec![];
+    let mut add_edges = |from: usize, relations: &[usize], nodes: &mut BTreeMap<usize, DotNode>| {
+        for relation in relations {
+            let label = describe(&Archetype::from(*relation));
+            for to in Archetype::from(from)
+                .base_wrapper()
+                .outgoing_nodes(*relation)
+            {
+                nodes
+                    .entry(to.id())
+                    .or_insert_with(|| concept_node(&Archetype::from(to.id())));
+                edges.push(DotEdge {
+                    from,
+                    to: to.id(),
+                    label: label.clone(),
+                });
+            }
+        }
+    };
+
+    for concept in all_archetypes() {
+        let kgn = KnowledgeGraphNode::from(concept.id());
+        if !kgn.is_newly_defined() && !kgn.is_imported() {
+            continue;
+        }
+        nodes.insert(concept.id(), concept_node(&concept));
+        add_edges(concept.id(), &concept_relations, &mut nodes);
+    }
+    for module in modules {
+        // modules may already have been added as plain nodes, if they're members of other modules
+        nodes.insert(module.id(), module_node(module));
+        add_edges(module.id(), &module_relations, &mut nodes);
+    }
+
+    DotConfig {
+        nodes: nodes.into_iter().map(|(_, node)| node).collect(),
+        edges,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tao::archetype::CreateImplementation;
+    use crate::tao::initialize_kb;
+    use zamm_yin::tao::form::Form;
+    use zamm_yin::tao::relation::attribute::Attribute;
+
+    #[test]
+    fn test_dot_config() {
+        initialize_kb();
+        let mut owner = Form::archetype().individuate_as_archetype();
+        owner.set_internal_name("dot-owner");
+        KnowledgeGraphNode::from(owner.id()).mark_newly_defined();
+        let mut attr = Attribute::archetype().individuate_as_archetype();
+        attr.set_internal_name("dot-attr");
+        KnowledgeGraphNode::from(attr.id()).mark_imported();
+        owner.add_attribute(&attr);
+        let module = owner.impl_mod("Module for the owner.");
+        let mut other = Form::archetype().individuate_as_archetype();
+        other.set_internal_name("dot-other");
+        let other_module = other.impl_mod("Module from some other crate.");
+
+        let cfg = dot_config(&[module]);
+        let node = |id: usize| cfg.nodes.iter().find(|n| n.id == id).unwrap();
+        assert_eq!(node(owner.id()).kind, DotNodeKind::NewlyDefined);
+        assert_eq!(node(owner.id()).flags, vec!["newly-defined"]);
+        assert_eq!(node(attr.id()).kind, DotNodeKind::Imported);
+        assert_eq!(node(Form::TYPE_ID).kind, DotNodeKind::Existing);
+        assert_eq!(node(module.id()).label, "mod dot_owner");
+        assert!(!cfg.nodes.iter().any(|n| n.id == other_module.id()));
+
+        let edges = cfg
+            .edges
+            .iter()
+            .map(|e| (e.from, e.label.as_str(), e.to))
+            .collect::<Vec<(usize, &str, usize)>>();
+        assert!(edges.contains(&(owner.id(), "inherits", Form::TYPE_ID)));
+        assert!(edges.contains(&(owner.id(), "has-attribute", attr.id())));
+        assert!(edges.contains(&(module.id(), "most-prominent-member", owner.id())));
+        assert!(!edges.iter().any(|(from, _, _)| *from == Form::TYPE_ID));
+    }
+}
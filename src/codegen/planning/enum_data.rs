@@ -0,0 +1,85 @@
+use super::concept_to_struct;
+use super::imports::internal_name;
+use crate::codegen::template::concept::data::EnumVariantConfig;
+use crate::codegen::{CodegenConfig, CodegenError, StructConfig};
+use crate::tao::archetype::rust_item_archetype::DataArchetype;
+use heck::CamelCase;
+use std::rc::Rc;
+use zamm_yin::node_wrappers::CommonNodeTrait;
+use zamm_yin::tao::archetype::Archetype;
+
+/// Whether or not the data concept can only take on one of a fixed set of variants.
+pub fn is_enum(target: &Archetype) -> bool {
+    !DataArchetype::from(target.id()).variants().is_empty()
+}
+
+/// The enum generated for an enumeration data concept. It lives right next to the concept's own
+/// struct.
+pub fn enum_struct(
+    target: &Archetype,
+    codegen_cfg: &CodegenConfig,
+) -> Result<StructConfig, CodegenError> {
+    let owner = concept_to_struct(target, codegen_cfg.yin)?;
+    Ok(StructConfig {
+        name: format!("{}Value", owner.name),
+        import: format!("{}Value", owner.import),
+    })
+}
+
+/// Variants of an enumeration data concept, in the order they were added.
+pub fn enum_variants(target: &Archetype) -> Result<Vec<EnumVariantConfig>, CodegenError> {
+    DataArchetype::from(target.id())
+        .variants()
+        .iter()
+        .map(|variant| {
+            Ok(EnumVariantConfig {
+                name: Rc::from(internal_name(&Archetype::from(variant.id()))?.to_camel_case()),
+                doc: variant.variant_documentation(),
+            })
+        })
+        .collect()
+}
+
+/// Rust code for a single variant of the enum.
+pub fn variant_value(enum_struct: &StructConfig, variant: &EnumVariantConfig) -> Rc<str> {
+    Rc::from(format!("{}::{}", enum_struct.name, variant.name).as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::add_variant;
+    use crate::tao::initialize_kb;
+    use crate::tao::perspective::KnowledgeGraphNode;
+    use zamm_yin::tao::archetype::{ArchetypeFormTrait, ArchetypeTrait};
+    use zamm_yin::tao::form::Form;
+
+    #[test]
+    fn test_enum_variants() {
+        initialize_kb();
+        let mut light = Form::archetype().individuate_as_archetype();
+        light.set_internal_name("traffic-light");
+        KnowledgeGraphNode::from(light.id()).mark_newly_defined();
+        assert!(!is_enum(&light));
+
+        add_variant(light, "red", "Stop.");
+        add_variant(light, "flashing-yellow", "Proceed with caution.");
+        assert!(is_enum(&light));
+
+        let enum_cfg = enum_struct(&light, &CodegenConfig::default()).unwrap();
+        assert_eq!(
+            enum_cfg,
+            StructConfig::new("crate::tao::form::TrafficLightValue".to_owned())
+        );
+        let variants = enum_variants(&light).unwrap();
+        assert_eq!(
+            variants.iter().map(|v| &*v.name).collect::<Vec<&str>>(),
+            vec!["Red", "FlashingYellow"]
+        );
+        assert_eq!(variants[0].doc, Some(Rc::from("Stop.")));
+        assert_eq!(
+            variant_value(&enum_cfg, &variants[1]),
+            Rc::from("TrafficLightValue::FlashingYellow")
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use super::accessor_trait::{accessor_trait_struct, generates_accessors, has_accessor_trait};
+use super::archetype::{activate_archetype, activate_attribute, activate_data};
+use super::imports::{
+    archetype_file_path, describe, import_path, in_own_submodule, internal_name, module_file_path,
+};
+use crate::codegen::id_lock::{IdLock, ID_LOCK};
+use crate::codegen::{CodegenConfig, CodegenError};
+use crate::tao::archetype::CreateImplementation;
+use crate::tao::form::rust_item::Concept;
+use crate::tao::perspective::{BuildInfo, BuildInfoExtension, KnowledgeGraphNode};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use zamm_yin::node_wrappers::CommonNodeTrait;
+use zamm_yin::tao::archetype::{Archetype, ArchetypeFormTrait, ArchetypeTrait};
+use zamm_yin::tao::form::FormTrait;
+
+/// Which kind of concept code generation treats a concept as. Only the first one that applies gets
+/// used, in the order listed here.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Activation {
+    /// The concept is an archetype, and gets archetype-specific functions.
+    Archetype,
+    /// The concept is an attribute, and gets owner and value archetype functions.
+    Attribute,
+    /// The concept is data, and gets value accessors.
+    Data,
+    /// The concept is none of the above, and only gets a plain form.
+    Form,
+}
+
+/// Where a concept's ID comes from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IdAssignment {
+    /// The concept was already assigned this ID during code generation.
+    Assigned(usize),
+    /// The concept will reuse the ID it was given in a previous build, as recorded in the ID lock.
+    Locked(usize),
+    /// The concept will get a fresh ID the next time code is generated.
+    Fresh,
+}
+
+/// Where accessors for the attributes and flags introduced by a concept get generated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccessorPlacement {
+    /// Accessors go into an inherent `impl` on the concept's own struct.
+    Inherent,
+    /// Accessors go into a trait of this name that is shared with the concept's descendants.
+    Trait(String),
+    /// No accessors get generated, because they're expected to be written by hand.
+    None,
+}
+
+/// Everything that goes into planning the code generated for a single concept.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    /// Name of the concept being explained.
+    pub concept: String,
+    /// Which kind of concept code generation treats this concept as.
+    pub activation: Activation,
+    /// Whether or not the concept is treated as the root node, and therefore doesn't get a form of
+    /// its own.
+    pub root: bool,
+    /// Whether or not the concept gets a submodule of its own.
+    pub own_submodule: bool,
+    /// The file that the concept's code gets output to.
+    pub file_path: String,
+    /// The path that the concept's struct gets imported from.
+    pub import_path: String,
+    /// Where the concept's ID comes from.
+    pub id: IdAssignment,
+    /// Where accessors for the concept's introduced attributes and flags get generated.
+    pub accessors: AccessorPlacement,
+    /// Attributes introduced by this concept itself.
+    pub introduced_attributes: Vec<String>,
+    /// Attributes inherited from the concept's ancestors.
+    pub inherited_attributes: Vec<String>,
+    /// Flags introduced by this concept itself.
+    pub introduced_flags: Vec<String>,
+    /// Flags inherited from the concept's ancestors.
+    pub inherited_flags: Vec<String>,
+    /// Accessor traits of ancestors that the concept also implements.
+    pub inherited_accessor_traits: Vec<String>,
+    /// The module file that declares the concept and re-exports its struct, if a module is being
+    /// generated for it.
+    pub module: Option<String>,
+}
+
+/// Display a list of names, or "none" if there aren't any.
+fn name_list(names: &[String]) -> String {
+    if names.is_empty() {
+        "none".to_owned()
+    } else {
+        names.join(", ")
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let activation = match self.activation {
+            Activation::Archetype => "archetype",
+            Activation::Attribute => "attribute",
+            Activation::Data => "data",
+            Activation::Form => "form",
+        };
+        writeln!(f, "{}", self.concept)?;
+        write!(f, "  treated as: {}", activation)?;
+        if self.root {
+            write!(f, " (root)")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "  file: {}", self.file_path)?;
+        writeln!(f, "  import: {}", self.import_path)?;
+        writeln!(
+            f,
+            "  own submodule: {}",
+            if self.own_submodule { "yes" } else { "no" }
+        )?;
+        match self.id {
+            IdAssignment::Assigned(id) => writeln!(f, "  ID: {}", id)?,
+            IdAssignment::Locked(id) => writeln!(f, "  ID: {} (from {})", id, ID_LOCK)?,
+            IdAssignment::Fresh => writeln!(f, "  ID: fresh")?,
+        }
+        match &self.accessors {
+            AccessorPlacement::Inherent => writeln!(f, "  accessors: inherent impl")?,
+            AccessorPlacement::Trait(name) => writeln!(f, "  accessors: trait {}", name)?,
+            AccessorPlacement::None => writeln!(f, "  accessors: none, written by hand")?,
+        }
+        writeln!(
+            f,
+            "  introduced attributes: {}",
+            name_list(&self.introduced_attributes)
+        )?;
+        writeln!(
+            f,
+            "  inherited attributes: {}",
+            name_list(&self.inherited_attributes)
+        )?;
+        writeln!(
+            f,
+            "  introduced flags: {}",
+            name_list(&self.introduced_flags)
+        )?;
+        writeln!(f, "  inherited flags: {}", name_list(&self.inherited_flags))?;
+        writeln!(
+            f,
+            "  inherited accessor traits: {}",
+            name_list(&self.inherited_accessor_traits)
+        )?;
+        writeln!(
+            f,
+            "  declared in: {}",
+            self.module.as_deref().unwrap_or("no generated module")
+        )
+    }
+}
+
+/// Which kind of concept code generation treats the target as.
+fn activation(target: &Archetype) -> Activation {
+    if activate_archetype(target) {
+        Activation::Archetype
+    } else if activate_attribute(target) {
+        Activation::Attribute
+    } else if activate_data(target) {
+        Activation::Data
+    } else {
+        Activation::Form
+    }
+}
+
+/// Where the target's ID comes from, given the IDs handed out in the previous build.
+fn id_assignment(target: &Archetype, ids: &IdLock) -> Result<IdAssignment, CodegenError> {
+    let assigned = target
+        .implementations()
+        .into_iter()
+        .filter_map(|i| i.embodiment())
+        .find(|e| e.has_ancestor(Concept::archetype().into()))
+        .and_then(|e| Concept::from(e.id()).concept_id());
+    if let Some(id) = assigned {
+        return Ok(IdAssignment::Assigned(*id));
+    }
+    Ok(match ids.id(&internal_name(target)?) {
+        Some(id) => IdAssignment::Locked(id),
+        None => IdAssignment::Fresh,
+    })
+}
+
+/// Names of the given concepts, split into those that are in `introduced` and those that aren't.
+fn split_introduced(all: Vec<Archetype>, introduced: &[Archetype]) -> (Vec<String>, Vec<String>) {
+    let (own, inherited): (Vec<Archetype>, Vec<Archetype>) =
+        all.into_iter().partition(|a| introduced.contains(a));
+    (
+        own.iter().map(describe).collect(),
+        inherited.iter().map(describe).collect(),
+    )
+}
+
+/// Explain how code gets generated for the target, given the IDs handed out in the previous build.
+pub fn explain(
+    target: &Archetype,
+    ids: &IdLock,
+    codegen_cfg: &CodegenConfig,
+) -> Result<Explanation, CodegenError> {
+    let kgn = KnowledgeGraphNode::from(target.id());
+
+    let accessors = if has_accessor_trait(target, codegen_cfg) {
+        AccessorPlacement::Trait(accessor_trait_struct(target, codegen_cfg)?.name)
+    } else if generates_accessors(target, codegen_cfg) {
+        AccessorPlacement::Inherent
+    } else {
+        AccessorPlacement::None
+    };
+
+    let added_attributes = target
+        .added_attributes()
+        .into_iter()
+        .map(|a| a.into())
+        .collect::<Vec<Archetype>>();
+    let (introduced_attributes, inherited_attributes) = split_introduced(
+        target.attributes().into_iter().map(|a| a.into()).collect(),
+        &added_attributes,
+    );
+    let (introduced_flags, inherited_flags) =
+        split_introduced(target.flags(), &target.added_flags());
+
+    let inherited_accessor_traits = target
+        .ancestry()
+        .iter()
+        .filter(|a| has_accessor_trait(a, codegen_cfg))
+        .map(|a| Ok(accessor_trait_struct(a, codegen_cfg)?.name))
+        .collect::<Result<Vec<String>, CodegenError>>()?;
+
+    let own_submodule = in_own_submodule(target);
+    // concepts without their own submodule get declared in the module of their parent instead
+    let declaring_concept = if own_submodule {
+        Some(*target)
+    } else {
+        target.parents().first().cloned()
+    };
+    let module = match declaring_concept {
+        Some(concept)
+            if BuildInfo::from(concept.id())
+                .representative_module()
+                .is_some() =>
+        {
+            Some(module_file_path(&concept)?)
+        }
+        _ => None,
+    };
+
+    Ok(Explanation {
+        concept: describe(target),
+        activation: activation(target),
+        root: kgn.is_root_analogue(),
+        own_submodule,
+        file_path: archetype_file_path(target)?,
+        import_path: import_path(&kgn, codegen_cfg.yin)?,
+        id: id_assignment(target, ids)?,
+        accessors,
+        introduced_attributes,
+        inherited_attributes,
+        introduced_flags,
+        inherited_flags,
+        inherited_accessor_traits,
+        module,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::id_lock::LockedId;
+    use crate::tao::initialize_kb;
+    use zamm_yin::tao::form::Form;
+    use zamm_yin::tao::relation::attribute::Attribute;
+    use zamm_yin::tao::relation::flag::Flag;
+
+    #[test]
+    fn test_explain() {
+        initialize_kb();
+        let mut lamp = Form::archetype().individuate_as_archetype();
+        lamp.set_internal_name("explained-lamp");
+        KnowledgeGraphNode::from(lamp.id()).mark_newly_defined();
+        lamp.implement_with_doc("A source of light.");
+        let mut brightness = Attribute::archetype().individuate_as_archetype();
+        brightness.set_internal_name("explained-brightness");
+        lamp.add_attribute(&brightness);
+        let mut on = Flag::archetype().individuate_as_archetype();
+        on.set_internal_name("explained-on");
+        lamp.add_flag(&on);
+
+        let ids = IdLock {
+            retired: vec![],
+            concepts: vec![LockedId {
+                name: "explained-lamp".to_owned(),
+                id: 7,
+            }],
+        };
+        let explanation = explain(&lamp, &ids, &CodegenConfig::default()).unwrap();
+        assert_eq!(explanation.concept, "explained-lamp");
+        assert_eq!(explanation.activation, Activation::Form);
+        assert!(!explanation.root);
+        assert!(!explanation.own_submodule);
+        assert_eq!(explanation.file_path, "src/tao/form/explained_lamp_form.rs");
+        assert_eq!(explanation.import_path, "crate::tao::form::ExplainedLamp");
+        assert_eq!(explanation.id, IdAssignment::Locked(7));
+        assert_eq!(explanation.accessors, AccessorPlacement::Inherent);
+        assert_eq!(
+            explanation.introduced_attributes,
+            vec!["explained-brightness".to_owned()]
+        );
+        assert_eq!(
+            explanation.introduced_flags,
+            vec!["explained-on".to_owned()]
+        );
+        assert!(explanation.inherited_accessor_traits.is_empty());
+    }
+
+    #[test]
+    fn test_explain_attribute() {
+        initialize_kb();
+        let mut attr = Attribute::archetype().individuate_as_archetype();
+        attr.set_internal_name("explained-attr");
+        KnowledgeGraphNode::from(attr.id()).mark_newly_defined();
+        let explanation = explain(&attr, &IdLock::default(), &CodegenConfig::default()).unwrap();
+        assert_eq!(explanation.activation, Activation::Attribute);
+        assert_eq!(explanation.id, IdAssignment::Fresh);
+        assert!(!explanation.inherited_attributes.is_empty());
+    }
+}
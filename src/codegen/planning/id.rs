@@ -1,24 +1,89 @@
-use std::cell::Cell;
+use crate::codegen::id_lock::{IdLock, LockedId};
+use std::collections::BTreeMap;
 
-thread_local! {
-    static NEXT_IMPLEMENTATION_ID: Cell<usize> = Cell::new(0);
+/// Hands out concept IDs, reusing the ones recorded in the ID lock whenever possible.
+#[derive(Clone, Debug)]
+pub struct IdAllocator {
+    /// IDs from the previous build.
+    previous: IdLock,
+    /// IDs assigned during this build, mapped to the concepts they were assigned to.
+    assigned: BTreeMap<usize, String>,
+    /// The first ID that this crate gets to use.
+    first_id: usize,
+    /// The next fresh ID to hand out.
+    next_id: usize,
 }
 
-/// Grab a new implementation ID, thus incrementing the global ID counter.
-pub fn grab_new_implementation_id(yin: bool) -> usize {
-    NEXT_IMPLEMENTATION_ID.with(|id| {
-        let mut return_id = id.get();
-        id.set(return_id + 1);
-
-        if !yin && return_id == 0 {
-            // things that build on top of Yin are effectively one-indexed instead of zero-indexed
-            // like Yin is
-            return_id += 1;
-            id.set(return_id + 1);
+impl IdAllocator {
+    /// Create a new allocator that continues on from the IDs in the lock.
+    pub fn new(previous: IdLock, yin: bool) -> Self {
+        // things that build on top of Yin are effectively one-indexed instead of zero-indexed like
+        // Yin is
+        let first_id = if yin { 0 } else { 1 };
+        let next_id = previous
+            .concepts
+            .iter()
+            .map(|c| c.id)
+            .chain(previous.retired.iter().cloned())
+            .max()
+            .map(|max| max + 1)
+            .unwrap_or(first_id)
+            .max(first_id);
+        Self {
+            previous,
+            assigned: BTreeMap::new(),
+            first_id,
+            next_id,
         }
+    }
+
+    /// Grab the ID for the concept with this name, thus incrementing the ID counter if the concept
+    /// doesn't have an ID yet.
+    pub fn grab(&mut self, name: &str) -> usize {
+        let id = match self.previous.id(name) {
+            Some(locked_id) if !self.assigned.contains_key(&locked_id) => locked_id,
+            _ => self.grab_unnamed(),
+        };
+        self.assigned.insert(id, name.to_owned());
+        id
+    }
+
+    /// Grab a fresh ID that isn't tied to any concept. It won't be handed out again, and gets
+    /// retired in the lock.
+    pub fn grab_unnamed(&mut self) -> usize {
+        let new_id = self.next_id;
+        self.next_id += 1;
+        new_id
+    }
+
+    /// Record an ID that was already chosen for the concept with this name.
+    pub fn record(&mut self, name: &str, id: usize) {
+        self.next_id = self.next_id.max(id + 1);
+        self.assigned.insert(id, name.to_owned());
+    }
+
+    /// IDs that are not assigned to anything during this build, but that still need to be kept
+    /// around so that later IDs don't shift.
+    pub fn retired_ids(&self) -> Vec<usize> {
+        (self.first_id..self.next_id)
+            .filter(|id| !self.assigned.contains_key(id))
+            .collect()
+    }
 
-        return_id
-    })
+    /// The lock that records IDs assigned during this build, to be used in the next one.
+    pub fn lock(&self) -> IdLock {
+        IdLock {
+            retired: self.retired_ids(),
+            concepts: self
+                .assigned
+                .iter()
+                .map(|(id, name)| LockedId {
+                    name: name.clone(),
+                    id: *id,
+                })
+                .collect(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -27,13 +92,107 @@ mod tests {
 
     #[test]
     fn yin_id_zero_indexed() {
-        assert_eq!(grab_new_implementation_id(true), 0);
-        assert_eq!(grab_new_implementation_id(true), 1);
+        let mut ids = IdAllocator::new(IdLock::default(), true);
+        assert_eq!(ids.grab("a"), 0);
+        assert_eq!(ids.grab("b"), 1);
     }
 
     #[test]
     fn yang_yin_id_one_indexed() {
-        assert_eq!(grab_new_implementation_id(false), 1);
-        assert_eq!(grab_new_implementation_id(false), 2);
+        let mut ids = IdAllocator::new(IdLock::default(), false);
+        assert_eq!(ids.grab("a"), 1);
+        assert_eq!(ids.grab("b"), 2);
+    }
+
+    #[test]
+    fn unnamed_ids_retired() {
+        let mut ids = IdAllocator::new(IdLock::default(), false);
+        assert_eq!(ids.grab_unnamed(), 1);
+        assert_eq!(ids.grab("a"), 2);
+        assert_eq!(ids.retired_ids(), vec![1]);
+    }
+
+    #[test]
+    fn recorded_ids_not_handed_out_again() {
+        let previous = IdLock {
+            retired: vec![],
+            concepts: vec![LockedId {
+                name: "a".to_owned(),
+                id: 7,
+            }],
+        };
+        let mut ids = IdAllocator::new(previous, false);
+        ids.record("recorded", 10);
+        ids.record("taken", 7);
+        assert_eq!(ids.grab("b"), 11);
+        // the locked ID was already recorded for something else, so a fresh one gets used instead
+        assert_eq!(ids.grab("a"), 12);
+        assert_eq!(ids.retired_ids(), vec![1, 2, 3, 4, 5, 6, 8, 9]);
+    }
+
+    #[test]
+    fn locked_ids_reused() {
+        let previous = IdLock {
+            retired: vec![],
+            concepts: vec![
+                LockedId {
+                    name: "a".to_owned(),
+                    id: 1,
+                },
+                LockedId {
+                    name: "b".to_owned(),
+                    id: 2,
+                },
+            ],
+        };
+        let mut ids = IdAllocator::new(previous, false);
+        // newly inserted concept doesn't shift the existing ones
+        assert_eq!(ids.grab("new"), 3);
+        assert_eq!(ids.grab("a"), 1);
+        assert_eq!(ids.grab("b"), 2);
+        assert_eq!(ids.retired_ids(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn removed_ids_retired() {
+        let previous = IdLock {
+            retired: vec![2],
+            concepts: vec![
+                LockedId {
+                    name: "a".to_owned(),
+                    id: 1,
+                },
+                LockedId {
+                    name: "removed".to_owned(),
+                    id: 3,
+                },
+                LockedId {
+                    name: "c".to_owned(),
+                    id: 4,
+                },
+            ],
+        };
+        let mut ids = IdAllocator::new(previous, false);
+        assert_eq!(ids.grab("a"), 1);
+        assert_eq!(ids.grab("c"), 4);
+        assert_eq!(ids.grab("new"), 5);
+        assert_eq!(ids.retired_ids(), vec![2, 3]);
+        assert_eq!(
+            ids.lock().concepts,
+            vec![
+                LockedId {
+                    name: "a".to_owned(),
+                    id: 1,
+                },
+                LockedId {
+                    name: "c".to_owned(),
+                    id: 4,
+                },
+                LockedId {
+                    name: "new".to_owned(),
+                    id: 5,
+                },
+            ]
+        );
     }
 }
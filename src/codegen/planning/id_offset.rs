@@ -0,0 +1,174 @@
+use super::imports::describe;
+use crate::codegen::{CodegenConfig, CodegenError};
+use crate::tao::callbacks::implements;
+use crate::tao::form::rust_item::{Crate, CrateExtension};
+use crate::tao::perspective::{BuildInfo, BuildInfoExtension, KnowledgeGraphNode};
+use std::collections::BTreeSet;
+use zamm_yin::node_wrappers::CommonNodeTrait;
+use zamm_yin::tao::archetype::Archetype;
+use zamm_yin::tao::form::FormTrait;
+
+/// Path to the constant that a crate exports for the highest concept ID used by it and by every
+/// crate it builds on. Hyphens in the crate name are converted to underscores, the same way Cargo
+/// does for the crate's import path.
+pub fn max_id_path(crate_name: &str) -> String {
+    format!("{}::tao::YIN_MAX_ID", crate_path(crate_name))
+}
+
+/// The name that a crate is imported by in Rust code.
+fn crate_path(crate_name: &str) -> String {
+    crate_name.replace('-', "_")
+}
+
+/// Name of the crate that defined this concept, if it was imported from another crate's build
+/// manifest.
+fn defining_crate(concept: &Archetype) -> Option<String> {
+    BuildInfo::from(concept.id())
+        .crate_name()
+        .map(|name| (*name).to_owned())
+}
+
+/// The crate whose concept IDs the targets should continue numbering from. This is whichever crate
+/// defined the ancestors of the targets, and that isn't itself built on by another such crate.
+/// Concepts that only descend from Yin continue on from Yin.
+///
+/// Ancestors that were imported without a crate name, such as ones defined with `define!` between
+/// `start_imports` and `end_imports`, could belong to any crate. Falling back to Yin for them could
+/// hand out IDs that collide with the ones their crate already uses, so they're an error instead.
+pub fn id_base_crate(targets: &[Archetype]) -> Result<String, CodegenError> {
+    let mut candidates = BTreeSet::new();
+    let mut built_on = BTreeSet::new();
+    for target in targets {
+        for ancestor in target.ancestry() {
+            let ancestor_crate = match defining_crate(&ancestor) {
+                Some(ancestor_crate) => ancestor_crate,
+                None if KnowledgeGraphNode::from(ancestor.id()).is_imported() => {
+                    return Err(CodegenError::UnknownIdBase {
+                        concept: describe(&ancestor),
+                    })
+                }
+                None => continue,
+            };
+            for further_ancestor in ancestor.ancestry() {
+                if let Some(further_crate) = defining_crate(&further_ancestor) {
+                    if further_crate != ancestor_crate {
+                        built_on.insert(further_crate);
+                    }
+                }
+            }
+            candidates.insert(ancestor_crate);
+        }
+    }
+
+    let mut bases = candidates
+        .into_iter()
+        .filter(|c| !built_on.contains(c))
+        .collect::<Vec<String>>();
+    match bases.len() {
+        0 => Ok(Crate::YIN_CRATE_NAME.to_owned()),
+        1 => Ok(bases.remove(0)),
+        _ => Err(CodegenError::AmbiguousIdBase { crates: bases }),
+    }
+}
+
+/// The crate that the newly defined concepts build on, or `None` when building Yin itself, which
+/// doesn't build on anything.
+fn base_crate(codegen_cfg: &CodegenConfig) -> Result<Option<String>, CodegenError> {
+    if codegen_cfg.yin {
+        return Ok(None);
+    }
+    let newly_defined = implements()
+        .filter_map(|i| i.target())
+        .map(|t| Archetype::from(t.id()))
+        .filter(|t| KnowledgeGraphNode::from(t.id()).is_newly_defined())
+        .collect::<Vec<Archetype>>();
+    Ok(Some(id_base_crate(&newly_defined)?))
+}
+
+/// Path to the max ID constant that the IDs of newly defined concepts are offset from, or `None`
+/// when building Yin itself, whose IDs aren't offset from anything.
+pub fn id_offset(codegen_cfg: &CodegenConfig) -> Result<Option<String>, CodegenError> {
+    Ok(base_crate(codegen_cfg)?.map(|c| max_id_path(&c)))
+}
+
+/// Path to the KB initialization function that has to run before the newly defined concepts can
+/// be initialized, or `None` when building Yin itself.
+pub fn base_initializer(codegen_cfg: &CodegenConfig) -> Result<Option<String>, CodegenError> {
+    Ok(base_crate(codegen_cfg)?.map(|c| format!("{}::tao::initialize_kb", crate_path(&c))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tao::initialize_kb;
+    use zamm_yin::tao::archetype::{ArchetypeFormTrait, ArchetypeTrait};
+    use zamm_yin::tao::form::Form;
+
+    fn concept_from(parent: &Archetype, name: &str, crate_name: Option<&str>) -> Archetype {
+        let mut concept = parent.individuate_as_archetype();
+        concept.set_internal_name(name);
+        if let Some(crate_name) = crate_name {
+            BuildInfo::from(concept.id()).set_crate_name(crate_name);
+        }
+        concept
+    }
+
+    #[test]
+    fn test_max_id_path() {
+        assert_eq!(max_id_path("zamm_yin"), "zamm_yin::tao::YIN_MAX_ID");
+        assert_eq!(max_id_path("my-crate"), "my_crate::tao::YIN_MAX_ID");
+    }
+
+    #[test]
+    fn test_base_yin() {
+        initialize_kb();
+        let new = concept_from(&Form::archetype(), "layer-yin-child", None);
+        assert_eq!(id_base_crate(&[new]).unwrap(), "zamm_yin");
+    }
+
+    #[test]
+    fn test_base_stacked_crates() {
+        initialize_kb();
+        let b = concept_from(&Form::archetype(), "layer-b", Some("crate_b"));
+        let c = concept_from(&b, "layer-c", Some("crate_c"));
+        let new_under_b = concept_from(&b, "layer-new-b", None);
+        let new_under_c = concept_from(&c, "layer-new-c", None);
+        assert_eq!(id_base_crate(&[new_under_b]).unwrap(), "crate_b");
+        // crate_c builds on crate_b, so its IDs come after crate_b's
+        assert_eq!(
+            id_base_crate(&[new_under_b, new_under_c]).unwrap(),
+            "crate_c"
+        );
+    }
+
+    #[test]
+    fn test_base_ambiguous() {
+        initialize_kb();
+        let left = concept_from(&Form::archetype(), "layer-left", Some("crate_left"));
+        let right = concept_from(&Form::archetype(), "layer-right", Some("crate_right"));
+        let new_left = concept_from(&left, "layer-new-left", None);
+        let new_right = concept_from(&right, "layer-new-right", None);
+        assert!(id_base_crate(&[new_left, new_right]).is_err());
+    }
+
+    #[test]
+    fn test_base_imported_without_crate() {
+        initialize_kb();
+        let imported = concept_from(&Form::archetype(), "layer-unnamed-crate", None);
+        KnowledgeGraphNode::from(imported.id()).mark_imported();
+        let new = concept_from(&imported, "layer-new-unnamed", None);
+        assert!(matches!(
+            id_base_crate(&[new]),
+            Err(CodegenError::UnknownIdBase { concept }) if concept == "layer-unnamed-crate"
+        ));
+    }
+
+    #[test]
+    fn test_base_imported_with_crate() {
+        initialize_kb();
+        let imported = concept_from(&Form::archetype(), "layer-named-crate", Some("crate-d"));
+        KnowledgeGraphNode::from(imported.id()).mark_imported();
+        let new = concept_from(&imported, "layer-new-named", None);
+        assert_eq!(id_base_crate(&[new]).unwrap(), "crate-d");
+    }
+}
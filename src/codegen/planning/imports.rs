@@ -1,11 +1,13 @@
-use crate::codegen::StructConfig;
+use crate::codegen::{CodegenError, StructConfig};
+use crate::tao::action::Implement;
+use crate::tao::form::rust_item::{Crate, CrateExtension};
 use crate::tao::perspective::{BuildInfo, BuildInfoExtension, KnowledgeGraphNode};
 use heck::{CamelCase, SnakeCase};
 use itertools::Itertools;
 use std::rc::Rc;
 use zamm_yin::node_wrappers::CommonNodeTrait;
 use zamm_yin::tao::archetype::{Archetype, ArchetypeFormTrait, ArchetypeTrait};
-use zamm_yin::tao::form::FormTrait;
+use zamm_yin::tao::form::{Form, FormTrait};
 use zamm_yin::tao::Tao;
 
 /// Whether or not this is the root node, or marked as an analogous root node.
@@ -23,18 +25,66 @@ pub fn in_own_submodule(target: &Archetype) -> bool {
         || !target.child_archetypes().is_empty()
 }
 
+/// A human-readable description of a concept, for use in error messages.
+pub fn describe(target: &Archetype) -> String {
+    match target.internal_name() {
+        Some(name) => (*name).to_owned(),
+        None => format!("unnamed concept #{}", target.id()),
+    }
+}
+
+/// The concept that an implementation request is for.
+pub fn request_target(request: &Implement) -> Result<Archetype, CodegenError> {
+    request
+        .target()
+        .map(|target| Archetype::from(target.id()))
+        .ok_or_else(|| CodegenError::MalformedConcept {
+            concept: format!("implementation request #{}", request.id()),
+            reason: "it has no target".to_owned(),
+        })
+}
+
+/// What an implementation request is to be embodied as, such as a concept struct or a module.
+pub fn request_embodiment(request: &Implement) -> Result<Form, CodegenError> {
+    request
+        .embodiment()
+        .ok_or_else(|| CodegenError::MalformedConcept {
+            concept: format!("implementation request #{}", request.id()),
+            reason: "it has no embodiment".to_owned(),
+        })
+}
+
+/// The name of the crate that code is being generated for.
+pub fn current_crate_name() -> Result<Rc<str>, CodegenError> {
+    Crate::current()
+        .implementation_name()
+        .ok_or_else(|| CodegenError::MalformedConcept {
+            concept: "the current crate".to_owned(),
+            reason: "it has no implementation name".to_owned(),
+        })
+}
+
+/// The internal name of a concept, which all generated Rust names are derived from.
+pub fn internal_name(target: &Archetype) -> Result<Rc<str>, CodegenError> {
+    target
+        .internal_name()
+        .ok_or_else(|| CodegenError::UnnamedConcept {
+            concept: describe(target),
+        })
+}
+
 /// The import path for concepts, starting from Tao and leading to the given archetype.
-pub fn ancestor_path(target: &Archetype, separator: &str) -> String {
+pub fn ancestor_path(target: &Archetype, separator: &str) -> Result<String, CodegenError> {
     let build_info = BuildInfo::from(target.id());
     match build_info.import_path() {
         Some(existing_path) => {
             let modules = existing_path.split("::").collect::<Vec<&str>>();
-            modules
+            Ok(modules
                 .iter()
                 .skip(1)
                 .take(modules.len() - 2)
                 .format(separator)
-                .to_string()
+                .to_string())
         }
         None => {
             // parent path matters because we want to follow whatever convention the parent is
@@ -42,66 +92,66 @@ pub fn ancestor_path(target: &Archetype, separator: &str) -> String {
             let parent_path = if root_node_or_equivalent(target) {
                 None
             } else {
-                Some(ancestor_path(&target.parents().first().unwrap(), separator))
+                Some(ancestor_path(
+                    &target.parents().first().unwrap(),
+                    separator,
+                )?)
             };
 
-            let target_name = target
-                .internal_name()
-                .unwrap()
-                .to_snake_case()
-                .to_ascii_lowercase();
+            let target_name = snake_name(target)?;
             if in_own_submodule(target) {
                 match parent_path {
-                    Some(actual_parent_path) => {
-                        format!("{}{}{}", actual_parent_path, separator, target_name)
-                    }
-                    None => target_name,
+                    Some(actual_parent_path) => Ok(format!(
+                        "{}{}{}",
+                        actual_parent_path, separator, target_name
+                    )),
+                    None => Ok(target_name),
                 }
             } else {
-                parent_path.unwrap() // if not in own module, then parent must be it
+                Ok(parent_path.unwrap()) // if not in own module, then parent must be it
             }
         }
     }
 }
 
-fn snake_name(target: &Archetype) -> String {
-    target
-        .internal_name()
-        .unwrap()
-        .to_snake_case()
-        .to_ascii_lowercase()
+fn snake_name(target: &Archetype) -> Result<String, CodegenError> {
+    Ok(internal_name(target)?.to_snake_case().to_ascii_lowercase())
 }
 
 /// Get the output path for a given concept.
-pub fn archetype_file_path(target: &Archetype) -> String {
+pub fn archetype_file_path(target: &Archetype) -> Result<String, CodegenError> {
     // append _form to filename to avoid
     // https://rust-lang.github.io/rust-clippy/master/index.html#module_inception
-    format!(
+    Ok(format!(
         "src/{}/{}_form.rs",
-        ancestor_path(target, "/"),
-        snake_name(target)
-    )
+        ancestor_path(target, "/")?,
+        snake_name(target)?
+    ))
 }
 
 /// Get the output path for a given concept.
-pub fn module_file_path(target: &Archetype) -> String {
+pub fn module_file_path(target: &Archetype) -> Result<String, CodegenError> {
     // module path should always be forced if mod.rs is being generated for it
-    assert!(
-        in_own_submodule(target),
-        "Getting module file path for {:?} which is not inside its own module",
-        target
-    );
-    format!("src/{}/mod.rs", ancestor_path(target, "/"))
+    if !in_own_submodule(target) {
+        return Err(CodegenError::MalformedConcept {
+            concept: describe(target),
+            reason: "it has a module, but isn't in a module of its own".to_owned(),
+        });
+    }
+    Ok(format!("src/{}/mod.rs", ancestor_path(target, "/")?))
 }
 
 /// Returns the full import path, including the crate itself.
 ///
 /// `yin_override` needed for now because Yin is not yet fully described by its own yin.md.
 /// todo: remove once Yin supports that
-pub fn import_path(target: &KnowledgeGraphNode, yin_override: bool) -> String {
+pub fn import_path(
+    target: &KnowledgeGraphNode,
+    yin_override: bool,
+) -> Result<String, CodegenError> {
     let build_info = BuildInfo::from(target.id());
     match build_info.import_path() {
-        Some(existing_path) => (*existing_path).to_owned(),
+        Some(existing_path) => Ok((*existing_path).to_owned()),
         None => {
             let yin_crate = if build_info.crate_name().is_some() {
                 (*build_info.crate_name().unwrap()).to_owned()
@@ -115,33 +165,32 @@ pub fn import_path(target: &KnowledgeGraphNode, yin_override: bool) -> String {
             } else {
                 "zamm_yin".to_owned()
             };
-            let struct_name = target.internal_name().unwrap().to_camel_case();
-            format!(
+            let target_archetype = Archetype::from(target.id());
+            let struct_name = internal_name(&target_archetype)?.to_camel_case();
+            Ok(format!(
                 "{}::{}::{}",
                 yin_crate,
-                ancestor_path(&Archetype::from(target.id()), "::"),
+                ancestor_path(&target_archetype, "::")?,
                 struct_name
-            )
+            ))
         }
     }
 }
 
 /// Turns a concept into a struct to be imported.
-pub fn concept_to_struct(target: &Archetype, yin_override: bool) -> StructConfig {
+pub fn concept_to_struct(
+    target: &Archetype,
+    yin_override: bool,
+) -> Result<StructConfig, CodegenError> {
     let build_info = BuildInfo::from(target.id());
-    let name = build_info.implementation_name().unwrap_or_else(|| {
-        Rc::from(
-            target
-                .internal_name()
-                .unwrap_or_else(|| panic!("{:?} has no internal name", target))
-                .to_camel_case()
-                .as_str(),
-        )
-    });
-    StructConfig {
-        name: (*name).to_owned(),
-        import: import_path(&KnowledgeGraphNode::from(target.id()), yin_override),
-    }
+    let name = match build_info.implementation_name() {
+        Some(name) => (*name).to_owned(),
+        None => internal_name(target)?.to_camel_case(),
+    };
+    Ok(StructConfig {
+        name,
+        import: import_path(&KnowledgeGraphNode::from(target.id()), yin_override)?,
+    })
 }
 
 #[cfg(test)]
@@ -195,7 +244,7 @@ mod tests {
     fn folder_path_tao() {
         initialize_kb();
         assert_eq!(
-            archetype_file_path(&Tao::archetype()),
+            archetype_file_path(&Tao::archetype()).unwrap(),
             "src/tao/tao_form.rs"
         );
     }
@@ -204,7 +253,7 @@ mod tests {
     fn folder_path_attributes() {
         initialize_kb();
         assert_eq!(
-            archetype_file_path(&Attribute::archetype().into()),
+            archetype_file_path(&Attribute::archetype().into()).unwrap(),
             "src/tao/relation/attribute/attribute_form.rs"
         );
     }
@@ -213,7 +262,7 @@ mod tests {
     fn folder_path_nested() {
         initialize_kb();
         assert_eq!(
-            archetype_file_path(&Owner::archetype().into()),
+            archetype_file_path(&Owner::archetype().into()).unwrap(),
             "src/tao/relation/attribute/owner_form.rs"
         );
     }
@@ -224,7 +273,7 @@ mod tests {
         let owner = Owner::archetype();
         BuildInfo::from(owner.id()).mark_own_module();
         assert_eq!(
-            archetype_file_path(&owner.into()),
+            archetype_file_path(&owner.into()).unwrap(),
             "src/tao/relation/attribute/owner/owner_form.rs"
         );
     }
@@ -237,7 +286,7 @@ mod tests {
         let owner = Owner::archetype();
         BuildInfo::from(owner.id()).mark_own_module();
         assert_eq!(
-            archetype_file_path(&owner.into()),
+            archetype_file_path(&owner.into()).unwrap(),
             "src/tao/newfangled/module/attribute/owner/owner_form.rs"
         );
     }
@@ -245,14 +294,17 @@ mod tests {
     #[test]
     fn module_path_tao() {
         initialize_kb();
-        assert_eq!(module_file_path(&Tao::archetype()), "src/tao/mod.rs");
+        assert_eq!(
+            module_file_path(&Tao::archetype()).unwrap(),
+            "src/tao/mod.rs"
+        );
     }
 
     #[test]
     fn module_path_attributes() {
         initialize_kb();
         assert_eq!(
-            module_file_path(&Attribute::archetype().into()),
+            module_file_path(&Attribute::archetype().into()).unwrap(),
             "src/tao/relation/attribute/mod.rs"
         );
     }
@@ -263,7 +315,7 @@ mod tests {
         let owner = Owner::archetype();
         BuildInfo::from(owner.id()).mark_own_module();
         assert_eq!(
-            module_file_path(&owner.into()),
+            module_file_path(&owner.into()).unwrap(),
             "src/tao/relation/attribute/owner/mod.rs"
         );
     }
@@ -272,7 +324,7 @@ mod tests {
     fn import_path_tao() {
         initialize_kb();
         assert_eq!(
-            import_path(&KnowledgeGraphNode::from(Tao::TYPE_ID), false),
+            import_path(&KnowledgeGraphNode::from(Tao::TYPE_ID), false).unwrap(),
             "zamm_yin::tao::Tao"
         );
     }
@@ -281,7 +333,7 @@ mod tests {
     fn import_path_attributes() {
         initialize_kb();
         assert_eq!(
-            import_path(&KnowledgeGraphNode::from(Attribute::TYPE_ID), false),
+            import_path(&KnowledgeGraphNode::from(Attribute::TYPE_ID), false).unwrap(),
             "zamm_yin::tao::relation::attribute::Attribute"
         );
     }
@@ -290,7 +342,7 @@ mod tests {
     fn import_path_nested() {
         initialize_kb();
         assert_eq!(
-            import_path(&KnowledgeGraphNode::from(Owner::TYPE_ID), false),
+            import_path(&KnowledgeGraphNode::from(Owner::TYPE_ID), false).unwrap(),
             "zamm_yin::tao::relation::attribute::Owner"
         );
     }
@@ -300,7 +352,7 @@ mod tests {
         initialize_kb();
         BuildInfo::from(Owner::TYPE_ID).mark_own_module();
         assert_eq!(
-            import_path(&KnowledgeGraphNode::from(Owner::TYPE_ID), false),
+            import_path(&KnowledgeGraphNode::from(Owner::TYPE_ID), false).unwrap(),
             "zamm_yin::tao::relation::attribute::owner::Owner"
         );
     }
@@ -311,7 +363,7 @@ mod tests {
         let mut owner = KnowledgeGraphNode::from(Owner::TYPE_ID);
         owner.mark_newly_defined();
         assert_eq!(
-            import_path(&owner, false),
+            import_path(&owner, false).unwrap(),
             "crate::tao::relation::attribute::Owner"
         );
     }
@@ -325,7 +377,7 @@ mod tests {
         BuildInfo::from(Owner::TYPE_ID).mark_own_module();
         owner.mark_newly_defined();
         assert_eq!(
-            import_path(&owner, false),
+            import_path(&owner, false).unwrap(),
             "crate::tao::newfangled::module::attribute::owner::Owner"
         );
     }
@@ -342,7 +394,7 @@ mod tests {
         // been implemented as part of a dependency
         BuildInfo::from(owner.id()).set_crate_name("mycrate");
         assert_eq!(
-            import_path(&owner, false),
+            import_path(&owner, false).unwrap(),
             "mycrate::tao::newfangled::module::attribute::owner::Owner"
         );
     }
@@ -360,7 +412,7 @@ mod tests {
         type2_node.mark_newly_defined();
         BuildInfo::from(type2.id()).mark_own_module();
         assert_eq!(
-            import_path(&type2_node, false),
+            import_path(&type2_node, false).unwrap(),
             "crate::tao::hello::world::World"
         );
     }
@@ -373,14 +425,17 @@ mod tests {
         root_node.set_internal_name("my-root");
         root_node.mark_newly_defined();
         root_node.mark_root_analogue();
-        assert_eq!(import_path(&root_node, false), "crate::my_root::MyRoot");
+        assert_eq!(
+            import_path(&root_node, false).unwrap(),
+            "crate::my_root::MyRoot"
+        );
     }
 
     #[test]
     fn struct_config_tao() {
         initialize_kb();
         assert_eq!(
-            concept_to_struct(&Tao::archetype(), false),
+            concept_to_struct(&Tao::archetype(), false).unwrap(),
             StructConfig {
                 name: "Tao".to_owned(),
                 import: "zamm_yin::tao::Tao".to_owned(),
@@ -392,7 +447,7 @@ mod tests {
     fn struct_config_attributes() {
         initialize_kb();
         assert_eq!(
-            concept_to_struct(&Attribute::archetype().into(), false),
+            concept_to_struct(&Attribute::archetype().into(), false).unwrap(),
             StructConfig {
                 name: "Attribute".to_owned(),
                 import: "zamm_yin::tao::relation::attribute::Attribute".to_owned(),
@@ -404,7 +459,7 @@ mod tests {
     fn struct_config_nested() {
         initialize_kb();
         assert_eq!(
-            concept_to_struct(&Owner::archetype().into(), false),
+            concept_to_struct(&Owner::archetype().into(), false).unwrap(),
             StructConfig {
                 name: "Owner".to_owned(),
                 import: "zamm_yin::tao::relation::attribute::Owner".to_owned(),
@@ -417,7 +472,7 @@ mod tests {
         initialize_kb();
         BuildInfo::from(Owner::TYPE_ID).mark_own_module();
         assert_eq!(
-            concept_to_struct(&Owner::archetype().into(), false),
+            concept_to_struct(&Owner::archetype().into(), false).unwrap(),
             StructConfig {
                 name: "Owner".to_owned(),
                 import: "zamm_yin::tao::relation::attribute::owner::Owner".to_owned(),
@@ -431,7 +486,7 @@ mod tests {
         let owner = Owner::archetype();
         KnowledgeGraphNode::from(owner.id()).mark_newly_defined();
         assert_eq!(
-            concept_to_struct(&owner.into(), false),
+            concept_to_struct(&owner.into(), false).unwrap(),
             StructConfig {
                 name: "Owner".to_owned(),
                 import: "crate::tao::relation::attribute::Owner".to_owned(),
@@ -446,11 +501,23 @@ mod tests {
         tao_build.set_implementation_name("TaoStruct");
         tao_build.set_import_path("crate::TaoStruct");
         assert_eq!(
-            concept_to_struct(&Tao::archetype(), false),
+            concept_to_struct(&Tao::archetype(), false).unwrap(),
             StructConfig {
                 name: "TaoStruct".to_owned(),
                 import: "crate::TaoStruct".to_owned(),
             }
         );
     }
+
+    #[test]
+    fn concept_to_struct_unnamed() {
+        initialize_kb();
+        let unnamed = Tao::archetype().individuate_as_archetype();
+        match concept_to_struct(&unnamed, false) {
+            Err(CodegenError::UnnamedConcept { concept }) => {
+                assert_eq!(concept, format!("unnamed concept #{}", unnamed.id()))
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
 }
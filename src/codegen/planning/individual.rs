@@ -0,0 +1,215 @@
+use super::imports::{concept_to_struct, describe, internal_name};
+use crate::codegen::template::concept::auto_init_kb::{IndividualConfig, IndividualValue};
+use crate::codegen::template::concept::individual::IndividualAccessorConfig;
+use crate::codegen::{CodegenConfig, CodegenError};
+use crate::tao::action::Implement;
+use crate::tao::archetype::CreateImplementation;
+use crate::tao::form::rust_item::{Concept, Crate, CrateExtension};
+use crate::tao::perspective::KnowledgeGraphNode;
+use heck::SnakeCase;
+use zamm_yin::node_wrappers::{BaseNodeTrait, CommonNodeTrait};
+use zamm_yin::tao::archetype::{Archetype, ArchetypeFormTrait};
+use zamm_yin::tao::form::{Form, FormTrait};
+
+/// Whether or not this request is for implementing a named individual, as opposed to a concept.
+pub fn is_individual_request(request: &Implement) -> bool {
+    request.target().unwrap().is_individual()
+}
+
+/// The ID that was assigned to an individual during KB initialization planning.
+pub fn individual_id(individual: &Form) -> Result<usize, CodegenError> {
+    individual
+        .concept_implementation()
+        .and_then(|i| Concept::from(i.embodiment().unwrap().id()).concept_id())
+        .map(|id| *id)
+        .ok_or_else(|| CodegenError::UnimplementedIndividual {
+            individual: describe(&Archetype::from(individual.id())),
+        })
+}
+
+/// The archetype that an individual is a direct instance of.
+fn archetype_of(individual: &Form) -> Archetype {
+    *individual.parents().first().unwrap()
+}
+
+/// Everything needed to initialize a named individual, whose ID has already been assigned.
+///
+/// Only the values of attributes that the individual's archetype has get initialized.
+pub fn individual_config(
+    request: &Implement,
+    codegen_cfg: &CodegenConfig,
+) -> Result<IndividualConfig, CodegenError> {
+    let individual = Form::from(request.target().unwrap().id());
+    let archetype = archetype_of(&individual);
+
+    let mut attributes = vec![];
+    for attr in archetype.attributes() {
+        let attr_struct = concept_to_struct(&attr.into(), codegen_cfg.yin)?;
+        for outgoing in individual.base_wrapper().outgoing_nodes(attr.id()) {
+            let value = Form::from(outgoing.id());
+            let value_cfg = if value.is_individual() {
+                IndividualValue::Individual(individual_id(&value)?)
+            } else {
+                IndividualValue::Concept(concept_to_struct(
+                    &Archetype::from(value.id()),
+                    codegen_cfg.yin,
+                )?)
+            };
+            attributes.push((attr_struct.clone(), value_cfg));
+        }
+    }
+
+    Ok(IndividualConfig {
+        name: (*internal_name(&Archetype::from(individual.id()))?).to_owned(),
+        id: individual_id(&individual)?,
+        archetype: concept_to_struct(&archetype, codegen_cfg.yin)?,
+        attributes,
+    })
+}
+
+/// Treat the crates that every KB gets initialized with as named individuals imported from Yang,
+/// so that code generation knows about them like any other individual without generating them
+/// again. Crates that are already named individuals are left alone.
+///
+/// Ideally these would be declared in yin.md, but that gets built with the published version of
+/// Yang, which can't declare named individuals yet.
+pub fn import_crate_individuals() {
+    let crates = vec![
+        (Crate::yin(), Crate::YIN_CRATE_NAME, "The Yin crate."),
+        (Crate::yang(), Crate::YANG_CRATE_NAME, "The Yang crate."),
+        (
+            Crate::current(),
+            Crate::CURRENT_CRATE_INTERNAL_NAME,
+            "The crate currently being built.",
+        ),
+    ];
+    for (build_crate, name, doc) in crates {
+        let mut individual = Form::from(build_crate.id());
+        if individual.concept_implementation().is_some() {
+            continue;
+        }
+        if individual.internal_name().is_none() {
+            individual.set_internal_name(name);
+        }
+        KnowledgeGraphNode::from(individual.id()).mark_imported();
+        let request = individual.implement_with_doc(doc);
+        KnowledgeGraphNode::from(request.id()).mark_imported();
+    }
+}
+
+/// Accessors for the newly defined named individuals that are direct instances of the target,
+/// given a function that turns IDs into the Rust expressions for them.
+pub fn individual_accessors(
+    target: &Archetype,
+    id_expression: impl Fn(usize) -> String,
+) -> Result<Vec<IndividualAccessorConfig>, CodegenError> {
+    target
+        .individuals()
+        .into_iter()
+        .filter(|i| {
+            i.is_individual()
+                && KnowledgeGraphNode::from(i.id()).is_newly_defined()
+                && archetype_of(i) == *target
+        })
+        .map(|i| {
+            let name = internal_name(&Archetype::from(i.id()))?;
+            let doc = i
+                .concept_implementation()
+                .and_then(|r| r.documentation())
+                .map(|d| (*d).to_owned())
+                .unwrap_or_else(|| format!("The {} individual.", name));
+            Ok(IndividualAccessorConfig {
+                name: name.to_snake_case(),
+                doc,
+                id: id_expression(individual_id(&i)?),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::define_individual;
+    use crate::tao::initialize_kb;
+    use std::rc::Rc;
+    use zamm_yin::tao::archetype::ArchetypeTrait;
+    use zamm_yin::tao::relation::attribute::Attribute;
+
+    fn assign_id(individual: &Form, id: usize) {
+        let request = individual.concept_implementation().unwrap();
+        Concept::from(request.embodiment().unwrap().id()).set_concept_id(id);
+    }
+
+    #[test]
+    fn test_individual_config() {
+        initialize_kb();
+        let mut lamp = Form::archetype().individuate_as_archetype();
+        lamp.set_internal_name("individual-lamp");
+        let mut paired = Attribute::archetype().individuate_as_archetype();
+        paired.set_internal_name("individual-paired");
+        lamp.add_attribute(&paired);
+
+        let mut desk = define_individual(lamp, "desk-lamp", "The lamp on the desk.");
+        let floor = define_individual(lamp, "floor-lamp", "The lamp on the floor.");
+        desk.add_outgoing(paired.id(), &floor);
+        assign_id(&desk, 3);
+        assign_id(&floor, 4);
+
+        let request = desk.concept_implementation().unwrap();
+        assert!(is_individual_request(&request));
+        let cfg = individual_config(&request, &CodegenConfig::default()).unwrap();
+        assert_eq!(cfg.name, "desk-lamp");
+        assert_eq!(cfg.id, 3);
+        assert_eq!(cfg.archetype.name, "IndividualLamp");
+        assert_eq!(
+            cfg.attributes
+                .iter()
+                .map(|(a, v)| (a.name.as_str(), v))
+                .collect::<Vec<(&str, &IndividualValue)>>(),
+            vec![("IndividualPaired", &IndividualValue::Individual(4))]
+        );
+
+        let accessors = individual_accessors(&lamp, |id| format!("YIN_MAX_ID + {}", id)).unwrap();
+        assert_eq!(
+            accessors,
+            vec![
+                IndividualAccessorConfig {
+                    name: "desk_lamp".to_owned(),
+                    doc: "The lamp on the desk.".to_owned(),
+                    id: "YIN_MAX_ID + 3".to_owned(),
+                },
+                IndividualAccessorConfig {
+                    name: "floor_lamp".to_owned(),
+                    doc: "The lamp on the floor.".to_owned(),
+                    id: "YIN_MAX_ID + 4".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unimplemented_value() {
+        initialize_kb();
+        let mut lamp = Form::archetype().individuate_as_archetype();
+        lamp.set_internal_name("unimplemented-lamp");
+        let loose = lamp.individuate_as_form();
+        assert!(individual_id(&loose).is_err());
+    }
+
+    #[test]
+    fn test_import_crate_individuals() {
+        initialize_kb();
+        import_crate_individuals();
+        import_crate_individuals();
+        let yin = Form::from(Crate::yin().id());
+        assert_eq!(yin.internal_name(), Some(Rc::from(Crate::YIN_CRATE_NAME)));
+        assert!(KnowledgeGraphNode::from(yin.id()).is_imported());
+        let request = yin.concept_implementation().unwrap();
+        assert!(KnowledgeGraphNode::from(request.id()).is_imported());
+        assert_eq!(Form::from(Crate::yin().id()).implementations().len(), 1);
+        assert!(Form::from(Crate::current().id())
+            .concept_implementation()
+            .is_some());
+    }
+}
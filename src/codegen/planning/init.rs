@@ -1,11 +1,19 @@
-use super::{concept_to_struct, grab_new_implementation_id};
-use crate::codegen::template::concept::auto_init_kb::{code_init, KBInitConfig, Link};
-use crate::codegen::{output_code, CodegenConfig, StructConfig};
+use super::archetype::primary_parent;
+use super::id_offset::id_offset;
+use super::imports::internal_name;
+use super::individual::individual_config;
+use super::{concept_to_struct, IdAllocator};
+use crate::codegen::filesystem::PlannedFile;
+use crate::codegen::template::concept::auto_init_kb::{
+    code_init, IndividualConfig, KBInitConfig, Link,
+};
+use crate::codegen::template::concept::visitor::VisitorMethod;
+use crate::codegen::{output_code, CodegenConfig, CodegenError, StructConfig};
 use crate::tao::action::Implement;
 use crate::tao::archetype::CreateImplementation;
 use crate::tao::form::rust_item::Concept;
 use crate::tao::perspective::KnowledgeGraphNode;
-use heck::CamelCase;
+use heck::{CamelCase, SnakeCase};
 use zamm_yin::node_wrappers::BaseNodeTrait;
 use zamm_yin::node_wrappers::CommonNodeTrait;
 use zamm_yin::tao::archetype::{Archetype, ArchetypeFormTrait, ArchetypeTrait};
@@ -13,31 +21,54 @@ use zamm_yin::tao::form::FormTrait;
 use zamm_yin::tao::relation::attribute::has_property::{HasAttribute, HasFlag};
 use zamm_yin::tao::relation::attribute::{OwnerArchetype, ValueArchetype};
 
+/// Give the concept or individual implemented by this request an ID, unless one has already been
+/// set for it.
+fn assign_id(
+    implement: &Implement,
+    target: &Archetype,
+    ids: &mut IdAllocator,
+) -> Result<usize, CodegenError> {
+    // only set ID for user if user hasn't already set it
+    let mut concept = Concept::from(implement.embodiment().unwrap().id());
+    let target_name = internal_name(target)?;
+    Ok(match concept.concept_id() {
+        Some(existing_id) => {
+            ids.record(&target_name, *existing_id);
+            *existing_id
+        }
+        None => {
+            let new_id = ids.grab(&target_name);
+            concept.set_concept_id(new_id);
+            new_id
+        }
+    })
+}
+
 fn setup_archetype_init(
     implement: &mut Implement,
     target_type: &Archetype,
-    concepts_to_initialize: &mut Vec<StructConfig>,
+    concepts_to_initialize: &mut Vec<(usize, Archetype, StructConfig)>,
     attributes: &mut Vec<Link>,
+    ids: &mut IdAllocator,
     codegen_cfg: &CodegenConfig,
-) {
-    let has_attr = concept_to_struct(&HasAttribute::archetype().into(), codegen_cfg.yin);
-    let has_flag = concept_to_struct(&HasFlag::archetype().into(), codegen_cfg.yin);
-    let target_struct = concept_to_struct(&target_type, codegen_cfg.yin);
-    concepts_to_initialize.push(target_struct);
+) -> Result<(), CodegenError> {
+    let has_attr = concept_to_struct(&HasAttribute::archetype().into(), codegen_cfg.yin)?;
+    let has_flag = concept_to_struct(&HasFlag::archetype().into(), codegen_cfg.yin)?;
 
-    // only set ID for user if user hasn't already set it
-    let mut concept = Concept::from(implement.embodiment().unwrap().id());
-    if concept.concept_id().is_none() {
-        concept.set_concept_id(grab_new_implementation_id(codegen_cfg.yin));
-    }
+    let id = assign_id(implement, target_type, ids)?;
+    concepts_to_initialize.push((
+        id,
+        *target_type,
+        concept_to_struct(&target_type, codegen_cfg.yin)?,
+    ));
 
-    let target_struct = concept_to_struct(&target_type, codegen_cfg.yin);
+    let target_struct = concept_to_struct(&target_type, codegen_cfg.yin)?;
 
     for flag in target_type.added_flags() {
         attributes.push(Link {
             from: target_struct.clone(),
             link_type: has_flag.clone(),
-            to: concept_to_struct(&flag, codegen_cfg.yin),
+            to: concept_to_struct(&flag, codegen_cfg.yin)?,
         });
     } // todo: set flags like we set attributes down below
 
@@ -45,28 +76,66 @@ fn setup_archetype_init(
         attributes.push(Link {
             from: target_struct.clone(),
             link_type: has_attr.clone(),
-            to: concept_to_struct(&attr.into(), codegen_cfg.yin),
+            to: concept_to_struct(&attr.into(), codegen_cfg.yin)?,
         });
     }
     let mut attributes_to_check = target_type.attributes();
     attributes_to_check.push(OwnerArchetype::archetype());
     attributes_to_check.push(ValueArchetype::archetype());
     for attr in attributes_to_check {
-        let attr_struct = concept_to_struct(&Archetype::from(attr.id()), codegen_cfg.yin);
+        let attr_struct = concept_to_struct(&Archetype::from(attr.id()), codegen_cfg.yin)?;
         // use base wrapper because we want to see if the flag is set with this node, not
         // with an ancestor
         for outgoing in target_type.base_wrapper().outgoing_nodes(attr.id()) {
             attributes.push(Link {
                 from: target_struct.clone(),
                 link_type: attr_struct.clone(),
-                to: concept_to_struct(&Archetype::from(outgoing.id()), codegen_cfg.yin),
+                to: concept_to_struct(&Archetype::from(outgoing.id()), codegen_cfg.yin)?,
             });
         }
     }
+    Ok(())
+}
+
+/// Name of the visitor method for this archetype.
+fn visit_method_name(target: &Archetype) -> Result<String, CodegenError> {
+    Ok(format!("visit_{}", internal_name(target)?.to_snake_case()))
+}
+
+/// Visitor methods for the concepts to be initialized. Each one delegates to the method for the
+/// concept's primary parent, if that parent is also being initialized here.
+fn visitor_methods(
+    concepts: &[(usize, Archetype, StructConfig)],
+) -> Result<Vec<VisitorMethod>, CodegenError> {
+    concepts
+        .iter()
+        .map(|(_, target, concept)| {
+            let parent = primary_parent(target);
+            let parent = match concepts.iter().find(|(_, a, _)| *a == parent) {
+                Some((_, _, parent_struct)) => {
+                    Some((parent_struct.clone(), visit_method_name(&parent)?))
+                }
+                None => None,
+            };
+            Ok(VisitorMethod {
+                concept: concept.clone(),
+                name: visit_method_name(target)?,
+                parent,
+            })
+        })
+        .collect()
 }
 
-fn init_config(archetype_requests: &mut [Implement], codegen_cfg: &CodegenConfig) -> KBInitConfig {
-    let mut concepts_to_initialize = Vec::<StructConfig>::new();
+/// Plan the initialization of newly defined concepts and named individuals, with IDs handed out
+/// by `ids`. Implementation requests for the meta objects of those concepts, and for their
+/// modules, get created along the way.
+pub fn init_config(
+    archetype_requests: &mut [Implement],
+    individual_requests: &[Implement],
+    ids: &mut IdAllocator,
+    codegen_cfg: &CodegenConfig,
+) -> Result<KBInitConfig, CodegenError> {
+    let mut concepts_to_initialize = Vec::<(usize, Archetype, StructConfig)>::new();
     let mut attributes = Vec::<Link>::new();
 
     for implement in archetype_requests {
@@ -76,8 +145,9 @@ fn init_config(archetype_requests: &mut [Implement], codegen_cfg: &CodegenConfig
             &target_type,
             &mut concepts_to_initialize,
             &mut attributes,
+            ids,
             codegen_cfg,
-        );
+        )?;
 
         // no need to gate this behind a version since specific metas should only be defined in
         // more recent versions of Yang
@@ -89,7 +159,7 @@ fn init_config(archetype_requests: &mut [Implement], codegen_cfg: &CodegenConfig
                 // the first time.
                 let mut meta_impl = target_meta.implement();
                 KnowledgeGraphNode::from(target_meta.id()).mark_newly_defined();
-                let target_name = target_type.internal_name().unwrap();
+                let target_name = internal_name(&target_type)?;
                 let meta_name = format!("{}-archetype", target_name);
                 target_meta.set_internal_name(&meta_name);
                 meta_impl.set_documentation(&format!(
@@ -101,8 +171,9 @@ fn init_config(archetype_requests: &mut [Implement], codegen_cfg: &CodegenConfig
                     &target_meta,
                     &mut concepts_to_initialize,
                     &mut attributes,
+                    ids,
                     codegen_cfg,
-                );
+                )?;
 
                 // later on in the generation process, modules will be created
                 if !target_meta.child_archetypes().is_empty() {
@@ -115,28 +186,66 @@ fn init_config(archetype_requests: &mut [Implement], codegen_cfg: &CodegenConfig
         }
     }
 
-    KBInitConfig {
-        concepts_to_initialize,
+    // individuals can point to each other, so every one of them needs an ID before any of them
+    // can be set up
+    for implement in individual_requests {
+        assign_id(
+            implement,
+            &Archetype::from(implement.target().unwrap().id()),
+            ids,
+        )?;
+    }
+    let individuals = individual_requests
+        .iter()
+        .map(|i| individual_config(i, codegen_cfg))
+        .collect::<Result<Vec<IndividualConfig>, CodegenError>>()?;
+
+    // concepts have to be initialized in order of ID, which is not necessarily the order they were
+    // requested in when IDs are locked
+    concepts_to_initialize.sort_by_key(|(id, _, _)| *id);
+    let visitor_methods = visitor_methods(&concepts_to_initialize)?;
+    Ok(KBInitConfig {
+        concepts_to_initialize: concepts_to_initialize
+            .into_iter()
+            .map(|(_, _, concept)| concept)
+            .collect(),
         attributes,
+        individuals,
+        visitor_methods,
+        retired_ids: ids.retired_ids(),
+        id_offset: id_offset(codegen_cfg)?,
         yin: codegen_cfg.yin,
-    }
+        // only a generated root module is known not to create any other nodes during
+        // initialization
+        size_test: codegen_cfg.yin || codegen_cfg.tao_root,
+    })
 }
 
-/// Create initialization file for newly defined concepts.
-pub fn handle_init(archetype_requests: &mut [Implement], codegen_cfg: &CodegenConfig) {
-    let code = code_init(&init_config(archetype_requests, codegen_cfg));
-    output_code(&code, "src/tao/auto_init.rs", codegen_cfg);
+/// Create initialization file for newly defined concepts and named individuals, as planned by
+/// `init_config`.
+pub fn handle_init(
+    init_cfg: &KBInitConfig,
+    codegen_cfg: &CodegenConfig,
+) -> Result<PlannedFile, CodegenError> {
+    output_code(&code_init(init_cfg), "src/tao/auto_init.rs", codegen_cfg)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codegen::id_lock::{IdLock, LockedId};
+    use crate::helper::define_individual;
     use crate::tao::archetype::CreateImplementation;
     use crate::tao::initialize_kb;
+    use std::rc::Rc;
     use zamm_yin::tao::archetype::AttributeArchetypeFormTrait;
     use zamm_yin::tao::form::{Form, FormTrait};
     use zamm_yin::tao::relation::attribute::Attribute;
 
+    fn ids() -> IdAllocator {
+        IdAllocator::new(IdLock::default(), false)
+    }
+
     #[test]
     fn test_single_new_concept() {
         initialize_kb();
@@ -147,7 +256,7 @@ mod tests {
         implement.set_target(&new_concept.as_form());
         implement.set_embodiment(&Concept::new().into());
         impls.push(implement);
-        let cfg = init_config(&mut impls, &CodegenConfig::default());
+        let cfg = init_config(&mut impls, &[], &mut ids(), &CodegenConfig::default()).unwrap();
         assert_eq!(
             cfg.concepts_to_initialize.first().map(|s| s.name.clone()),
             Some("Bobby".to_owned())
@@ -183,7 +292,7 @@ mod tests {
         implement_value.set_embodiment(&Concept::new().into());
         impls.push(implement_value);
 
-        let cfg = init_config(&mut impls, &CodegenConfig::default());
+        let cfg = init_config(&mut impls, &[], &mut ids(), &CodegenConfig::default()).unwrap();
         assert_eq!(
             cfg.concepts_to_initialize
                 .iter()
@@ -217,7 +326,7 @@ mod tests {
         implement_attr.set_embodiment(&Concept::new().into());
         impls.push(implement_attr);
 
-        let cfg = init_config(&mut impls, &CodegenConfig::default());
+        let cfg = init_config(&mut impls, &[], &mut ids(), &CodegenConfig::default()).unwrap();
         // it should avoid outputting inherited attributes introduced by its ancestors
         assert_eq!(
             cfg.attributes
@@ -228,6 +337,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_visitor_methods() {
+        initialize_kb();
+        let mut animal = Form::archetype().individuate_as_archetype();
+        animal.set_internal_name("visited-animal");
+        let mut dog = animal.individuate_as_archetype();
+        dog.set_internal_name("visited-dog");
+        let mut impls = vec![animal.implement(), dog.implement()];
+
+        let cfg = init_config(&mut impls, &[], &mut ids(), &CodegenConfig::default()).unwrap();
+        assert_eq!(
+            cfg.visitor_methods
+                .iter()
+                .map(|m| (
+                    m.name.as_str(),
+                    m.parent.as_ref().map(|(_, parent)| parent.as_str())
+                ))
+                .collect::<Vec<(&str, Option<&str>)>>(),
+            vec![
+                ("visit_visited_animal", None),
+                ("visit_visited_dog", Some("visit_visited_animal")),
+            ]
+        );
+    }
+
     #[test]
     fn test_meta() {
         initialize_kb();
@@ -242,7 +376,67 @@ mod tests {
         implement.set_target(&new_type.as_form());
         implement.set_embodiment(&Concept::new().into());
 
-        init_config(&mut [implement], &CodegenConfig::default());
+        init_config(&mut [implement], &[], &mut ids(), &CodegenConfig::default()).unwrap();
         assert!(new_meta_type.concept_implementation().is_some());
     }
+
+    #[test]
+    fn test_locked_ids() {
+        initialize_kb();
+        let mut first = Form::archetype().individuate_as_archetype();
+        first.set_internal_name("first");
+        let mut second = Form::archetype().individuate_as_archetype();
+        second.set_internal_name("second");
+        let mut impls = vec![first.implement(), second.implement()];
+        let mut locked_ids = IdAllocator::new(
+            IdLock {
+                retired: vec![1],
+                concepts: vec![LockedId {
+                    name: "first".to_owned(),
+                    id: 3,
+                }],
+            },
+            false,
+        );
+
+        let cfg = init_config(&mut impls, &[], &mut locked_ids, &CodegenConfig::default()).unwrap();
+        assert_eq!(
+            cfg.concepts_to_initialize
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["First", "Second"]
+        );
+        assert_eq!(cfg.retired_ids, vec![1, 2]);
+        assert_eq!(
+            Concept::from(impls[1].embodiment().unwrap().id()).concept_id(),
+            Some(Rc::new(4))
+        );
+    }
+
+    #[test]
+    fn test_individuals() {
+        initialize_kb();
+        let mut lamp = Form::archetype().individuate_as_archetype();
+        lamp.set_internal_name("init-lamp");
+        let desk = define_individual(lamp, "init-desk-lamp", "The lamp on the desk.");
+        let mut impls = vec![lamp.implement()];
+        let individual_requests = vec![desk.concept_implementation().unwrap()];
+
+        let cfg = init_config(
+            &mut impls,
+            &individual_requests,
+            &mut ids(),
+            &CodegenConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.individuals
+                .iter()
+                .map(|i| (i.name.as_str(), i.id, i.archetype.name.as_str()))
+                .collect::<Vec<(&str, usize, &str)>>(),
+            vec![("init-desk-lamp", 2, "InitLamp")]
+        );
+        assert!(!cfg.size_test);
+    }
 }
@@ -0,0 +1,137 @@
+use super::archetype::or_form_default;
+use super::concept_to_struct;
+use crate::codegen::template::concept::inverse::InverseAccessorConfig;
+use crate::codegen::{CodegenConfig, CodegenError};
+use crate::tao::perspective::BuildInfo;
+use std::collections::HashSet;
+use std::rc::Rc;
+use zamm_yin::node_wrappers::CommonNodeTrait;
+use zamm_yin::tao::archetype::{
+    Archetype, ArchetypeFormTrait, ArchetypeTrait, AttributeArchetype, AttributeArchetypeFormTrait,
+};
+use zamm_yin::tao::relation::attribute::Attribute;
+
+/// Have an inverse accessor generated on the attribute's value archetype, which retrieves every
+/// owner that points to the value through this attribute.
+///
+/// Hereditary attributes also count the owners of the value's ancestors, and single-valued
+/// attributes only count owners whose current value is this one. The value archetype has to be
+/// generated in the same build, or else there's nowhere to put the accessor.
+pub fn set_inverse_accessor(attr: &AttributeArchetype, name: &str, doc: &str) {
+    let mut info = BuildInfo::from(attr.id());
+    info.set_inverse_accessor_name(name);
+    info.set_inverse_accessor_documentation(doc);
+}
+
+/// The name and documentation of the attribute's inverse accessor, if it has one. Child
+/// attributes don't inherit this, or else they'd generate accessors with clashing names.
+pub fn inverse_accessor(attr: &AttributeArchetype) -> Option<(Rc<str>, Rc<str>)> {
+    let info = BuildInfo::from(attr.id());
+    Some((
+        info.inverse_accessor_name()?,
+        info.inverse_accessor_documentation()?,
+    ))
+}
+
+/// Every attribute that currently exists, in order of ID.
+fn all_attributes() -> Vec<AttributeArchetype> {
+    let mut visited = HashSet::new();
+    let mut to_visit = vec![Attribute::archetype()];
+    while let Some(next) = to_visit.pop() {
+        for child in next.child_archetypes() {
+            if visited.insert(child.id()) {
+                to_visit.push(child);
+            }
+        }
+    }
+    let mut ids = visited.into_iter().collect::<Vec<usize>>();
+    ids.sort_unstable();
+    ids.into_iter().map(AttributeArchetype::from).collect()
+}
+
+/// Configs for the inverse accessors that should be generated on the target, one for every
+/// attribute with an inverse accessor whose values are of the target type.
+pub fn inverse_configs(
+    target: &Archetype,
+    codegen_cfg: &CodegenConfig,
+) -> Result<Vec<InverseAccessorConfig>, CodegenError> {
+    let mut configs = vec![];
+    for attr in all_attributes() {
+        let (name, doc) = match inverse_accessor(&attr) {
+            Some(inverse) => inverse,
+            None => continue,
+        };
+        if or_form_default(attr.value_archetype()) != *target {
+            continue;
+        }
+        configs.push(InverseAccessorConfig {
+            property_name: name,
+            public: true,
+            doc,
+            attr: concept_to_struct(&attr.into(), codegen_cfg.yin)?,
+            owner_type: concept_to_struct(
+                &or_form_default(attr.owner_archetype()),
+                codegen_cfg.yin,
+            )?,
+            value_type: concept_to_struct(target, codegen_cfg.yin)?,
+            hereditary: !attr.is_nonhereditary_attr(),
+            multi_valued: attr.is_multi_valued_attr(),
+        });
+    }
+    Ok(configs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tao::initialize_kb;
+    use zamm_yin::tao::form::Form;
+
+    #[test]
+    fn test_inverse_accessor_not_inherited() {
+        initialize_kb();
+        let mut contains = Attribute::archetype().individuate_as_archetype();
+        contains.set_internal_name("inverse-contains");
+        set_inverse_accessor(&contains, "contained_in", "the boxes that contain this.");
+        let child = contains.individuate_as_archetype();
+
+        assert_eq!(
+            inverse_accessor(&contains),
+            Some((
+                Rc::from("contained_in"),
+                Rc::from("the boxes that contain this.")
+            ))
+        );
+        assert_eq!(inverse_accessor(&child), None);
+    }
+
+    #[test]
+    fn test_inverse_configs() {
+        initialize_kb();
+        let mut shelf = Form::archetype().individuate_as_archetype();
+        shelf.set_internal_name("inverse-shelf");
+        let mut book = Form::archetype().individuate_as_archetype();
+        book.set_internal_name("inverse-book");
+        let mut holds = Attribute::archetype().individuate_as_archetype();
+        holds.set_internal_name("inverse-holds");
+        holds.set_owner_archetype(&shelf);
+        holds.set_value_archetype(&book);
+        holds.mark_multi_valued_attr();
+        shelf.add_attribute(&holds);
+        set_inverse_accessor(&holds, "held_by", "the shelves that hold this book.");
+
+        let configs = inverse_configs(&book, &CodegenConfig::default()).unwrap();
+        assert_eq!(configs.len(), 1);
+        let cfg = &configs[0];
+        assert_eq!(&*cfg.property_name, "held_by");
+        assert_eq!(cfg.attr.name, "InverseHolds");
+        assert_eq!(cfg.owner_type.name, "InverseShelf");
+        assert_eq!(cfg.value_type.name, "InverseBook");
+        assert!(cfg.hereditary);
+        assert!(cfg.multi_valued);
+
+        assert!(inverse_configs(&shelf, &CodegenConfig::default())
+            .unwrap()
+            .is_empty());
+    }
+}
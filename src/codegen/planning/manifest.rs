@@ -0,0 +1,551 @@
+use super::accessor_trait::{has_accessor_trait, mark_accessor_trait};
+use super::imports::{concept_to_struct, in_own_submodule, internal_name, root_node_or_equivalent};
+use super::individual::individual_id;
+use super::inverse::{inverse_accessor, set_inverse_accessor};
+use crate::codegen::manifest::{
+    BuildManifest, ConceptBuildInfo, IndividualBuildInfo, InverseBuildInfo, ModuleBuildInfo,
+};
+use crate::codegen::{CodegenConfig, CodegenError};
+use crate::tao::action::Implement;
+use crate::tao::archetype::rust_item_archetype::DataArchetype;
+use crate::tao::archetype::CreateImplementation;
+use crate::tao::form::rust_item::{
+    Concept, Crate, CrateExtension, EnumVariant, Module, ModuleExtension,
+};
+use crate::tao::perspective::{BuildInfo, BuildInfoExtension, KnowledgeGraphNode};
+use std::convert::TryFrom;
+use zamm_yin::node_wrappers::CommonNodeTrait;
+use zamm_yin::tao::archetype::{Archetype, ArchetypeFormTrait, AttributeArchetype};
+use zamm_yin::tao::form::{Form, FormTrait};
+
+/// Replace the `crate` prefix of a locally defined path with the name of the crate, so that the
+/// path also works from outside the crate.
+fn absolute_path(path: &str, crate_name: &str) -> String {
+    if path == "crate" || path.starts_with("crate::") {
+        format!("{}{}", crate_name, &path["crate".len()..])
+    } else {
+        path.to_owned()
+    }
+}
+
+/// Turn a module's re-export into a full path that a downstream crate can use as well.
+///
+/// Re-exports that start with one of the module's own submodules are rewritten to be re-exported
+/// from the module itself, because that's where downstream crates will be able to find them.
+pub fn absolute_re_export(module: &Module, module_path: &str, re_export: &str) -> String {
+    let segments = re_export.split("::").collect::<Vec<&str>>();
+    let from_submodule = segments.len() > 1
+        && module
+            .submodules()
+            .iter()
+            .any(|s| ModuleExtension::implementation_name(s).as_deref() == Some(segments[0]));
+    if from_submodule {
+        format!("{}::{}", module_path, segments.last().unwrap())
+    } else {
+        re_export.to_owned()
+    }
+}
+
+/// The name of the crate currently being built.
+fn current_crate_name() -> String {
+    Crate::current()
+        .implementation_name()
+        .map(|name| (*name).to_owned())
+        .unwrap_or_else(|| "crate".to_owned())
+}
+
+/// The full import path of the module that the given archetype is the most prominent member of.
+pub fn module_import_path(
+    primary: &Archetype,
+    codegen_cfg: &CodegenConfig,
+) -> Result<String, CodegenError> {
+    let struct_path = absolute_path(
+        &concept_to_struct(primary, codegen_cfg.yin)?.import,
+        &current_crate_name(),
+    );
+    // archetypes with their own module live directly inside of it
+    Ok(struct_path
+        .rsplitn(2, "::")
+        .last()
+        .unwrap_or(&struct_path)
+        .to_owned())
+}
+
+/// Build information for a single archetype implementation request.
+fn concept_build_info(
+    request: &Implement,
+    codegen_cfg: &CodegenConfig,
+) -> Result<ConceptBuildInfo, CodegenError> {
+    let target = Archetype::from(request.target().unwrap().id());
+    let parent = if root_node_or_equivalent(&target) {
+        None
+    } else {
+        match target.parents().first() {
+            Some(parent) => Some((*internal_name(parent)?).to_owned()),
+            None => None,
+        }
+    };
+    let struct_config = concept_to_struct(&target, codegen_cfg.yin)?;
+    Ok(ConceptBuildInfo {
+        name: (*internal_name(&target)?).to_owned(),
+        parent,
+        import_path: absolute_path(&struct_config.import, &current_crate_name()),
+        implementation_name: struct_config.name,
+        own_module: in_own_submodule(&target),
+        accessor_trait: has_accessor_trait(&target, codegen_cfg),
+        variants: DataArchetype::from(target.id())
+            .variants()
+            .iter()
+            .map(|v| v.internal_name().map(|n| (*n).to_owned()))
+            .collect::<Option<Vec<String>>>()
+            .unwrap_or_default(),
+        inverse: inverse_accessor(&AttributeArchetype::from(target.id())).map(|(name, doc)| {
+            InverseBuildInfo {
+                name: (*name).to_owned(),
+                doc: (*doc).to_owned(),
+            }
+        }),
+    })
+}
+
+/// Build information for a single module implementation request.
+fn module_build_info(
+    request: &Implement,
+    codegen_cfg: &CodegenConfig,
+) -> Result<ModuleBuildInfo, CodegenError> {
+    let module = Module::from(request.embodiment().unwrap().id());
+    let primary = Archetype::from(module.most_prominent_member().unwrap().id());
+    let module_path = module_import_path(&primary, codegen_cfg)?;
+    let implementation_name = match ModuleExtension::implementation_name(&module) {
+        Some(name) => (*name).to_owned(),
+        None => module_path.rsplit("::").next().unwrap().to_owned(),
+    };
+    Ok(ModuleBuildInfo {
+        concept: (*internal_name(&primary)?).to_owned(),
+        implementation_name,
+        doc: request.documentation().map(|d| (*d).to_owned()),
+        re_exports: module
+            .re_exports()
+            .iter()
+            .map(|r| absolute_re_export(&module, &module_path, r))
+            .collect(),
+    })
+}
+
+/// Build information for a single named individual implementation request, whose ID has already
+/// been assigned.
+fn individual_build_info(request: &Implement) -> Result<IndividualBuildInfo, CodegenError> {
+    let individual = Form::from(request.target().unwrap().id());
+    let archetype = *individual.parents().first().unwrap();
+    Ok(IndividualBuildInfo {
+        name: (*internal_name(&Archetype::from(individual.id()))?).to_owned(),
+        archetype: (*internal_name(&archetype)?).to_owned(),
+        id: individual_id(&individual)?,
+    })
+}
+
+/// Collect the build information for everything generated in this build.
+pub fn build_manifest(
+    archetype_requests: &[Implement],
+    module_requests: &[Implement],
+    individual_requests: &[Implement],
+    codegen_cfg: &CodegenConfig,
+) -> Result<BuildManifest, CodegenError> {
+    let mut manifest = BuildManifest {
+        crate_name: current_crate_name(),
+        ..BuildManifest::default()
+    };
+    for request in archetype_requests {
+        manifest
+            .concepts
+            .push(concept_build_info(request, codegen_cfg)?);
+    }
+    for request in module_requests {
+        manifest
+            .modules
+            .push(module_build_info(request, codegen_cfg)?);
+    }
+    for request in individual_requests {
+        manifest.individuals.push(individual_build_info(request)?);
+    }
+    Ok(manifest)
+}
+
+/// Find the concept described by the build information, creating it if it doesn't exist yet.
+/// Returns `None` if the concept can't be created yet because its parent doesn't exist yet.
+fn find_or_create(info: &ConceptBuildInfo) -> Option<Archetype> {
+    if let Ok(existing) = Archetype::try_from(info.name.as_str()) {
+        return Some(existing);
+    }
+    let parent = match &info.parent {
+        Some(parent_name) => Archetype::try_from(parent_name.as_str()).ok()?,
+        None => return None,
+    };
+    let mut concept = parent.individuate_as_archetype();
+    concept.set_internal_name(&info.name);
+    KnowledgeGraphNode::from(concept.id()).mark_imported();
+    Some(concept)
+}
+
+/// Record the build information for a concept.
+fn load_concept(concept: &Archetype, info: &ConceptBuildInfo, crate_name: &str) {
+    let mut build_info = BuildInfo::from(concept.id());
+    build_info.set_crate_name(crate_name);
+    build_info.set_import_path(&info.import_path);
+    build_info.set_implementation_name(&info.implementation_name);
+    if info.own_module {
+        build_info.mark_own_module();
+    }
+    if info.accessor_trait {
+        mark_accessor_trait(concept);
+    }
+    let mut data = DataArchetype::from(concept.id());
+    if data.variants().is_empty() {
+        for name in &info.variants {
+            let mut variant = EnumVariant::new();
+            variant.set_internal_name(name);
+            data.add_variant(&variant);
+        }
+    }
+    if let Some(inverse) = &info.inverse {
+        let attr = AttributeArchetype::from(concept.id());
+        if inverse_accessor(&attr).is_none() {
+            set_inverse_accessor(&attr, &inverse.name, &inverse.doc);
+        }
+    }
+}
+
+/// Recreate a named individual that was generated as part of another crate, along with the ID it
+/// was initialized with there.
+fn load_individual(info: &IndividualBuildInfo) -> Result<(), CodegenError> {
+    if Archetype::try_from(info.name.as_str()).is_ok() {
+        return Ok(());
+    }
+    let archetype = Archetype::try_from(info.archetype.as_str()).map_err(|_| {
+        CodegenError::InvalidManifest {
+            reason: format!(
+                "individual {} is of unknown archetype {}",
+                info.name, info.archetype
+            ),
+        }
+    })?;
+    let mut individual = archetype.individuate_as_form();
+    individual.set_internal_name(&info.name);
+    KnowledgeGraphNode::from(individual.id()).mark_imported();
+    let request = individual.implement();
+    KnowledgeGraphNode::from(request.id()).mark_imported();
+    Concept::from(request.embodiment().unwrap().id()).set_concept_id(info.id);
+    Ok(())
+}
+
+/// Load build information for concepts, modules, and named individuals that were generated as part of another crate.
+///
+/// Concepts that don't exist yet get created, so this should be called between `start_imports`
+/// and `end_imports` so that everything here gets marked as imported.
+pub fn load_manifest(manifest: &BuildManifest) -> Result<(), CodegenError> {
+    let mut pending = manifest.concepts.iter().collect::<Vec<&ConceptBuildInfo>>();
+    // parents may be listed after their children, so keep going until nothing changes
+    while !pending.is_empty() {
+        let mut remaining = Vec::new();
+        for info in &pending {
+            match find_or_create(info) {
+                Some(concept) => load_concept(&concept, info, &manifest.crate_name),
+                None => remaining.push(*info),
+            }
+        }
+        if remaining.len() == pending.len() {
+            return Err(CodegenError::InvalidManifest {
+                reason: format!(
+                    "{} cannot be created because its parent is unknown",
+                    remaining
+                        .iter()
+                        .map(|info| info.name.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                ),
+            });
+        }
+        pending = remaining;
+    }
+
+    for info in &manifest.modules {
+        let concept = Archetype::try_from(info.concept.as_str()).map_err(|_| {
+            CodegenError::InvalidManifest {
+                reason: format!(
+                    "module {} is for unknown concept {}",
+                    info.implementation_name, info.concept
+                ),
+            }
+        })?;
+        let mut module = concept.impl_mod(info.doc.as_deref().unwrap_or_default());
+        BuildInfo::from(module.id()).set_implementation_name(&info.implementation_name);
+        for re_export in &info.re_exports {
+            module.add_re_export(re_export);
+        }
+    }
+
+    for info in &manifest.individuals {
+        load_individual(info)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::planning::adopt_imported_modules;
+    use crate::codegen::planning::enum_data::enum_variants;
+    use crate::tao::initialize_kb;
+    use std::rc::Rc;
+    use zamm_yin::tao::archetype::ArchetypeTrait;
+    use zamm_yin::tao::relation::attribute::Attribute;
+
+    #[test]
+    fn test_absolute_path() {
+        assert_eq!(
+            absolute_path("crate::tao::form::Crate", "zamm_yang"),
+            "zamm_yang::tao::form::Crate"
+        );
+        assert_eq!(
+            absolute_path("zamm_yin::tao::form::Form", "zamm_yang"),
+            "zamm_yin::tao::form::Form"
+        );
+        assert_eq!(
+            absolute_path("crater::Crater", "zamm_yang"),
+            "crater::Crater"
+        );
+    }
+
+    #[test]
+    fn test_absolute_re_export() {
+        initialize_kb();
+        let mut module = Module::new();
+        module.has_extension("module_extension::ModuleExtension");
+        assert_eq!(
+            absolute_re_export(
+                &module,
+                "zamm_yang::tao::form::rust_item",
+                "module_extension::ModuleExtension"
+            ),
+            "zamm_yang::tao::form::rust_item::ModuleExtension"
+        );
+        assert_eq!(
+            absolute_re_export(
+                &module,
+                "zamm_yang::tao::form::rust_item",
+                "zamm_yin::tao::form::FormTrait"
+            ),
+            "zamm_yin::tao::form::FormTrait"
+        );
+    }
+
+    #[test]
+    fn test_build_manifest() {
+        initialize_kb();
+        Crate::current().set_implementation_name("manifest_crate");
+        let mut new_concept = Form::archetype().individuate_as_archetype();
+        new_concept.set_internal_name("manifest-concept");
+        KnowledgeGraphNode::from(new_concept.id()).mark_newly_defined();
+        let request = new_concept.implement_with_doc("A concept to save.");
+
+        let manifest = build_manifest(&[request], &[], &[], &CodegenConfig::default()).unwrap();
+        assert_eq!(manifest.crate_name, "manifest_crate");
+        assert_eq!(
+            manifest.concepts,
+            vec![ConceptBuildInfo {
+                name: "manifest-concept".to_owned(),
+                parent: Some("form".to_owned()),
+                import_path: "manifest_crate::tao::form::ManifestConcept".to_owned(),
+                implementation_name: "ManifestConcept".to_owned(),
+                own_module: false,
+                accessor_trait: false,
+                variants: vec![],
+                inverse: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_build_manifest_inverse() {
+        initialize_kb();
+        let mut holds = Attribute::archetype().individuate_as_archetype();
+        holds.set_internal_name("manifest-holds");
+        KnowledgeGraphNode::from(holds.id()).mark_newly_defined();
+        set_inverse_accessor(&holds, "held_by", "the shelves that hold this.");
+        let request = holds.implement_with_doc("An attribute to save.");
+
+        let manifest = build_manifest(&[request], &[], &[], &CodegenConfig::default()).unwrap();
+        assert_eq!(
+            manifest.concepts[0].inverse,
+            Some(InverseBuildInfo {
+                name: "held_by".to_owned(),
+                doc: "the shelves that hold this.".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_manifest() {
+        initialize_kb();
+        let manifest = BuildManifest {
+            crate_name: "upstream".to_owned(),
+            generated_files: vec![],
+            concepts: vec![
+                // child listed before its parent on purpose
+                ConceptBuildInfo {
+                    name: "loaded-child".to_owned(),
+                    parent: Some("loaded-parent".to_owned()),
+                    import_path: "upstream::tao::form::loaded_parent::LoadedChild".to_owned(),
+                    implementation_name: "LoadedChild".to_owned(),
+                    own_module: false,
+                    accessor_trait: false,
+                    variants: vec!["on".to_owned(), "off".to_owned()],
+                    inverse: None,
+                },
+                ConceptBuildInfo {
+                    name: "loaded-parent".to_owned(),
+                    parent: Some("form".to_owned()),
+                    import_path: "upstream::tao::form::loaded_parent::LoadedParent".to_owned(),
+                    implementation_name: "LoadedParent".to_owned(),
+                    own_module: true,
+                    accessor_trait: true,
+                    variants: vec![],
+                    inverse: None,
+                },
+                ConceptBuildInfo {
+                    name: "loaded-holds".to_owned(),
+                    parent: Some("attribute".to_owned()),
+                    import_path: "upstream::tao::relation::attribute::LoadedHolds".to_owned(),
+                    implementation_name: "LoadedHolds".to_owned(),
+                    own_module: false,
+                    accessor_trait: false,
+                    variants: vec![],
+                    inverse: Some(InverseBuildInfo {
+                        name: "held_by".to_owned(),
+                        doc: "the parents that hold this.".to_owned(),
+                    }),
+                },
+            ],
+            modules: vec![ModuleBuildInfo {
+                concept: "loaded-parent".to_owned(),
+                implementation_name: "loaded_parent".to_owned(),
+                doc: Some("Loaded from upstream.".to_owned()),
+                re_exports: vec!["upstream::tao::form::loaded_parent::Extension".to_owned()],
+            }],
+            individuals: vec![IndividualBuildInfo {
+                name: "loaded-individual".to_owned(),
+                archetype: "loaded-child".to_owned(),
+                id: 77,
+            }],
+        };
+        load_manifest(&manifest).unwrap();
+
+        let parent = Archetype::try_from("loaded-parent").unwrap();
+        let child = Archetype::try_from("loaded-child").unwrap();
+        assert!(child.has_parent(parent));
+        assert!(KnowledgeGraphNode::from(child.id()).is_imported());
+        let parent_info = BuildInfo::from(parent.id());
+        assert!(parent_info.is_own_module());
+        assert!(has_accessor_trait(&parent, &CodegenConfig::default()));
+        assert!(!has_accessor_trait(&child, &CodegenConfig::default()));
+        assert_eq!(
+            enum_variants(&child)
+                .unwrap()
+                .iter()
+                .map(|v| (*v.name).to_owned())
+                .collect::<Vec<String>>(),
+            vec!["On", "Off"]
+        );
+        assert_eq!(parent_info.crate_name(), Some(Rc::from("upstream")));
+        assert_eq!(
+            parent_info.import_path(),
+            Some(Rc::from("upstream::tao::form::loaded_parent::LoadedParent"))
+        );
+        let module = parent_info.representative_module().unwrap();
+        assert_eq!(
+            module.re_exports(),
+            vec![Rc::from("upstream::tao::form::loaded_parent::Extension")]
+        );
+        assert_eq!(
+            inverse_accessor(&AttributeArchetype::try_from("loaded-holds").unwrap()),
+            Some((Rc::from("held_by"), Rc::from("the parents that hold this.")))
+        );
+        let individual = Form::try_from("loaded-individual").unwrap();
+        assert!(individual.has_parent(child));
+        assert!(KnowledgeGraphNode::from(individual.id()).is_imported());
+        assert_eq!(individual_id(&individual).unwrap(), 77);
+    }
+
+    #[test]
+    fn test_adopt_loaded_module() {
+        initialize_kb();
+        let manifest = BuildManifest {
+            crate_name: "upstream".to_owned(),
+            generated_files: vec![],
+            concepts: vec![ConceptBuildInfo {
+                name: "adoptive-parent".to_owned(),
+                parent: Some("form".to_owned()),
+                import_path: "upstream::tao::form::adoptive_parent::AdoptiveParent".to_owned(),
+                implementation_name: "AdoptiveParent".to_owned(),
+                own_module: true,
+                accessor_trait: false,
+                variants: vec![],
+                inverse: None,
+            }],
+            modules: vec![ModuleBuildInfo {
+                concept: "adoptive-parent".to_owned(),
+                implementation_name: "adoptive_parent".to_owned(),
+                doc: Some("Loaded from upstream.".to_owned()),
+                re_exports: vec![],
+            }],
+            individuals: vec![],
+        };
+        load_manifest(&manifest).unwrap();
+        for implement in Archetype::try_from("adoptive-parent")
+            .unwrap()
+            .implementations()
+        {
+            KnowledgeGraphNode::from(implement.id()).mark_imported();
+        }
+
+        let parent = Archetype::try_from("adoptive-parent").unwrap();
+        let mut child = parent.individuate_as_archetype();
+        child.set_internal_name("adopted-child");
+        KnowledgeGraphNode::from(child.id()).mark_newly_defined();
+        let request = child.implement_with_doc("A child defined downstream.");
+        adopt_imported_modules(&[request]).unwrap();
+
+        let local_modules = parent
+            .implementations()
+            .into_iter()
+            .filter(|i| !KnowledgeGraphNode::from(i.id()).is_imported())
+            .collect::<Vec<Implement>>();
+        assert_eq!(local_modules.len(), 1);
+        assert_eq!(
+            local_modules[0].documentation(),
+            Some(Rc::from("Loaded from upstream."))
+        );
+    }
+
+    #[test]
+    fn test_load_manifest_unknown_parent() {
+        initialize_kb();
+        let manifest = BuildManifest {
+            crate_name: "upstream".to_owned(),
+            generated_files: vec![],
+            concepts: vec![ConceptBuildInfo {
+                name: "orphan".to_owned(),
+                parent: Some("nonexistent".to_owned()),
+                import_path: "upstream::tao::Orphan".to_owned(),
+                implementation_name: "Orphan".to_owned(),
+                own_module: false,
+                accessor_trait: false,
+                variants: vec![],
+                inverse: None,
+            }],
+            modules: vec![],
+            individuals: vec![],
+        };
+        assert!(load_manifest(&manifest).is_err());
+    }
+}
@@ -1,13 +1,35 @@
+mod accessor_trait;
 mod archetype;
+mod builder;
+mod cardinality;
+mod dot;
+mod enum_data;
+mod explain;
 mod id;
+mod id_offset;
 mod imports;
+mod individual;
 mod init;
+mod inverse;
+mod manifest;
 mod module;
+mod spec;
+mod validation;
 
-use id::grab_new_implementation_id;
 use imports::{concept_to_struct, in_own_submodule};
 
 pub use archetype::code_archetype;
+pub use builder::mark_required_attr;
+pub use cardinality::{set_max_values, set_min_values};
+pub use dot::dot_config;
+pub use explain::{explain, AccessorPlacement, Activation, Explanation, IdAssignment};
+pub use id::IdAllocator;
+pub use id_offset::{id_base_crate, max_id_path};
 pub use imports::{archetype_file_path, module_file_path};
-pub use init::handle_init;
-pub use module::code_module;
+pub use individual::{import_crate_individuals, is_individual_request};
+pub use init::{handle_init, init_config};
+pub use inverse::set_inverse_accessor;
+pub use manifest::{build_manifest, load_manifest};
+pub use module::{adopt_imported_modules, code_module, complete_module_tree, module_target};
+pub use spec::load_spec;
+pub use validation::validate_implementations;
@@ -1,24 +1,164 @@
-use super::imports::import_path;
+use super::accessor_trait::has_accessor_trait;
+use super::enum_data::is_enum;
+use super::id_offset::base_initializer;
+use super::imports::{
+    describe, import_path, internal_name, request_embodiment, request_target,
+    root_node_or_equivalent,
+};
 use super::in_own_submodule;
 use crate::codegen::template::concept::archetype_module::{
     code_archetype_module, ArchetypeModuleConfig,
 };
+use crate::codegen::{CodegenConfig, CodegenError};
 use crate::tao::action::Implement;
+use crate::tao::archetype::CreateImplementation;
+use crate::tao::callbacks::archetypes_to_implement;
 use crate::tao::form::rust_item::{Module, ModuleExtension};
 use crate::tao::perspective::{BuildInfo, BuildInfoExtension, KnowledgeGraphNode};
+use heck::CamelCase;
+use std::rc::Rc;
 use zamm_yin::node_wrappers::CommonNodeTrait;
-use zamm_yin::tao::archetype::{Archetype, ArchetypeFormTrait};
+use zamm_yin::tao::archetype::{Archetype, ArchetypeFormTrait, ArchetypeTrait};
+use zamm_yin::tao::form::FormTrait;
+use zamm_yin::tao::Tao;
+
+/// Retrieve the request to implement a module for this concept, if there is one with the given
+/// import status.
+fn module_request(target: &Archetype, imported: bool) -> Result<Option<Implement>, CodegenError> {
+    for request in target.implementations() {
+        if request_embodiment(&request)?.has_ancestor(Module::archetype().into())
+            && KnowledgeGraphNode::from(request.id()).is_imported() == imported
+        {
+            return Ok(Some(request));
+        }
+    }
+    Ok(None)
+}
+
+/// The module that a module implementation request is for, along with the archetype that the
+/// module is named after.
+pub fn module_target(request: &Implement) -> Result<(Module, Archetype), CodegenError> {
+    let module = Module::from(request_embodiment(request)?.id());
+    match module.most_prominent_member() {
+        Some(member) => Ok((module, Archetype::from(member.id()))),
+        None => Err(CodegenError::MalformedConcept {
+            concept: describe(&request_target(request)?),
+            reason: "its module has no most prominent member to be named after".to_owned(),
+        }),
+    }
+}
+
+/// The name that a module gets declared with in its parent module.
+fn module_name(module: &Module) -> Result<String, CodegenError> {
+    match ModuleExtension::implementation_name(module) {
+        Some(name) => Ok((*name).to_owned()),
+        None => Err(CodegenError::MalformedConcept {
+            concept: describe(&Archetype::from(module.id())),
+            reason: "it is a module without an implementation name".to_owned(),
+        }),
+    }
+}
+
+/// Newly defined concepts need to be declared in the modules of their ancestors, even when those
+/// ancestors were defined in another crate. For every such ancestor that only has an imported
+/// module, implement that module locally as well with the same documentation and re-exports, so
+/// that the module doesn't have to be declared all over again.
+pub fn adopt_imported_modules(archetype_requests: &[Implement]) -> Result<(), CodegenError> {
+    for request in archetype_requests {
+        let target = request_target(request)?;
+        if !KnowledgeGraphNode::from(target.id()).is_newly_defined() {
+            continue;
+        }
+        let mut ancestor = target;
+        while let Some(parent) = ancestor.parents().first().cloned() {
+            ancestor = parent;
+            if root_node_or_equivalent(&ancestor) {
+                break;
+            }
+            if KnowledgeGraphNode::from(ancestor.id()).is_newly_defined()
+                || module_request(&ancestor, false)?.is_some()
+            {
+                continue;
+            }
+            let imported_request = match module_request(&ancestor, true)? {
+                Some(imported_request) => imported_request,
+                None => continue,
+            };
+            let imported_module = Module::from(request_embodiment(&imported_request)?.id());
+            let doc = imported_request
+                .documentation()
+                .unwrap_or_else(|| Rc::from(""));
+            let mut adopted_module = ancestor.impl_mod(&doc);
+            if let Some(name) = ModuleExtension::implementation_name(&imported_module) {
+                BuildInfo::from(adopted_module.id()).set_implementation_name(&name);
+            }
+            for re_export in imported_module.re_exports() {
+                adopted_module.add_re_export(&re_export);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Documentation for a module that was implemented only because it leads to newly defined
+/// concepts.
+fn intermediate_module_doc(ancestor: &Archetype) -> Result<String, CodegenError> {
+    if ancestor.id() == Tao::TYPE_ID {
+        Ok("Concepts of this crate, along with those of every crate it builds on.".to_owned())
+    } else {
+        Ok(format!(
+            "{} and its subtypes.",
+            internal_name(ancestor)?.to_camel_case()
+        ))
+    }
+}
+
+/// Implement a module for every ancestor that newly defined concepts get declared under, all the
+/// way up to the root, so that the entire module tree down to those concepts gets generated.
+/// Ancestors that already have a local module, including adopted ones, are left as they are.
+pub fn complete_module_tree(archetype_requests: &[Implement]) -> Result<(), CodegenError> {
+    for request in archetype_requests {
+        let target = request_target(request)?;
+        if !KnowledgeGraphNode::from(target.id()).is_newly_defined() {
+            continue;
+        }
+        let mut ancestor = target;
+        while let Some(parent) = ancestor.parents().first().cloned() {
+            ancestor = parent;
+            if module_request(&ancestor, false)?.is_none() {
+                ancestor.impl_mod(&intermediate_module_doc(&ancestor)?);
+            }
+            if root_node_or_equivalent(&ancestor) {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
 
 /// Generate code for a given module. Post-processing still needed.
-pub fn code_module(request: Implement, module: Module, parent: Archetype) -> String {
+pub fn code_module(
+    request: Implement,
+    module: Module,
+    parent: Archetype,
+    codegen_cfg: &CodegenConfig,
+) -> Result<String, CodegenError> {
     let mut archetype_names = vec![];
+    let mut accessor_traits = vec![];
+    let mut enums = vec![];
     let mut public_submodules = vec![];
     let mut private_submodules = vec![];
     let mut re_exports = vec![];
 
     let parent_node = KnowledgeGraphNode::from(parent.id());
     if parent_node.is_newly_defined() {
-        archetype_names.push(parent.internal_name().unwrap());
+        archetype_names.push(internal_name(&parent)?);
+        if has_accessor_trait(&parent, codegen_cfg) {
+            accessor_traits.push(internal_name(&parent)?);
+        }
+        if is_enum(&parent) {
+            enums.push(internal_name(&parent)?);
+        }
     } else if parent_node.is_imported() {
         // Parent is already defined as part of a dependency, we're only creating this crate so
         // that we can access the children as well. In which case, we should also re-export the
@@ -45,11 +185,23 @@ pub fn code_module(request: Implement, module: Module, parent: Archetype) -> Str
         // Instead, this needs to be a re-export of the *currently* imported Yin concepts. Note
         // that for this to be successful, the newly defined concepts have to be children of the
         // imported concepts, not children of Yang's initialized concepts from a previous build.
-        re_exports.push(import_path(&parent_node, false));
+        re_exports.push(import_path(&parent_node, false)?);
+        if has_accessor_trait(&parent, codegen_cfg) {
+            re_exports.push(format!("{}Trait", import_path(&parent_node, false)?));
+        }
+        if is_enum(&parent) {
+            re_exports.push(format!("{}Value", import_path(&parent_node, false)?));
+        }
         for child in parent.child_archetypes() {
             let child_node = KnowledgeGraphNode::from(child.id());
             if child_node.is_imported() && !in_own_submodule(&child) {
-                re_exports.push(import_path(&child_node, false));
+                re_exports.push(import_path(&child_node, false)?);
+                if has_accessor_trait(&child, codegen_cfg) {
+                    re_exports.push(format!("{}Trait", import_path(&child_node, false)?));
+                }
+                if is_enum(&child) {
+                    re_exports.push(format!("{}Value", import_path(&child_node, false)?));
+                }
             }
         }
     }
@@ -60,28 +212,42 @@ pub fn code_module(request: Implement, module: Module, parent: Archetype) -> Str
                 Some(existing_module) => existing_module,
                 None => continue,
             };
-            public_submodules.push(
-                (*ModuleExtension::implementation_name(&child_submodule).unwrap()).to_owned(),
-            );
+            public_submodules.push(module_name(&child_submodule)?);
         } else if KnowledgeGraphNode::from(child.id()).is_newly_defined() {
-            archetype_names.push(child.internal_name().unwrap());
+            archetype_names.push(internal_name(&child)?);
+            if has_accessor_trait(&child, codegen_cfg) {
+                accessor_traits.push(internal_name(&child)?);
+            }
+            if is_enum(&child) {
+                enums.push(internal_name(&child)?);
+            }
         } // else, if this child doesn't have their own module, and has also been already defined,
           // then we will have already set them for re-export earlier
     }
 
     for submodule in module.submodules() {
-        private_submodules.push((*submodule.implementation_name().unwrap()).to_owned());
+        private_submodules.push(module_name(&submodule)?);
     }
 
     for re_export in module.re_exports() {
         re_exports.push((*re_export).to_owned());
     }
 
-    code_archetype_module(&ArchetypeModuleConfig {
+    let root_init = if codegen_cfg.tao_root && parent.id() == Tao::TYPE_ID {
+        base_initializer(codegen_cfg)?
+    } else {
+        None
+    };
+
+    Ok(code_archetype_module(&ArchetypeModuleConfig {
         doc: request.documentation(),
         archetype_names,
+        accessor_traits,
+        enums,
         private_submodules,
         public_submodules,
         re_exports,
-    })
+        root_init,
+        initializes_concepts: !archetypes_to_implement().is_empty(),
+    }))
 }
@@ -0,0 +1,467 @@
+use super::builder::mark_required_attr;
+use super::cardinality::{set_max_values, set_min_values};
+use super::inverse::set_inverse_accessor;
+use crate::codegen::session::CodegenSession;
+use crate::codegen::spec::{AttributeSpec, ConceptSpec, FlagSpec, IndividualSpec, Spec};
+use crate::codegen::CodegenError;
+use crate::helper::add_variant;
+use crate::tao::archetype::rust_item_archetype::DataArchetype;
+use crate::tao::archetype::CreateImplementation;
+use std::convert::TryFrom;
+use zamm_yin::node_wrappers::{BaseNodeTrait, CommonNodeTrait};
+use zamm_yin::tao::archetype::{
+    Archetype, ArchetypeFormTrait, ArchetypeTrait, AttributeArchetype, AttributeArchetypeFormTrait,
+};
+use zamm_yin::tao::relation::attribute::Attribute;
+use zamm_yin::tao::relation::flag::Flag;
+use zamm_yin::tao::Tao;
+
+/// Look up a concept by its internal name, if it has been defined yet.
+fn find(name: &str) -> Option<Archetype> {
+    Archetype::try_from(name).ok()
+}
+
+/// Look up an optional concept by its internal name, falling back to the given default if no name
+/// was specified. Returns `None` only if a name was specified but doesn't exist yet.
+fn find_or(name: &Option<String>, default: Archetype) -> Option<Archetype> {
+    match name {
+        Some(name) => find(name),
+        None => Some(default),
+    }
+}
+
+/// Create a new concept under the given parent, as `define_child!` does. Concepts under Tao are
+/// defined as `define!` does instead.
+fn define_child(name: &str, parent: Archetype, session: &CodegenSession) -> Archetype {
+    let mut concept = session.define_concept(name);
+    if parent != Tao::archetype() {
+        concept.add_parent(parent);
+    }
+    concept
+}
+
+/// Define a concept, along with its data properties.
+fn define_concept(spec: &ConceptSpec, parent: Archetype, session: &CodegenSession) {
+    let concept = define_child(&spec.name, parent, session);
+    if let Some(doc) = &spec.doc {
+        concept.implement_with_doc(doc);
+    }
+
+    let mut data = DataArchetype::from(concept.id());
+    if let Some(primitive) = &spec.rust_primitive {
+        data.set_rust_primitive(primitive);
+    }
+    if let Some(unboxed) = &spec.unboxed_representation {
+        data.set_unboxed_representation(unboxed);
+    }
+    if let Some(default_value) = &spec.default_value {
+        data.set_default_value(default_value);
+    }
+    if let Some(dummy_value) = &spec.dummy_value {
+        data.set_dummy_value(dummy_value);
+    }
+    for variant in &spec.variants {
+        add_variant(concept, &variant.name, &variant.doc);
+    }
+}
+
+/// Document a newly defined attribute or flag, as `add_attr!` and `add_flag!` do.
+fn implement_property(property: &Archetype, doc: &str, dual_doc: &str) {
+    let mut new_impl = property.implement_with_doc(doc);
+    new_impl.set_dual_purpose_documentation(dual_doc);
+}
+
+/// Define an attribute and add it to its owner, as `add_attr!` does. Returns false if the
+/// concepts it refers to haven't been defined yet.
+fn define_attribute(spec: &AttributeSpec, session: &CodegenSession) -> bool {
+    let (parent, mut owner, value) = match (
+        find_or(&spec.parent, Attribute::archetype().into()),
+        find_or(&spec.owner, Tao::archetype()),
+        find_or(&spec.value, Tao::archetype()),
+    ) {
+        (Some(parent), Some(owner), Some(value)) => (parent, owner, value),
+        _ => return false,
+    };
+
+    let attr = define_child(&spec.name, parent, session);
+    let mut new_aa = AttributeArchetype::from(attr.id());
+    if owner != Tao::archetype() {
+        new_aa.set_owner_archetype(&owner);
+    }
+    if value != Tao::archetype() {
+        new_aa.set_value_archetype(&value);
+    }
+    owner.add_attribute(&new_aa);
+    implement_property(&attr, &spec.doc, &spec.dual_doc);
+    if spec.multi_valued {
+        new_aa.mark_multi_valued_attr();
+    }
+    if spec.nonhereditary {
+        new_aa.mark_nonhereditary_attr();
+    }
+    if let Some(inverse) = &spec.inverse {
+        set_inverse_accessor(&new_aa, &inverse.name, &inverse.dual_doc);
+    }
+    if spec.required {
+        mark_required_attr(&new_aa);
+    }
+    if let Some(min) = spec.min_values {
+        set_min_values(&new_aa, min);
+    }
+    if let Some(max) = spec.max_values {
+        set_max_values(&new_aa, max);
+    }
+    true
+}
+
+/// Make sure that the attribute's cardinality can actually be satisfied.
+fn check_cardinality(spec: &AttributeSpec) -> Result<(), CodegenError> {
+    let min = match (spec.min_values, spec.required) {
+        (Some(min), _) => min,
+        (None, true) => 1,
+        (None, false) => 0,
+    };
+    let reason = match spec.max_values {
+        Some(0) => Some("can't keep zero values"),
+        Some(max) if max > 1 && !spec.multi_valued => {
+            Some("can't keep more than one value without being multi-valued")
+        }
+        Some(max) if max < min => Some("needs more values than it can keep"),
+        None if min > 1 && !spec.multi_valued => {
+            Some("can't need more than one value without being multi-valued")
+        }
+        _ => None,
+    };
+    match reason {
+        Some(reason) => Err(CodegenError::InvalidSpec {
+            reason: format!("attribute {} {}", spec.name, reason),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Define a flag and add it to its owner, as `add_flag!` does. Returns false if the concepts it
+/// refers to haven't been defined yet.
+fn define_flag(spec: &FlagSpec, session: &CodegenSession) -> bool {
+    let (parent, mut owner) = match (
+        find_or(&spec.parent, Flag::archetype().into()),
+        find(&spec.owner),
+    ) {
+        (Some(parent), Some(owner)) => (parent, owner),
+        _ => return false,
+    };
+
+    let flag = define_child(&spec.name, parent, session);
+    let mut new_aa = AttributeArchetype::from(flag.id());
+    new_aa.set_owner_archetype(&owner);
+    owner.add_flag(&flag);
+    implement_property(&flag, &spec.doc, &spec.dual_doc);
+    if spec.nonhereditary {
+        new_aa.mark_nonhereditary_attr();
+    }
+    true
+}
+
+/// Define a named individual along with its attribute values, as `define_individual` would.
+/// Returns false if the concepts it refers to haven't been defined yet.
+fn define_individual_spec(spec: &IndividualSpec, session: &CodegenSession) -> bool {
+    let archetype = match find(&spec.archetype) {
+        Some(archetype) => archetype,
+        None => return false,
+    };
+    let mut values = vec![];
+    for (attribute, attribute_values) in &spec.attributes {
+        for value in attribute_values.names() {
+            match (find(attribute), find(value)) {
+                (Some(attribute), Some(value)) => values.push((attribute, value)),
+                _ => return false,
+            }
+        }
+    }
+
+    let mut individual = session.define_individual(archetype, &spec.name, &spec.doc);
+    for (attribute, value) in values {
+        individual.add_outgoing(attribute.id(), &value);
+    }
+    true
+}
+
+/// Keep trying to define items until all of them are defined, because items may refer to other
+/// items listed after them.
+fn define_all<T>(
+    items: &[T],
+    kind: &str,
+    name: impl Fn(&T) -> &str,
+    define: impl Fn(&T) -> bool,
+) -> Result<(), CodegenError> {
+    let mut pending = items.iter().collect::<Vec<&T>>();
+    while !pending.is_empty() {
+        let remaining = pending
+            .iter()
+            .filter(|item| !define(item))
+            .copied()
+            .collect::<Vec<&T>>();
+        if remaining.len() == pending.len() {
+            return Err(CodegenError::InvalidSpec {
+                reason: format!(
+                    "{} {} refer to unknown concepts",
+                    kind,
+                    remaining
+                        .iter()
+                        .map(|item| name(item))
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                ),
+            });
+        }
+        pending = remaining;
+    }
+    Ok(())
+}
+
+/// Build the same graph that the equivalent `define_child!`, `add_attr!`, `add_flag!`,
+/// `define_individual`, and `module!` calls would build.
+///
+/// Concepts that are defined while the session is importing get marked as imported, just as they
+/// would be with the macros.
+pub fn load_spec(spec: &Spec, session: &CodegenSession) -> Result<(), CodegenError> {
+    define_all(
+        &spec.concepts,
+        "Concepts",
+        |c| &c.name,
+        |c| match find_or(&c.parent, Tao::archetype()) {
+            Some(parent) => {
+                define_concept(c, parent, session);
+                true
+            }
+            None => false,
+        },
+    )?;
+    for attribute in &spec.attributes {
+        check_cardinality(attribute)?;
+    }
+    define_all(
+        &spec.attributes,
+        "Attributes",
+        |a| &a.name,
+        |a| define_attribute(a, session),
+    )?;
+    define_all(
+        &spec.flags,
+        "Flags",
+        |f| &f.name,
+        |f| define_flag(f, session),
+    )?;
+    define_all(
+        &spec.individuals,
+        "Individuals",
+        |i| &i.name,
+        |i| define_individual_spec(i, session),
+    )?;
+
+    for module_spec in &spec.modules {
+        let concept = find(&module_spec.concept).ok_or_else(|| CodegenError::InvalidSpec {
+            reason: format!("module is for unknown concept {}", module_spec.concept),
+        })?;
+        let mut module = concept.impl_mod(&module_spec.doc);
+        for extension in &module_spec.extensions {
+            module.has_extension(extension);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::planning::builder::is_required_attr;
+    use crate::codegen::planning::cardinality::{max_values, min_values};
+    use crate::codegen::planning::inverse::inverse_accessor;
+    use crate::codegen::spec::{AttributeValues, InverseSpec, ModuleSpec, VariantSpec};
+    use crate::tao::form::rust_item::data::Data;
+    use crate::tao::initialize_kb;
+    use crate::tao::perspective::KnowledgeGraphNode;
+    use std::rc::Rc;
+    use zamm_yin::tao::form::{Form, FormTrait};
+
+    #[test]
+    fn test_load_spec() {
+        initialize_kb();
+        let spec = Spec {
+            concepts: vec![
+                ConceptSpec {
+                    name: "spec-lamp-color".to_owned(),
+                    parent: Some("spec-light".to_owned()),
+                    doc: Some("The color of a lamp.".to_owned()),
+                    rust_primitive: Some("u8".to_owned()),
+                    variants: vec![VariantSpec {
+                        name: "red".to_owned(),
+                        doc: "Stop.".to_owned(),
+                    }],
+                    ..ConceptSpec::default()
+                },
+                ConceptSpec {
+                    name: "spec-light".to_owned(),
+                    parent: Some("data".to_owned()),
+                    ..ConceptSpec::default()
+                },
+                ConceptSpec {
+                    name: "spec-lamp".to_owned(),
+                    doc: Some("A source of light.".to_owned()),
+                    ..ConceptSpec::default()
+                },
+            ],
+            attributes: vec![AttributeSpec {
+                name: "spec-shines".to_owned(),
+                owner: Some("spec-lamp".to_owned()),
+                value: Some("spec-lamp-color".to_owned()),
+                doc: "The colors a lamp shines.".to_owned(),
+                dual_doc: "the colors this lamp shines.".to_owned(),
+                multi_valued: true,
+                inverse: Some(InverseSpec {
+                    name: "shone_by".to_owned(),
+                    dual_doc: "the lamps that shine this color.".to_owned(),
+                }),
+                required: true,
+                ..AttributeSpec::default()
+            }],
+            flags: vec![FlagSpec {
+                name: "spec-on".to_owned(),
+                owner: "spec-lamp".to_owned(),
+                doc: "Whether a lamp is on.".to_owned(),
+                dual_doc: "whether this lamp is on.".to_owned(),
+                ..FlagSpec::default()
+            }],
+            individuals: vec![IndividualSpec {
+                name: "spec-desk-lamp".to_owned(),
+                archetype: "spec-lamp".to_owned(),
+                doc: "The lamp on the desk.".to_owned(),
+                attributes: vec![(
+                    "spec-shines".to_owned(),
+                    AttributeValues::Many(vec!["spec-lamp-color".to_owned()]),
+                )]
+                .into_iter()
+                .collect(),
+            }],
+            modules: vec![ModuleSpec {
+                concept: "spec-lamp".to_owned(),
+                doc: "Lamps and their lights.".to_owned(),
+                extensions: vec![],
+            }],
+        };
+        load_spec(&spec, &CodegenSession::default()).unwrap();
+
+        let lamp = Archetype::try_from("spec-lamp").unwrap();
+        let color = Archetype::try_from("spec-lamp-color").unwrap();
+        let shines = AttributeArchetype::try_from("spec-shines").unwrap();
+        let on = Archetype::try_from("spec-on").unwrap();
+        assert!(KnowledgeGraphNode::from(lamp.id()).is_newly_defined());
+        assert!(color.has_ancestor(Data::archetype().into()));
+        assert_eq!(
+            DataArchetype::from(color.id()).rust_primitive(),
+            Some(Rc::from("u8"))
+        );
+        assert_eq!(DataArchetype::from(color.id()).variants().len(), 1);
+        assert_eq!(shines.owner_archetype(), lamp);
+        assert_eq!(shines.value_archetype(), color);
+        assert!(shines.is_multi_valued_attr());
+        assert!(is_required_attr(&shines));
+        assert_eq!(
+            inverse_accessor(&shines).map(|(name, _)| name),
+            Some(Rc::from("shone_by"))
+        );
+        assert!(lamp.added_attributes().contains(&shines));
+        assert!(lamp.added_flags().contains(&on));
+        assert!(!lamp.implementations().is_empty());
+
+        let desk_lamp = Form::try_from("spec-desk-lamp").unwrap();
+        assert!(desk_lamp.is_individual());
+        assert!(desk_lamp.has_ancestor(lamp));
+        assert_eq!(
+            desk_lamp
+                .outgoing_nodes(shines.id())
+                .iter()
+                .map(|n| n.id())
+                .collect::<Vec<usize>>(),
+            vec![color.id()]
+        );
+        assert!(desk_lamp.concept_implementation().is_some());
+    }
+
+    #[test]
+    fn test_load_spec_cardinality() {
+        initialize_kb();
+        let attribute = AttributeSpec {
+            name: "spec-bulb".to_owned(),
+            doc: "The bulbs of a lamp.".to_owned(),
+            dual_doc: "the bulbs of this lamp.".to_owned(),
+            multi_valued: true,
+            min_values: Some(2),
+            max_values: Some(4),
+            ..AttributeSpec::default()
+        };
+        load_spec(
+            &Spec {
+                attributes: vec![attribute.clone()],
+                ..Spec::default()
+            },
+            &CodegenSession::default(),
+        )
+        .unwrap();
+        let bulb = AttributeArchetype::try_from("spec-bulb").unwrap();
+        assert_eq!(min_values(&bulb), 2);
+        assert_eq!(max_values(&bulb), Some(4));
+
+        let unsatisfiable = Spec {
+            attributes: vec![AttributeSpec {
+                name: "spec-unsatisfiable".to_owned(),
+                max_values: Some(1),
+                ..attribute.clone()
+            }],
+            ..Spec::default()
+        };
+        assert!(load_spec(&unsatisfiable, &CodegenSession::default()).is_err());
+        let single_valued = Spec {
+            attributes: vec![AttributeSpec {
+                name: "spec-single".to_owned(),
+                multi_valued: false,
+                min_values: None,
+                ..attribute
+            }],
+            ..Spec::default()
+        };
+        assert!(load_spec(&single_valued, &CodegenSession::default()).is_err());
+    }
+
+    #[test]
+    fn test_load_spec_importing_session() {
+        initialize_kb();
+        let mut session = CodegenSession::default();
+        session.start_imports();
+        let spec = Spec {
+            concepts: vec![ConceptSpec {
+                name: "spec-imported".to_owned(),
+                ..ConceptSpec::default()
+            }],
+            ..Spec::default()
+        };
+        session.load_spec(&spec).unwrap();
+        let kgn = KnowledgeGraphNode::from(Archetype::try_from("spec-imported").unwrap().id());
+        assert!(kgn.is_imported());
+        assert!(!kgn.is_newly_defined());
+    }
+
+    #[test]
+    fn test_load_spec_unknown_parent() {
+        initialize_kb();
+        let spec = Spec {
+            concepts: vec![ConceptSpec {
+                name: "spec-orphan".to_owned(),
+                parent: Some("spec-nonexistent".to_owned()),
+                ..ConceptSpec::default()
+            }],
+            ..Spec::default()
+        };
+        assert!(load_spec(&spec, &CodegenSession::default()).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use super::accessor_trait::generates_accessors;
+use super::archetype::{activate_archetype, activate_attribute, activate_data, or_form_default};
+use super::enum_data::{enum_variants, is_enum};
+use super::imports::{archetype_file_path, describe, module_file_path, request_target};
+use super::inverse::inverse_accessor;
+use super::module::module_target;
+use crate::codegen::{CodegenConfig, CodegenError};
+use crate::tao::action::Implement;
+use crate::tao::archetype::rust_item_archetype::DataArchetype;
+use crate::tao::archetype::CreateImplementation;
+use crate::tao::perspective::KnowledgeGraphNode;
+use std::collections::HashMap;
+use zamm_yin::node_wrappers::CommonNodeTrait;
+use zamm_yin::tao::archetype::{
+    Archetype, ArchetypeFormTrait, AttributeArchetype, AttributeArchetypeFormTrait,
+};
+
+/// Check that every accessor this archetype introduces can be documented.
+fn validate_accessors(target: &Archetype, problems: &mut Vec<CodegenError>) {
+    let mut properties = target.added_flags();
+    properties.extend(target.added_attributes().into_iter().map(Archetype::from));
+    for property in properties {
+        let documented = property
+            .accessor_implementation()
+            .and_then(|i| i.dual_purpose_documentation())
+            .is_some();
+        if !documented {
+            problems.push(CodegenError::MissingDocumentation {
+                concept: describe(&property),
+            });
+        }
+    }
+}
+
+/// Check that data values can be represented in Rust.
+fn validate_data(target: &Archetype, problems: &mut Vec<CodegenError>) {
+    if is_enum(target) {
+        // every variant needs a name to be used as Rust code
+        if let Err(e) = enum_variants(target) {
+            problems.push(e);
+        }
+        return;
+    }
+    let data = DataArchetype::from(target.id());
+    if data.rust_primitive().is_none() {
+        problems.push(CodegenError::MissingRustPrimitive {
+            concept: describe(target),
+        });
+    }
+    if data.default_value().is_none() {
+        problems.push(CodegenError::MissingDefaultValue {
+            concept: describe(target),
+        });
+    }
+}
+
+/// Check that an attribute's inverse accessor, if it has one, has somewhere to go.
+fn validate_inverse(target: &Archetype, problems: &mut Vec<CodegenError>) {
+    let attr = AttributeArchetype::from(target.id());
+    if inverse_accessor(&attr).is_none() {
+        return;
+    }
+    let value = or_form_default(attr.value_archetype());
+    if !KnowledgeGraphNode::from(value.id()).is_newly_defined() {
+        problems.push(CodegenError::UngeneratedInverse {
+            attribute: describe(target),
+            value: describe(&value),
+        });
+    }
+}
+
+/// Check everything that code generation will need to know about a single archetype.
+fn validate_archetype(
+    target: &Archetype,
+    codegen_cfg: &CodegenConfig,
+    problems: &mut Vec<CodegenError>,
+) {
+    let kgn = KnowledgeGraphNode::from(target.id());
+    if kgn.is_newly_defined() && kgn.is_imported() {
+        problems.push(CodegenError::ConflictingOrigin {
+            concept: describe(target),
+        });
+    }
+    if target.internal_name().is_none() {
+        problems.push(CodegenError::UnnamedConcept {
+            concept: describe(target),
+        });
+    }
+    // same precedence as in code generation
+    if activate_data(target) && !activate_archetype(target) && !activate_attribute(target) {
+        validate_data(target, problems);
+    }
+    if generates_accessors(target, codegen_cfg) {
+        validate_accessors(target, problems);
+    }
+    if activate_attribute(target) {
+        validate_inverse(target, problems);
+    }
+}
+
+/// Check all archetype and module implementation requests for problems, before any code gets
+/// generated.
+///
+/// Unlike generation itself, this doesn't stop at the first problem. Every problem found gets
+/// reported together.
+pub fn validate_implementations(
+    archetype_requests: &[Implement],
+    module_requests: &[Implement],
+    codegen_cfg: &CodegenConfig,
+) -> Result<(), CodegenError> {
+    let mut problems = Vec::new();
+    let mut file_owners: HashMap<String, Vec<String>> = HashMap::new();
+    for request in archetype_requests {
+        let target = match request_target(request) {
+            Ok(target) => target,
+            Err(e) => {
+                problems.push(e);
+                continue;
+            }
+        };
+        validate_archetype(&target, codegen_cfg, &mut problems);
+        // don't bother looking for file paths if the path can't even be computed
+        if let Ok(path) = archetype_file_path(&target) {
+            file_owners.entry(path).or_default().push(describe(&target));
+        }
+    }
+    for request in module_requests {
+        let path = module_target(request)
+            .and_then(|(_, primary)| Ok((module_file_path(&primary)?, describe(&primary))));
+        match path {
+            Ok((path, primary)) => file_owners
+                .entry(path)
+                .or_default()
+                .push(format!("the {} module", primary)),
+            Err(e) => problems.push(e),
+        }
+    }
+
+    let mut duplicate_paths = file_owners
+        .into_iter()
+        .filter(|(_, concepts)| concepts.len() > 1)
+        .collect::<Vec<(String, Vec<String>)>>();
+    duplicate_paths.sort();
+    for (path, concepts) in duplicate_paths {
+        problems.push(CodegenError::DuplicateFilePath { path, concepts });
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(CodegenError::Invalid(problems))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::planning::set_inverse_accessor;
+    use crate::tao::form::rust_item::{Concept, Module};
+    use crate::tao::initialize_kb;
+    use crate::tao::perspective::{BuildInfo, BuildInfoExtension};
+    use zamm_yin::tao::archetype::ArchetypeTrait;
+    use zamm_yin::tao::form::{Form, FormTrait};
+    use zamm_yin::tao::relation::attribute::Attribute;
+
+    fn implement(target: &Archetype) -> Implement {
+        let mut implement = Implement::new();
+        implement.set_target(&target.as_form());
+        implement.set_embodiment(&Concept::new().into());
+        implement
+    }
+
+    fn problems(requests: &[Implement]) -> Vec<String> {
+        module_problems(requests, &[])
+    }
+
+    fn module_problems(requests: &[Implement], module_requests: &[Implement]) -> Vec<String> {
+        match validate_implementations(requests, module_requests, &CodegenConfig::default()) {
+            Ok(()) => Vec::new(),
+            Err(CodegenError::Invalid(problems)) => {
+                problems.iter().map(|p| p.to_string()).collect()
+            }
+            Err(other) => panic!("Unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_valid_concept() {
+        initialize_kb();
+        let mut target = Form::archetype().individuate_as_archetype();
+        target.set_internal_name("valid-concept");
+        KnowledgeGraphNode::from(target.id()).mark_newly_defined();
+        assert_eq!(problems(&[implement(&target)]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_all_problems_reported() {
+        initialize_kb();
+        let unnamed = Form::archetype().individuate_as_archetype();
+        let mut conflicted = Form::archetype().individuate_as_archetype();
+        conflicted.set_internal_name("conflicted");
+        let mut conflicted_kgn = KnowledgeGraphNode::from(conflicted.id());
+        conflicted_kgn.mark_newly_defined();
+        conflicted_kgn.mark_imported();
+
+        assert_eq!(
+            problems(&[implement(&unnamed), implement(&conflicted)]),
+            vec![
+                format!("unnamed concept #{} has no internal name", unnamed.id()),
+                "conflicted is both newly defined and imported".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undocumented_attribute() {
+        initialize_kb();
+        let mut owner = Form::archetype().individuate_as_archetype();
+        owner.set_internal_name("owner-concept");
+        let mut attr = Attribute::archetype().individuate_as_archetype();
+        attr.set_internal_name("undocumented-attr");
+        owner.add_attribute(&attr);
+        attr.implement_with_doc("An attribute without dual-purpose documentation.");
+
+        assert_eq!(
+            problems(&[implement(&owner)]),
+            vec!["undocumented-attr has no dual-purpose documentation for its accessors"]
+        );
+    }
+
+    #[test]
+    fn test_ungenerated_inverse() {
+        initialize_kb();
+        let mut attr = Attribute::archetype().individuate_as_archetype();
+        attr.set_internal_name("inverse-attr");
+        KnowledgeGraphNode::from(attr.id()).mark_newly_defined();
+        attr.set_value_archetype(&Form::archetype());
+        set_inverse_accessor(&attr, "pointed_to_by", "the concepts that point to this.");
+
+        assert_eq!(
+            problems(&[implement(&attr.into())]),
+            vec!["inverse-attr has an inverse accessor, but its value type form isn't generated"]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_file_path() {
+        initialize_kb();
+        let mut first = Form::archetype().individuate_as_archetype();
+        first.set_internal_name("same-name");
+        let mut second = Form::archetype().individuate_as_archetype();
+        second.set_internal_name("SameName");
+
+        assert_eq!(
+            problems(&[implement(&first), implement(&second)]),
+            vec!["same-name, SameName would all be output to src/tao/form/same_name_form.rs"]
+        );
+    }
+
+    fn implement_module(target: &Archetype, member: Option<&Archetype>) -> Implement {
+        let mut module = Module::new();
+        if let Some(member) = member {
+            module.set_most_prominent_member(&member.as_form());
+        }
+        let mut implement = Implement::new();
+        implement.set_target(&target.as_form());
+        implement.set_embodiment(&module.into());
+        implement
+    }
+
+    #[test]
+    fn test_module_without_member() {
+        initialize_kb();
+        let mut target = Form::archetype().individuate_as_archetype();
+        target.set_internal_name("memberless");
+
+        assert_eq!(
+            module_problems(&[], &[implement_module(&target, None)]),
+            vec![
+                "memberless is malformed: its module has no most prominent member to be named \
+                    after"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_module_path() {
+        initialize_kb();
+        let mut first = Form::archetype().individuate_as_archetype();
+        first.set_internal_name("same-module");
+        let mut second = Form::archetype().individuate_as_archetype();
+        second.set_internal_name("SameModule");
+        BuildInfo::from(first.id()).mark_own_module();
+        BuildInfo::from(second.id()).mark_own_module();
+
+        assert_eq!(
+            module_problems(
+                &[],
+                &[
+                    implement_module(&first, Some(&first)),
+                    implement_module(&second, Some(&second)),
+                ],
+            ),
+            vec![
+                "the same-module module, the SameModule module would all be output to \
+                    src/tao/form/same_module/mod.rs"
+            ]
+        );
+    }
+}
@@ -4,6 +4,8 @@ pub mod mark_autogen;
 ///
 /// Sometimes, it's just too hard to autogenerate perfectly formatted code.
 pub mod mark_fmt;
+/// Carry hand-written regions over from previously generated files.
+pub mod user_regions;
 
 use super::CodegenConfig;
 use mark_autogen::add_autogeneration_comments;
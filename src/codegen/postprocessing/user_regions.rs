@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+
+/// Comment that starts a hand-written region, followed by the region's anchor.
+pub const USER_CODE_BEGIN: &str = "// BEGIN USER CODE:";
+
+/// Comment that ends a hand-written region, followed by the region's anchor.
+pub const USER_CODE_END: &str = "// END USER CODE:";
+
+/// Result of carrying hand-written regions over into newly generated code.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergedCode {
+    /// The generated code, with hand-written regions filled back in.
+    pub code: String,
+    /// Anchors of the existing regions that no longer have a place in the generated code.
+    pub orphaned: Vec<String>,
+}
+
+/// An empty region that hand-written code can later be added to.
+pub fn user_region(anchor: &str) -> String {
+    format!(
+        "{} {}\n{} {}",
+        USER_CODE_BEGIN, anchor, USER_CODE_END, anchor
+    )
+}
+
+/// The anchor of a region that this line starts, if it starts one.
+fn region_start(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix(USER_CODE_BEGIN)
+        .map(|anchor| anchor.trim())
+}
+
+/// Whether or not this line ends the region with the given anchor.
+fn is_region_end(line: &str, anchor: &str) -> bool {
+    match line.trim().strip_prefix(USER_CODE_END) {
+        Some(end_anchor) => end_anchor.trim() == anchor,
+        None => false,
+    }
+}
+
+/// Find the contents of every region in the code, keyed by anchor. Contents include the trailing
+/// newline of each line, so that they can be put back as-is.
+///
+/// Returns the reason if the regions are malformed.
+pub fn extract_user_regions(code: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut regions = BTreeMap::new();
+    let mut current: Option<(String, String)> = None;
+    for line in code.split_inclusive('\n') {
+        match &mut current {
+            Some((anchor, content)) => {
+                if is_region_end(line, anchor) {
+                    let (anchor, content) = current.take().unwrap();
+                    if regions.insert(anchor.clone(), content).is_some() {
+                        return Err(format!("region {} appears more than once", anchor));
+                    }
+                } else if let Some(nested) = region_start(line) {
+                    return Err(format!("region {} starts inside region {}", nested, anchor));
+                } else {
+                    content.push_str(line);
+                }
+            }
+            None => {
+                if let Some(anchor) = region_start(line) {
+                    current = Some((anchor.to_owned(), String::new()));
+                }
+            }
+        }
+    }
+    match current {
+        Some((anchor, _)) => Err(format!("region {} is never ended", anchor)),
+        None => Ok(regions),
+    }
+}
+
+/// Whether or not any region in the code has hand-written code in it. Malformed regions are
+/// treated as having no code, since they'll get reported when merging anyways.
+pub fn has_user_code(code: &str) -> bool {
+    match extract_user_regions(code) {
+        Ok(regions) => regions.values().any(|content| !content.trim().is_empty()),
+        Err(_) => false,
+    }
+}
+
+/// Fill the regions of newly generated code with the hand-written contents of the same regions in
+/// the existing code.
+///
+/// Returns the reason if the regions in either version of the code are malformed.
+pub fn merge_user_regions(generated: &str, existing: &str) -> Result<MergedCode, String> {
+    let mut existing_regions = extract_user_regions(existing)?;
+    // make sure the generated regions are well-formed before relying on them
+    let generated_regions = extract_user_regions(generated)?;
+
+    let mut code = String::new();
+    let mut current: Option<String> = None;
+    for line in generated.split_inclusive('\n') {
+        match &current {
+            Some(anchor) => {
+                if is_region_end(line, anchor) {
+                    if let Some(content) = existing_regions.get(anchor) {
+                        code.push_str(content);
+                    } else {
+                        code.push_str(&generated_regions[anchor]);
+                    }
+                    code.push_str(line);
+                    current = None;
+                }
+            }
+            None => {
+                code.push_str(line);
+                if let Some(anchor) = region_start(line) {
+                    current = Some(anchor.to_owned());
+                }
+            }
+        }
+    }
+
+    existing_regions.retain(|anchor, content| {
+        !generated_regions.contains_key(anchor) && !content.trim().is_empty()
+    });
+    Ok(MergedCode {
+        code,
+        orphaned: existing_regions.into_keys().collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_user_region() {
+        assert_eq!(
+            user_region("items"),
+            "// BEGIN USER CODE: items\n// END USER CODE: items"
+        );
+    }
+
+    #[test]
+    fn test_extract_regions() {
+        let regions = extract_user_regions(indoc! {"
+            struct A {}
+
+            // BEGIN USER CODE: items
+            impl A {}
+            // END USER CODE: items
+
+            mod tests {
+                // BEGIN USER CODE: tests
+                // END USER CODE: tests
+            }
+        "})
+        .unwrap();
+        assert_eq!(
+            regions.get("items").map(|s| s.as_str()),
+            Some("impl A {}\n")
+        );
+        assert_eq!(regions.get("tests").map(|s| s.as_str()), Some(""));
+    }
+
+    #[test]
+    fn test_has_user_code() {
+        assert!(!has_user_code(&user_region("items")));
+        assert!(!has_user_code(
+            "// BEGIN USER CODE: items\n    \n// END USER CODE: items\n"
+        ));
+        assert!(has_user_code(
+            "// BEGIN USER CODE: items\nimpl A {}\n// END USER CODE: items\n"
+        ));
+    }
+
+    #[test]
+    fn test_extract_malformed() {
+        assert_eq!(
+            extract_user_regions("// BEGIN USER CODE: items\nimpl A {}\n"),
+            Err("region items is never ended".to_owned())
+        );
+        assert_eq!(
+            extract_user_regions(indoc! {"
+                // BEGIN USER CODE: items
+                // BEGIN USER CODE: tests
+            "}),
+            Err("region tests starts inside region items".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_merge_regions() {
+        let generated = indoc! {"
+            struct A {}
+
+            // BEGIN USER CODE: items
+            // END USER CODE: items
+
+            mod tests {
+                // BEGIN USER CODE: tests
+                // END USER CODE: tests
+            }
+        "};
+        let existing = indoc! {"
+            struct A {
+                old: bool,
+            }
+
+            // BEGIN USER CODE: items
+            impl A {
+                fn custom(&self) {}
+            }
+            // END USER CODE: items
+
+            // BEGIN USER CODE: removed
+            fn removed() {}
+            // END USER CODE: removed
+        "};
+        assert_eq!(
+            merge_user_regions(generated, existing).unwrap(),
+            MergedCode {
+                code: indoc! {"
+                    struct A {}
+
+                    // BEGIN USER CODE: items
+                    impl A {
+                        fn custom(&self) {}
+                    }
+                    // END USER CODE: items
+
+                    mod tests {
+                        // BEGIN USER CODE: tests
+                        // END USER CODE: tests
+                    }
+                "}
+                .to_owned(),
+                orphaned: vec!["removed".to_owned()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_merge_without_existing() {
+        let generated = "// BEGIN USER CODE: items\n// END USER CODE: items\n";
+        assert_eq!(
+            merge_user_regions(generated, "").unwrap(),
+            MergedCode {
+                code: generated.to_owned(),
+                orphaned: vec![],
+            }
+        );
+    }
+}
@@ -0,0 +1,18 @@
+use path_abs::{PathAbs, PathInfo};
+use std::env;
+use std::fs;
+
+/// Create an empty directory for a test to write files into. The name keeps concurrently running
+/// tests out of each other's way.
+pub fn scratch_dir(name: &str) -> PathAbs {
+    let dir = env::temp_dir().join(format!("zamm-yang-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    PathAbs::new(dir).unwrap()
+}
+
+/// Write a file into a scratch directory, and return its full path.
+pub fn scratch_file(dir: &PathAbs, name: &str, content: &str) -> String {
+    let path = dir.as_path().join(name);
+    fs::write(&path, content).unwrap();
+    path.to_str().unwrap().to_owned()
+}
@@ -0,0 +1,310 @@
+use crate::codegen::filesystem::CodegenPlan;
+use crate::codegen::id_lock::{IdLock, ID_LOCK};
+use crate::codegen::planning::{load_spec, IdAllocator};
+use crate::codegen::spec::Spec;
+use crate::codegen::track_autogen::{tracked_files, AutogenTracker};
+use crate::codegen::verify::{find_drift, manifest_files, DriftReport};
+use crate::codegen::{CodegenConfig, CodegenError};
+use crate::tao::archetype::CreateImplementation;
+use crate::tao::callbacks::{generate_all, implements};
+use crate::tao::perspective::KnowledgeGraphNode;
+use std::cell::RefCell;
+use zamm_yin::node_wrappers::CommonNodeTrait;
+use zamm_yin::tao::archetype::{Archetype, ArchetypeFormTrait, ArchetypeTrait};
+use zamm_yin::tao::form::Form;
+use zamm_yin::tao::Tao;
+
+thread_local! {
+    static DEFAULT_SESSION: RefCell<CodegenSession> = RefCell::new(CodegenSession::default());
+}
+
+/// All the state for a single code generation run, so that multiple runs can happen independently
+/// of each other within the same process.
+///
+/// Concept IDs are handed out by the session as well, continuing on from the ID lock file the
+/// first time they're needed. Note that the knowledge base itself is still shared by every session
+/// on the same thread.
+#[derive(Clone, Debug, Default)]
+pub struct CodegenSession {
+    /// Options for all code generated during this session.
+    pub config: CodegenConfig,
+    import_in_progress: bool,
+    autogen: AutogenTracker,
+    ids: Option<IdAllocator>,
+}
+
+impl CodegenSession {
+    /// Start a new session that generates code with the given options.
+    pub fn new(config: CodegenConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Returns whether or not we're currently in the process of importing another file.
+    pub fn import_in_progress(&self) -> bool {
+        self.import_in_progress
+    }
+
+    /// Start interpreting new information as imported. New concepts will not be marked as new,
+    /// but instead as imported.
+    pub fn start_imports(&mut self) {
+        self.import_in_progress = true;
+    }
+
+    /// Mark all concepts introduced thus far as imported ones. Imports are informational-only and
+    /// will not be acted on.
+    pub fn end_imports(&mut self) {
+        self.import_in_progress = false;
+        for i in implements() {
+            KnowledgeGraphNode::from(i.id()).mark_imported();
+        }
+    }
+
+    /// Mark a concept that was just defined as imported if this session is importing, or as newly
+    /// defined otherwise.
+    pub fn mark_origin(&self, kgn: &mut KnowledgeGraphNode) {
+        if self.import_in_progress {
+            kgn.mark_imported();
+        } else {
+            kgn.mark_newly_defined();
+        }
+    }
+
+    /// Defines a new concept with the given name directly under Tao, just as `define!` does.
+    pub fn define_concept(&self, name: &str) -> Archetype {
+        let mut concept = Tao::archetype().individuate_as_archetype();
+        concept.set_internal_name(name);
+        self.mark_origin(&mut KnowledgeGraphNode::from(concept.id()));
+        concept
+    }
+
+    /// Defines a new named individual of the given archetype. The individual gets initialized
+    /// along with the generated concepts, and can be retrieved through an accessor on its
+    /// archetype. Individuals defined while importing are left for their original crate to
+    /// initialize.
+    pub fn define_individual(&self, archetype: Archetype, name: &str, doc: &str) -> Form {
+        let mut individual = archetype.individuate_as_form();
+        individual.set_internal_name(name);
+        self.mark_origin(&mut KnowledgeGraphNode::from(individual.id()));
+        let request = individual.implement_with_doc(doc);
+        if self.import_in_progress {
+            KnowledgeGraphNode::from(request.id()).mark_imported();
+        }
+        individual
+    }
+
+    /// Define everything listed in the specification. See `load_spec` for details.
+    pub fn load_spec(&self, spec: &Spec) -> Result<(), CodegenError> {
+        load_spec(spec, self)
+    }
+
+    /// Everything output so far during this session.
+    pub fn autogen(&self) -> &AutogenTracker {
+        &self.autogen
+    }
+
+    /// Everything output so far during this session, for tracking output that happened outside of
+    /// `handle_all_implementations`.
+    pub fn autogen_mut(&mut self) -> &mut AutogenTracker {
+        &mut self.autogen
+    }
+
+    /// The allocator that hands out concept IDs during this session. It gets started from the ID
+    /// lock file the first time it's needed.
+    pub fn ids(&mut self) -> Result<&mut IdAllocator, CodegenError> {
+        let ids = self.take_ids()?;
+        Ok(self.ids.get_or_insert(ids))
+    }
+
+    /// Take the ID allocator out of this session, so that it can be used without holding on to
+    /// the session. Put it back with `restore_ids` once done.
+    pub(crate) fn take_ids(&mut self) -> Result<IdAllocator, CodegenError> {
+        match self.ids.take() {
+            Some(ids) => Ok(ids),
+            None => Ok(IdAllocator::new(IdLock::read(ID_LOCK)?, self.config.yin)),
+        }
+    }
+
+    /// Put back an ID allocator taken out with `take_ids`.
+    pub(crate) fn restore_ids(&mut self, ids: IdAllocator) {
+        self.ids = Some(ids);
+    }
+
+    /// Handle all defined implementation requests.
+    ///
+    /// Returns the plan of every file that was output, including the build manifest. If
+    /// `dry_run` is set in this session's config, then the plan is all that gets produced, and
+    /// the filesystem is left untouched. All implementation requests are validated before
+    /// anything is generated.
+    pub fn handle_all_implementations(&mut self) -> Result<CodegenPlan, CodegenError> {
+        let config = self.config;
+        let plan = generate_all(&config, self.ids()?)?;
+        self.record_plan(&plan)?;
+        Ok(plan)
+    }
+
+    /// Track every file in the plan as output during this session, and save the tracker. Nothing
+    /// gets tracked in a dry run.
+    pub(crate) fn record_plan(&mut self, plan: &CodegenPlan) -> Result<(), CodegenError> {
+        if self.config.dry_run {
+            return Ok(());
+        }
+        for file in &plan.files {
+            self.autogen.record(file)?;
+        }
+        self.save_autogen()
+    }
+
+    /// Regenerate everything in memory, and compare it against every planned file and every file
+    /// tracked by a previous run. Nothing gets written, regardless of `dry_run`.
+    pub fn verify(&mut self) -> Result<DriftReport, CodegenError> {
+        let dry_run_cfg = CodegenConfig {
+            dry_run: true,
+            ..self.config
+        };
+        let plan = generate_all(&dry_run_cfg, self.ids()?)?;
+        find_drift(&plan, &tracked_files()?, &manifest_files()?)
+    }
+
+    /// Output everything output during this session to the tracker file, after cleaning up any
+    /// files that were generated in a previous run but not in this one.
+    pub fn save_autogen(&mut self) -> Result<(), CodegenError> {
+        self.autogen.save()
+    }
+}
+
+/// Run something with the session that the free functions in this crate, such as
+/// `handle_all_implementations` and `start_imports`, act on.
+///
+/// The session stays borrowed until `f` returns, so `f` must not call any of those free functions
+/// itself.
+pub fn with_default_session<T>(f: impl FnOnce(&mut CodegenSession) -> T) -> T {
+    DEFAULT_SESSION.with(|s| f(&mut s.borrow_mut()))
+}
+
+/// Grab a new implementation ID from the default session, thus incrementing its ID counter.
+///
+/// The ID isn't tied to any concept, so it doesn't get recorded in the ID lock and won't stay
+/// stable across builds.
+#[deprecated(
+    since = "0.2.1",
+    note = "concept IDs are now handed out by name through `CodegenSession::ids`"
+)]
+pub fn grab_new_implementation_id(yin: bool) -> usize {
+    with_default_session(|s| {
+        let ids = match s.ids.take() {
+            Some(ids) => ids,
+            // there was no ID lock before, so carry on as if there still isn't one
+            None => IdAllocator::new(IdLock::read(ID_LOCK).unwrap_or_default(), yin),
+        };
+        s.ids.get_or_insert(ids).grab_unnamed()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::filesystem::{OutputStatus, PlannedFile};
+    use crate::tao::archetype::CreateImplementation;
+    use crate::tao::initialize_kb;
+    use zamm_yin::tao::archetype::{ArchetypeFormTrait, ArchetypeTrait};
+    use zamm_yin::tao::form::Form;
+
+    #[test]
+    fn test_independent_imports() {
+        let mut first = CodegenSession::default();
+        let second = CodegenSession::default();
+        first.start_imports();
+        assert!(first.import_in_progress());
+        assert!(!second.import_in_progress());
+    }
+
+    #[test]
+    fn test_end_imports() {
+        initialize_kb();
+        let mut session = CodegenSession::default();
+        session.start_imports();
+        let mut concept = Form::archetype().individuate_as_archetype();
+        concept.set_internal_name("session-import");
+        let implement = concept.implement_with_doc("Imported during a session.");
+        session.end_imports();
+        assert!(!session.import_in_progress());
+        assert!(KnowledgeGraphNode::from(implement.id()).is_imported());
+    }
+
+    #[test]
+    fn test_session_defines_imported_individual() {
+        initialize_kb();
+        let mut importing = CodegenSession::default();
+        importing.start_imports();
+        let defining = CodegenSession::default();
+        let imported = importing.define_individual(Form::archetype(), "session-imported", "Old.");
+        let defined = defining.define_individual(Form::archetype(), "session-defined", "New.");
+        assert!(KnowledgeGraphNode::from(imported.id()).is_imported());
+        assert!(
+            KnowledgeGraphNode::from(imported.concept_implementation().unwrap().id()).is_imported()
+        );
+        assert!(KnowledgeGraphNode::from(defined.id()).is_newly_defined());
+        assert!(!KnowledgeGraphNode::from(defined.id()).is_imported());
+    }
+
+    #[test]
+    fn test_ids_kept_between_generations() {
+        let mut session = CodegenSession::default();
+        let first = session.ids().unwrap().grab("session-concept");
+        let second = session.ids().unwrap().grab("other-session-concept");
+        assert_eq!(second, first + 1);
+        let other = session.take_ids().unwrap();
+        assert_eq!(other.lock().id("session-concept"), Some(first));
+        session.restore_ids(other);
+        assert_eq!(
+            session.ids().unwrap().lock().id("other-session-concept"),
+            Some(second)
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_grab_new_implementation_id() {
+        let first = grab_new_implementation_id(false);
+        assert_eq!(grab_new_implementation_id(false), first + 1);
+    }
+
+    #[test]
+    fn test_record_planned_file() {
+        let mut session = CodegenSession::default();
+        session
+            .autogen_mut()
+            .record(&PlannedFile {
+                path: "src/tao/form/session_form.rs".to_owned(),
+                code: String::new(),
+                git_ignore: Some("/project/src/tao/form/.gitignore".to_owned()),
+                cargo_track: false,
+                autogen: true,
+                status: OutputStatus::Created,
+            })
+            .unwrap();
+        let other = CodegenSession::default();
+        assert_eq!(session.autogen().files().len(), 2);
+        assert!(other.autogen().files().is_empty());
+    }
+
+    #[test]
+    fn test_skip_committed_file() {
+        let mut session = CodegenSession::default();
+        session
+            .autogen_mut()
+            .record(&PlannedFile {
+                path: "yang.lock".to_owned(),
+                code: String::new(),
+                git_ignore: None,
+                cargo_track: false,
+                autogen: false,
+                status: OutputStatus::Created,
+            })
+            .unwrap();
+        assert!(session.autogen().files().is_empty());
+    }
+}
@@ -0,0 +1,518 @@
+use crate::codegen::CodegenError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// A single variant of an enumeration data concept.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VariantSpec {
+    /// Internal name of the variant.
+    pub name: String,
+    /// Documentation for the variant.
+    pub doc: String,
+}
+
+/// A concept to define, as `define_child!` would.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConceptSpec {
+    /// Internal name of the concept.
+    pub name: String,
+    /// Internal name of the concept's parent. Concepts without one get defined directly under
+    /// Tao, as `define!` would.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// Documentation for the concept. Concepts without documentation don't get implemented.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    /// The Rust primitive that this data concept is implemented by.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rust_primitive: Option<String>,
+    /// The syntax used to refer to an unboxed version of the Rust primitive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unboxed_representation: Option<String>,
+    /// Rust code for the default value of this data concept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<String>,
+    /// Rust code for the dummy test value of this data concept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dummy_value: Option<String>,
+    /// Variants that this data concept can take on, if it is an enumeration.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<VariantSpec>,
+}
+
+/// An accessor on an attribute's value type, for retrieving the owners that point to a value.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InverseSpec {
+    /// Name of the accessor.
+    pub name: String,
+    /// Documentation for the accessor.
+    pub dual_doc: String,
+}
+
+/// An attribute to define and add to its owner, as `add_attr!` would.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AttributeSpec {
+    /// Internal name of the attribute.
+    pub name: String,
+    /// Internal name of the attribute's parent. Defaults to `attribute`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// Internal name of the concept that has this attribute. Defaults to Tao.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Internal name of the concept that this attribute points to. Defaults to Tao.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Documentation for the attribute itself.
+    pub doc: String,
+    /// Documentation for the attribute's getter and setter.
+    pub dual_doc: String,
+    /// Whether or not the attribute can have more than one value.
+    #[serde(default)]
+    pub multi_valued: bool,
+    /// Whether or not the attribute is not inherited by descendants of the owner.
+    #[serde(default)]
+    pub nonhereditary: bool,
+    /// Accessor to generate on the value type for retrieving the owners of a value, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inverse: Option<InverseSpec>,
+    /// Whether or not generated builders refuse to build the owner without this attribute, and
+    /// generated validation reports owners that lack it.
+    #[serde(default)]
+    pub required: bool,
+    /// Fewest values that the owner needs for this attribute to pass generated validation. Being
+    /// `required` is the same as needing at least one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_values: Option<usize>,
+    /// Most values that the attribute keeps. Once the owner has this many, generated setters
+    /// replace the oldest value instead of adding another one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_values: Option<usize>,
+}
+
+/// A flag to define and add to its owner, as `add_flag!` would.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlagSpec {
+    /// Internal name of the flag.
+    pub name: String,
+    /// Internal name of the flag's parent. Defaults to `flag`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// Internal name of the concept that has this flag.
+    pub owner: String,
+    /// Documentation for the flag itself.
+    pub doc: String,
+    /// Documentation for the flag's getter and setter.
+    pub dual_doc: String,
+    /// Whether or not the flag is not inherited by descendants of the owner.
+    #[serde(default)]
+    pub nonhereditary: bool,
+}
+
+/// The values of one of an individual's attributes. A single value doesn't have to be put in a
+/// list.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeValues {
+    /// The internal name of the only value.
+    One(String),
+    /// The internal names of every value, for multi-valued attributes.
+    Many(Vec<String>),
+}
+
+impl AttributeValues {
+    /// The internal names of every value, in order.
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            AttributeValues::One(name) => vec![name],
+            AttributeValues::Many(names) => names.iter().map(|n| n.as_str()).collect(),
+        }
+    }
+}
+
+/// A named individual to define, as `define_individual` would.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndividualSpec {
+    /// Internal name of the individual.
+    pub name: String,
+    /// Internal name of the archetype that the individual is an instance of.
+    pub archetype: String,
+    /// Documentation for the individual's accessor.
+    pub doc: String,
+    /// Values of the individual's attributes, keyed by the internal name of each attribute. Each
+    /// value is the internal name of another concept or individual, and multi-valued attributes
+    /// can take a list of them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, AttributeValues>,
+}
+
+/// A module to implement for a concept, as `module!` would.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModuleSpec {
+    /// Internal name of the concept that the module is for.
+    pub concept: String,
+    /// Documentation for the module.
+    pub doc: String,
+    /// Extension traits that the module includes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+}
+
+/// A declarative description of concepts to define, so that they can be specified without
+/// writing Rust code.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spec {
+    /// Concepts to define.
+    #[serde(default, rename = "concept", skip_serializing_if = "Vec::is_empty")]
+    pub concepts: Vec<ConceptSpec>,
+    /// Attributes to define.
+    #[serde(default, rename = "attribute", skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<AttributeSpec>,
+    /// Flags to define.
+    #[serde(default, rename = "flag", skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<FlagSpec>,
+    /// Named individuals to define.
+    #[serde(default, rename = "individual", skip_serializing_if = "Vec::is_empty")]
+    pub individuals: Vec<IndividualSpec>,
+    /// Modules to implement.
+    #[serde(default, rename = "module", skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<ModuleSpec>,
+}
+
+impl Spec {
+    /// Whether or not the spec defines nothing at all.
+    pub fn is_empty(&self) -> bool {
+        self.concepts.is_empty()
+            && self.attributes.is_empty()
+            && self.flags.is_empty()
+            && self.individuals.is_empty()
+            && self.modules.is_empty()
+    }
+
+    /// Read a spec from TOML.
+    pub fn from_toml(content: &str) -> Result<Self, CodegenError> {
+        toml::from_str(content).map_err(|e| CodegenError::InvalidSpec {
+            reason: e.to_string(),
+        })
+    }
+
+    /// Read a spec from JSON.
+    pub fn from_json(content: &str) -> Result<Self, CodegenError> {
+        serde_json::from_str(content).map_err(|e| CodegenError::InvalidSpec {
+            reason: e.to_string(),
+        })
+    }
+
+    /// Read a spec from the `toml` code blocks of a Markdown document, so that specs can be written
+    /// in the same literate style as `yin.md`. All other code blocks are ignored.
+    ///
+    /// Documents like `yin.md` itself define their concepts in `rust` code blocks instead, which
+    /// only ZAMM can run from a build script. If the `toml` blocks of a document with `rust` blocks
+    /// don't make for a spec that defines anything, the error says as much.
+    pub fn from_markdown(content: &str) -> Result<Self, CodegenError> {
+        let mut toml_content = String::new();
+        let mut has_rust_blocks = false;
+        let mut in_toml_block = false;
+        let mut in_other_block = false;
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if in_toml_block || in_other_block {
+                if trimmed.starts_with("```") {
+                    in_toml_block = false;
+                    in_other_block = false;
+                } else if in_toml_block {
+                    toml_content.push_str(line);
+                    toml_content.push('\n');
+                }
+            } else if let Some(info) = trimmed.strip_prefix("```") {
+                in_toml_block = info.trim() == "toml";
+                in_other_block = !in_toml_block;
+                has_rust_blocks |= info.trim() == "rust";
+            }
+        }
+        let spec = Self::from_toml(&toml_content);
+        if !has_rust_blocks {
+            return spec;
+        }
+        let reason = match spec {
+            Ok(spec) if !spec.is_empty() => return Ok(spec),
+            Ok(_) => "the toml code blocks don't define anything".to_owned(),
+            Err(CodegenError::InvalidSpec { reason }) => reason,
+            Err(e) => return Err(e),
+        };
+        Err(CodegenError::InvalidSpec {
+            reason: format!(
+                "{}, and rust code blocks can only be run by ZAMM from a build script",
+                reason
+            ),
+        })
+    }
+
+    /// Read a spec from a file. Files ending in `.json` are read as JSON, files ending in `.md` are
+    /// read as Markdown, and everything else as TOML.
+    ///
+    /// A file that doesn't define anything is almost certainly a mistake, so it counts as invalid.
+    pub fn read(path: &str) -> Result<Self, CodegenError> {
+        let content = fs::read_to_string(path).map_err(|e| CodegenError::io(path, e))?;
+        let spec = match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&content),
+            Some("md") => Self::from_markdown(&content),
+            _ => Self::from_toml(&content),
+        }?;
+        if spec.is_empty() {
+            return Err(CodegenError::InvalidSpec {
+                reason: format!("{} doesn't define anything", path),
+            });
+        }
+        Ok(spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn example_spec() -> Spec {
+        Spec {
+            concepts: vec![ConceptSpec {
+                name: "color".to_owned(),
+                parent: Some("data".to_owned()),
+                doc: Some("A color of light.".to_owned()),
+                variants: vec![VariantSpec {
+                    name: "red".to_owned(),
+                    doc: "The color red.".to_owned(),
+                }],
+                ..ConceptSpec::default()
+            }],
+            attributes: vec![AttributeSpec {
+                name: "hue".to_owned(),
+                owner: Some("lamp".to_owned()),
+                value: Some("color".to_owned()),
+                doc: "The color of a lamp.".to_owned(),
+                dual_doc: "the color of this lamp.".to_owned(),
+                multi_valued: true,
+                ..AttributeSpec::default()
+            }],
+            flags: vec![],
+            individuals: vec![],
+            modules: vec![ModuleSpec {
+                concept: "color".to_owned(),
+                doc: "Colors of light.".to_owned(),
+                extensions: vec![],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let spec = Spec::from_toml(indoc! {r#"
+            [[concept]]
+            name = "color"
+            parent = "data"
+            doc = "A color of light."
+            variants = [{ name = "red", doc = "The color red." }]
+
+            [[attribute]]
+            name = "hue"
+            owner = "lamp"
+            value = "color"
+            doc = "The color of a lamp."
+            dual_doc = "the color of this lamp."
+            multi_valued = true
+
+            [[module]]
+            concept = "color"
+            doc = "Colors of light."
+        "#})
+        .unwrap();
+        assert_eq!(spec, example_spec());
+    }
+
+    #[test]
+    fn test_individuals_from_toml() {
+        let spec = Spec::from_toml(indoc! {r#"
+            [[individual]]
+            name = "desk-lamp"
+            archetype = "lamp"
+            doc = "The lamp on the desk."
+            attributes = { hue = "red", bulbs = ["left-bulb", "right-bulb"] }
+        "#})
+        .unwrap();
+        let mut attributes = BTreeMap::new();
+        attributes.insert("hue".to_owned(), AttributeValues::One("red".to_owned()));
+        attributes.insert(
+            "bulbs".to_owned(),
+            AttributeValues::Many(vec!["left-bulb".to_owned(), "right-bulb".to_owned()]),
+        );
+        assert_eq!(
+            spec.individuals,
+            vec![IndividualSpec {
+                name: "desk-lamp".to_owned(),
+                archetype: "lamp".to_owned(),
+                doc: "The lamp on the desk.".to_owned(),
+                attributes,
+            }]
+        );
+        assert_eq!(
+            spec.individuals[0].attributes["bulbs"].names(),
+            vec!["left-bulb", "right-bulb"]
+        );
+    }
+
+    #[test]
+    fn test_cardinality_from_toml() {
+        let spec = Spec::from_toml(indoc! {r#"
+            [[attribute]]
+            name = "bulb"
+            doc = "The bulbs of a lamp."
+            dual_doc = "the bulbs of this lamp."
+            multi_valued = true
+            min_values = 1
+            max_values = 3
+        "#})
+        .unwrap();
+        assert_eq!(spec.attributes[0].min_values, Some(1));
+        assert_eq!(spec.attributes[0].max_values, Some(3));
+    }
+
+    #[test]
+    fn test_inverse_from_toml() {
+        let spec = Spec::from_toml(indoc! {r#"
+            [[attribute]]
+            name = "hue"
+            doc = "The color of a lamp."
+            dual_doc = "the color of this lamp."
+            inverse = { name = "lamps", dual_doc = "the lamps with this color." }
+            required = true
+        "#})
+        .unwrap();
+        assert!(spec.attributes[0].required);
+        assert_eq!(
+            spec.attributes[0].inverse,
+            Some(InverseSpec {
+                name: "lamps".to_owned(),
+                dual_doc: "the lamps with this color.".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_json() {
+        let spec = Spec::from_json(indoc! {r#"
+            {
+                "concept": [{
+                    "name": "color",
+                    "parent": "data",
+                    "doc": "A color of light.",
+                    "variants": [{"name": "red", "doc": "The color red."}]
+                }],
+                "attribute": [{
+                    "name": "hue",
+                    "owner": "lamp",
+                    "value": "color",
+                    "doc": "The color of a lamp.",
+                    "dual_doc": "the color of this lamp.",
+                    "multi_valued": true
+                }],
+                "module": [{"concept": "color", "doc": "Colors of light."}]
+            }
+        "#})
+        .unwrap();
+        assert_eq!(spec, example_spec());
+    }
+
+    #[test]
+    fn test_from_markdown() {
+        let spec = Spec::from_markdown(indoc! {r#"
+            # Colors
+
+            Colors are data:
+
+            ```toml
+            [[concept]]
+            name = "color"
+            parent = "data"
+            doc = "A color of light."
+            variants = [{ name = "red", doc = "The color red." }]
+            ```
+
+            Rust code is left for other tools:
+
+            ```rust
+            define!(ignored);
+            ```
+
+            ```toml
+            [[attribute]]
+            name = "hue"
+            owner = "lamp"
+            value = "color"
+            doc = "The color of a lamp."
+            dual_doc = "the color of this lamp."
+            multi_valued = true
+
+            [[module]]
+            concept = "color"
+            doc = "Colors of light."
+            ```
+        "#})
+        .unwrap();
+        assert_eq!(spec, example_spec());
+    }
+
+    #[test]
+    fn test_markdown_with_only_rust() {
+        let literate = indoc! {r#"
+            ```rust
+            define!(lamp);
+            ```
+
+            ```toml
+            zamm_yang = "0.2.0"
+            ```
+        "#};
+        match Spec::from_markdown(literate) {
+            Err(CodegenError::InvalidSpec { reason }) => {
+                assert!(reason.contains("unknown field `zamm_yang`"));
+                assert!(reason.ends_with("can only be run by ZAMM from a build script"));
+            }
+            other => panic!("Expected an invalid spec, got {:?}", other),
+        }
+        assert!(Spec::from_markdown("```rust\ndefine!(lamp);\n```\n").is_err());
+        assert!(Spec::from_markdown("# Nothing\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_spec() {
+        assert!(Spec::from_toml("[[concept]]\nparent = \"form\"").is_err());
+    }
+
+    #[test]
+    fn test_unknown_key() {
+        let misspelled = Spec::from_toml(indoc! {r#"
+            [[attribute]]
+            name = "hue"
+            doc = "The color of a lamp."
+            dual_doc = "the color of this lamp."
+            multivalued = true
+        "#});
+        match misspelled {
+            Err(CodegenError::InvalidSpec { reason }) => {
+                assert!(reason.contains("unknown field `multivalued`"))
+            }
+            other => panic!("Expected an invalid spec, got {:?}", other),
+        }
+        assert!(Spec::from_toml("[[concepts]]\nname = \"color\"").is_err());
+    }
+}
@@ -11,7 +11,7 @@ pub struct FileFragment {
     preamble: Option<AtomicFragment>,
     contents: Rc<RefCell<AppendedFragment>>,
     tests: Vec<Rc<RefCell<dyn CodeFragment>>>,
-    self_import: Option<String>,
+    self_imports: Vec<String>,
     current_crate: Option<Rc<str>>,
 }
 
@@ -36,10 +36,10 @@ impl FileFragment {
         self.tests.push(test);
     }
 
-    /// If there's something defined within this file, set the import path here, and it will be
+    /// If there's something defined within this file, add its import path here, and it will be
     /// excluded from the file's final set of imports.
-    pub fn set_self_import(&mut self, import: String) {
-        self.self_import = Some(import);
+    pub fn add_self_import(&mut self, import: String) {
+        self.self_imports.push(import);
     }
 
     /// Set the current crate for file imports.
@@ -62,10 +62,11 @@ impl FileFragment {
             combined.append(Rc::new(RefCell::new(test_mod)));
         }
 
-        let mut exluded_imports = vec![];
-        if let Some(excluded_import) = &self.self_import {
-            exluded_imports.push(excluded_import.as_str());
-        }
+        let exluded_imports = self
+            .self_imports
+            .iter()
+            .map(|i| i.as_str())
+            .collect::<Vec<&str>>();
         let imports = imports_as_str(
             &*self
                 .current_crate
@@ -12,6 +12,10 @@ pub enum SelfReference {
     Immutable,
     /// Equivalent to "&mut self" at the beginning of the arguments list.
     Mutable,
+    /// Equivalent to "self" at the beginning of the arguments list.
+    Owned,
+    /// Equivalent to "mut self" at the beginning of the arguments list.
+    OwnedMutable,
 }
 
 impl Default for SelfReference {
@@ -151,6 +155,8 @@ impl CodeFragment for FunctionFragment {
             SelfReference::None => (),
             SelfReference::Immutable => args.insert(0, "&self".to_owned()),
             SelfReference::Mutable => args.insert(0, "&mut self".to_owned()),
+            SelfReference::Owned => args.insert(0, "self".to_owned()),
+            SelfReference::OwnedMutable => args.insert(0, "mut self".to_owned()),
         };
         let args_str = args.iter().format(", ").to_string();
         let return_type = match &self.return_type {
@@ -321,6 +327,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_function_owned_self() {
+        let mut f = FunctionFragment::new("with_value".to_owned());
+        f.set_self_reference(SelfReference::OwnedMutable);
+        f.add_arg("value".to_owned(), "i64".to_owned());
+        f.set_return("Self".to_owned());
+        f.append(Rc::new(RefCell::new(AtomicFragment::new(
+            "self.value = value;\nself".to_owned(),
+        ))));
+
+        assert_eq!(
+            f.body(80),
+            indoc! {"
+                fn with_value(mut self, value: i64) -> Self {
+                    self.value = value;
+                    self
+                }"}
+        );
+    }
+
     #[test]
     fn test_function_imports() {
         let mut f = FunctionFragment::new("foo".to_owned());
@@ -12,6 +12,8 @@ pub struct TraitFragment {
     declaration: ItemDeclaration,
     /// Any traits that are required to be implemented before this one.
     required_traits: Vec<StructConfig>,
+    /// Imports needed by the trait declaration itself, beyond those of the required traits.
+    imports: Vec<String>,
     /// Actual internal code fragments for the trait.
     content: Rc<RefCell<AppendedFragment>>,
 }
@@ -30,6 +32,11 @@ impl TraitFragment {
         self.required_traits.push(required_trait);
     }
 
+    /// Add an import needed by the trait declaration itself.
+    pub fn add_import(&mut self, import: String) {
+        self.imports.push(import);
+    }
+
     /// Add a fragment to the internals of this trait.
     pub fn append(&mut self, fragment: Rc<RefCell<dyn CodeFragment>>) {
         self.content.borrow_mut().append(fragment);
@@ -45,6 +52,7 @@ impl Default for TraitFragment {
             name: String::default(),
             declaration,
             required_traits: vec![],
+            imports: vec![],
             content,
         }
     }
@@ -104,6 +112,7 @@ impl CodeFragment for TraitFragment {
             .iter()
             .map(|r| r.import.clone())
             .collect::<Vec<String>>();
+        imports.extend(self.imports.iter().cloned());
         imports.append(&mut self.content.borrow().imports());
         imports
     }
@@ -190,6 +199,7 @@ mod tests {
             name: "Baz".to_owned(),
             import: "crate::Baz".to_owned(),
         });
+        f.add_import("crate::Qux".to_owned());
         f.append(Rc::new(RefCell::new(AtomicFragment {
             imports: vec!["crate::operators::plus".to_owned()],
             atom: "".to_owned(),
@@ -197,7 +207,12 @@ mod tests {
 
         assert_eq!(
             f.imports(),
-            vec!["crate::Bar", "crate::Baz", "crate::operators::plus"]
+            vec![
+                "crate::Bar",
+                "crate::Baz",
+                "crate::Qux",
+                "crate::operators::plus"
+            ]
         );
     }
 }
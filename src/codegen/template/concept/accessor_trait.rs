@@ -0,0 +1,104 @@
+use crate::codegen::template::basic::{ImplementationFragment, ItemDeclarationAPI, TraitFragment};
+use crate::codegen::StructConfig;
+
+/// Config values at the time of accessor trait code generation.
+#[derive(Clone, Debug, Default)]
+pub struct AccessorTraitConfig {
+    /// The trait that holds the accessors.
+    pub accessor_trait: StructConfig,
+    /// The concept whose attributes and flags the accessors are for.
+    pub owner_type: StructConfig,
+}
+
+/// Get the declaration for a trait that holds attribute and flag accessors as default methods.
+///
+/// Accessors will still need to be added to the trait.
+pub fn accessor_trait_fragment(cfg: &AccessorTraitConfig) -> TraitFragment {
+    let mut accessor_trait = TraitFragment::new(cfg.accessor_trait.name.clone());
+    accessor_trait.mark_as_public();
+    accessor_trait.document(format!(
+        "Accessors for the attributes and flags of `{owner}`, shared with all of its descendants.",
+        owner = cfg.owner_type.name
+    ));
+    // accessors work directly with the underlying node
+    accessor_trait.add_required_trait(StructConfig {
+        name: "Deref<Target = FinalNode>".to_owned(),
+        import: "std::ops::Deref".to_owned(),
+    });
+    accessor_trait.add_required_trait(StructConfig::new("std::ops::DerefMut".to_owned()));
+    accessor_trait.add_import("zamm_yin::node_wrappers::FinalNode".to_owned());
+    accessor_trait
+}
+
+/// Get the implementation of an accessor trait for a concept. The implementation is empty,
+/// because all accessors are default methods.
+pub fn accessor_trait_impl(
+    accessor_trait: &StructConfig,
+    concept: &StructConfig,
+    same_file_as_trait: bool,
+) -> ImplementationFragment {
+    let mut implementation =
+        ImplementationFragment::new_trait_impl(accessor_trait.clone(), concept.clone());
+    implementation.mark_same_file_as_struct();
+    if same_file_as_trait {
+        implementation.mark_same_file_as_trait();
+    }
+    implementation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::template::basic::{AtomicFragment, CodeFragment};
+    use indoc::indoc;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn test_config() -> AccessorTraitConfig {
+        AccessorTraitConfig {
+            accessor_trait: StructConfig::new("crate::tao::perspective::BuildInfoTrait".to_owned()),
+            owner_type: StructConfig::new("crate::tao::perspective::BuildInfo".to_owned()),
+        }
+    }
+
+    #[test]
+    fn test_accessor_trait() {
+        let mut f = accessor_trait_fragment(&test_config());
+        f.append(Rc::new(RefCell::new(AtomicFragment::new(
+            "fn accessor(&self) {}".to_owned(),
+        ))));
+        assert_eq!(
+            f.body(80),
+            indoc! {"
+                /// Accessors for the attributes and flags of `BuildInfo`, shared with all of its
+                /// descendants.
+                pub trait BuildInfoTrait: Deref<Target = FinalNode> + DerefMut {
+                    fn accessor(&self) {}
+                }"}
+        );
+        assert_eq!(
+            f.imports(),
+            vec![
+                "std::ops::Deref",
+                "std::ops::DerefMut",
+                "zamm_yin::node_wrappers::FinalNode"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_accessor_trait_impl() {
+        let cfg = test_config();
+        let f = accessor_trait_impl(
+            &cfg.accessor_trait,
+            &StructConfig::new("crate::tao::perspective::BuildSubInfo".to_owned()),
+            false,
+        );
+        assert_eq!(f.body(80), "impl BuildInfoTrait for BuildSubInfo {}");
+        assert_eq!(f.imports(), vec!["crate::tao::perspective::BuildInfoTrait"]);
+
+        let own_impl = accessor_trait_impl(&cfg.accessor_trait, &cfg.owner_type, true);
+        assert_eq!(own_impl.body(80), "impl BuildInfoTrait for BuildInfo {}");
+        assert_eq!(own_impl.imports(), Vec::<String>::new());
+    }
+}
@@ -1,6 +1,10 @@
-use crate::codegen::template::basic::{CodeFragment, ItemDeclarationAPI, ModuleFragment};
+use crate::codegen::template::basic::{
+    AtomicFragment, CodeFragment, FunctionFragment, ItemDeclarationAPI, ModuleFragment,
+};
 use crate::codegen::CODE_WIDTH;
 use heck::{CamelCase, SnakeCase};
+use indoc::formatdoc;
+use std::cell::RefCell;
 use std::rc::Rc;
 
 /// Config values at the time of Archetype module code generation.
@@ -10,12 +14,40 @@ pub struct ArchetypeModuleConfig {
     pub doc: Option<Rc<str>>,
     /// Names of Archetypes to be included directly in this module.
     pub archetype_names: Vec<Rc<str>>,
+    /// Names of the Archetypes in `archetype_names` that also come with an accessor trait.
+    pub accessor_traits: Vec<Rc<str>>,
+    /// Names of the Archetypes in `archetype_names` that also come with a generated enum.
+    pub enums: Vec<Rc<str>>,
     /// Submodules that are not to be accessible outside of this module.
     pub private_submodules: Vec<String>,
     /// Submodules that are to be accessible outside of this module. Usually user-defined ones.
     pub public_submodules: Vec<String>,
     /// Re-exports from this module.
     pub re_exports: Vec<String>,
+    /// Path to the `initialize_kb` function of the crate being built on, if this is the crate's
+    /// root module. The root module declares the generated KB initialization file, and defines an
+    /// `initialize_kb` of its own that chains on from the dependency's.
+    pub root_init: Option<String>,
+    /// Whether or not the generated KB initialization file initializes any concepts at all. Only
+    /// then does it define the `AnyConcept` enum and the `Visitor` trait for the root module to
+    /// re-export.
+    pub initializes_concepts: bool,
+}
+
+/// Returns a function that initializes the dependency's concepts, followed by this crate's.
+fn initialize_kb_fragment(dependency_init: &str) -> FunctionFragment {
+    let mut init_fn = FunctionFragment::new("initialize_kb".to_owned());
+    init_fn.mark_as_public();
+    init_fn.document(
+        "Initialize the KB with the concepts of this crate, along with those of every crate it \
+        builds on."
+            .to_owned(),
+    );
+    init_fn.append(Rc::new(RefCell::new(AtomicFragment::new(formatdoc! {"
+        {dependency_init}();
+        auto_init::initialize_types();
+    ", dependency_init = dependency_init}))));
+    init_fn
 }
 
 /// Returns a module that represents an archetype and its descendants.
@@ -37,6 +69,20 @@ pub fn archetype_module_fragment(cfg: &ArchetypeModuleConfig) -> ModuleFragment
             form_module_name,
             archetype_name.to_camel_case()
         ));
+        if cfg.accessor_traits.contains(archetype_name) {
+            module.re_export(format!(
+                "{}::{}Trait",
+                form_module_name,
+                archetype_name.to_camel_case()
+            ));
+        }
+        if cfg.enums.contains(archetype_name) {
+            module.re_export(format!(
+                "{}::{}Value",
+                form_module_name,
+                archetype_name.to_camel_case()
+            ));
+        }
     }
 
     for private_module in &cfg.private_submodules {
@@ -50,6 +96,21 @@ pub fn archetype_module_fragment(cfg: &ArchetypeModuleConfig) -> ModuleFragment
         module.re_export(re_export.clone());
     }
 
+    if let Some(dependency_init) = &cfg.root_init {
+        module.add_submodule("auto_init".to_owned());
+        module.re_export("auto_init::YIN_MAX_ID".to_owned());
+        module.re_export("auto_init::DowncastError".to_owned());
+        module.re_export("auto_init::MissingAttributeError".to_owned());
+        if cfg.initializes_concepts {
+            module.re_export("auto_init::AnyConcept".to_owned());
+            module.re_export("auto_init::Visitor".to_owned());
+            module.re_export("auto_init::walk".to_owned());
+        }
+        module.append(Rc::new(RefCell::new(initialize_kb_fragment(
+            dependency_init,
+        ))));
+    }
+
     module
 }
 
@@ -74,9 +135,13 @@ mod tests {
                 Rc::from("concept-one"),
                 Rc::from("concept-two"),
             ],
+            accessor_traits: vec![Rc::from("primary")],
+            enums: vec![Rc::from("concept-two")],
             private_submodules: vec![],
             public_submodules: vec!["subtype".to_owned(), "primary_extension".to_owned()],
             re_exports: vec!["zamm_yin::path::to::primary::*".to_owned()],
+            root_init: None,
+            initializes_concepts: false,
         });
 
         assert_eq!(
@@ -92,10 +157,50 @@ mod tests {
                 mod primary_form;
 
                 pub use concept_one_form::ConceptOne;
-                pub use concept_two_form::ConceptTwo;
-                pub use primary_form::Primary;
+                pub use concept_two_form::{ConceptTwo, ConceptTwoValue};
+                pub use primary_form::{Primary, PrimaryTrait};
                 pub use zamm_yin::path::to::primary::*;
             "}
         );
     }
+
+    #[test]
+    fn test_root_module() {
+        let frag = archetype_module_fragment(&ArchetypeModuleConfig {
+            public_submodules: vec!["form".to_owned()],
+            root_init: Some("zamm_yin::tao::initialize_kb".to_owned()),
+            initializes_concepts: true,
+            ..ArchetypeModuleConfig::default()
+        });
+
+        assert_eq!(
+            frag.body(80),
+            indoc! {"
+                pub mod form;
+
+                mod auto_init;
+
+                pub use auto_init::{walk, AnyConcept, DowncastError, MissingAttributeError, Visitor, YIN_MAX_ID};
+
+                /// Initialize the KB with the concepts of this crate, along with those of every
+                /// crate it builds on.
+                pub fn initialize_kb() {
+                    zamm_yin::tao::initialize_kb();
+                    auto_init::initialize_types();
+                }
+            "}
+        );
+    }
+
+    #[test]
+    fn test_root_module_without_concepts() {
+        let frag = archetype_module_fragment(&ArchetypeModuleConfig {
+            root_init: Some("zamm_yin::tao::initialize_kb".to_owned()),
+            ..ArchetypeModuleConfig::default()
+        });
+
+        assert!(frag
+            .body(80)
+            .contains("pub use auto_init::{DowncastError, MissingAttributeError, YIN_MAX_ID};"));
+    }
 }
@@ -1,7 +1,7 @@
 use super::util::kb_test_function;
 use crate::codegen::template::basic::{
     AtomicFragment, FileFragment, FunctionCallFragment, FunctionFragment, ImplementationFragment,
-    ItemDeclarationAPI, SelfReference,
+    ItemDeclarationAPI, SelfReference, TraitFragment,
 };
 use crate::codegen::StructConfig;
 use indoc::formatdoc;
@@ -56,6 +56,9 @@ pub struct AttributePropertyConfig {
     pub rust_primitive: Option<Rc<str>>,
     /// Code for the Rust primitive when in an unboxed representation.
     pub rust_primitive_unboxed: Option<Rc<str>>,
+    /// Import path for the Rust primitive, if it isn't already in scope. This is the case for
+    /// generated enums.
+    pub rust_primitive_import: Option<String>,
     /// Dummy default test value to set the primitive to.
     pub primitive_test_value: Option<Rc<str>>,
     /// Dummy override test value to set the primitive to.
@@ -65,6 +68,9 @@ pub struct AttributePropertyConfig {
     pub hereditary: bool,
     /// Whether or not this attribute can contain multiple values.
     pub multi_valued: bool,
+    /// The most values that the attribute keeps, if it's capped at all. Once the owner has this
+    /// many, newer values replace older ones.
+    pub max_values: Option<usize>,
 }
 
 impl Default for AttributePropertyConfig {
@@ -78,10 +84,12 @@ impl Default for AttributePropertyConfig {
             value_type: StructConfig::default(),
             rust_primitive: None,
             rust_primitive_unboxed: None,
+            rust_primitive_import: None,
             primitive_test_value: None,
             dummy_test_value: None,
             hereditary: true,
             multi_valued: false,
+            max_values: None,
         }
     }
 }
@@ -118,14 +126,24 @@ fn setter_fragment(cfg: &AttributePropertyConfig) -> FunctionFragment {
     f.add_import("zamm_yin::tao::form::FormTrait".to_owned());
     f.add_import("zamm_yin::node_wrappers::BaseNodeTrait".to_owned());
     f.add_import("zamm_yin::node_wrappers::CommonNodeTrait".to_owned());
+    if let Some(import) = &cfg.rust_primitive_import {
+        f.add_import(import.clone());
+    }
 
     if cfg.public {
         f.mark_as_public();
     }
-    if cfg.multi_valued {
-        f.document(format!("Add one of {}", cfg.doc));
-    } else {
-        f.document(format!("Set {}", cfg.doc));
+    match (cfg.multi_valued, cfg.max_values) {
+        (true, Some(max)) if cfg.rust_primitive.is_some() => f.document(format!(
+            "Add one of {} Once there are {} of them, the oldest one gets dropped to make room.",
+            cfg.doc, max
+        )),
+        (true, Some(max)) => f.document(format!(
+            "Add one of {} Once there are {} of them, any more get ignored.",
+            cfg.doc, max
+        )),
+        (true, None) => f.document(format!("Add one of {}", cfg.doc)),
+        (false, _) => f.document(format!("Set {}", cfg.doc)),
     }
     f.set_self_reference(SelfReference::Mutable);
 
@@ -133,6 +151,9 @@ fn setter_fragment(cfg: &AttributePropertyConfig) -> FunctionFragment {
     match &cfg.rust_primitive_unboxed {
         Some(unboxed_primitive) => {
             f.add_arg(arg_name, unboxed_primitive.to_string());
+            if let Some(max) = cfg.max_values {
+                f.append(Rc::new(RefCell::new(replace_fragment(cfg, max))));
+            }
             f.append(Rc::new(RefCell::new(AtomicFragment::new(formatdoc! {"
                 let mut value_concept = {value_concept}::new();
                 value_concept.set_value({value});",
@@ -140,7 +161,13 @@ fn setter_fragment(cfg: &AttributePropertyConfig) -> FunctionFragment {
                 value = cfg.property_name
             }))));
         }
-        None => f.add_arg(arg_name, format!("&{}", cfg.value_type.name)),
+        None => {
+            f.add_arg(arg_name, format!("&{}", cfg.value_type.name));
+            // a single value gets overridden by setting another one, so there's nothing to cap
+            if let (true, Some(max)) = (cfg.multi_valued, cfg.max_values) {
+                f.append(Rc::new(RefCell::new(replace_fragment(cfg, max))));
+            }
+        }
     };
 
     let final_value = if cfg.rust_primitive.is_some() {
@@ -160,6 +187,56 @@ fn setter_fragment(cfg: &AttributePropertyConfig) -> FunctionFragment {
     f
 }
 
+/// Code that keeps the owner from having more than the maximum number of values. Primitive values
+/// get replaced in place, because they're only ever created by the setter. Any other value might
+/// also be pointed to by other concepts, and edges can't be removed, so once the owner has the
+/// maximum number of those, new ones get ignored instead.
+fn replace_fragment(cfg: &AttributePropertyConfig, max: usize) -> AtomicFragment {
+    let replacement = if cfg.rust_primitive.is_none() {
+        formatdoc! {"
+            if existing.len() >= {max} {{
+                // edges can't be removed, and these values might be shared, so there's no room
+                return;
+            }}",
+            max = max,
+        }
+    } else if max == 1 {
+        formatdoc! {"
+            if let Some(current) = existing.last() {{
+                // edges can't be removed, so the current value gets overwritten instead
+                {value_concept}::from(current.id()).set_value({value});
+                return;
+            }}",
+            value_concept = cfg.value_type.name,
+            value = cfg.property_name,
+        }
+    } else {
+        formatdoc! {"
+            if existing.len() >= {max} {{
+                // edges can't be removed, so newer values get shifted down instead
+                for pair in existing.windows(2) {{
+                    let mut older = pair[0];
+                    older.set_value(pair[1].value().unwrap());
+                }}
+                {value_concept}::from(existing.last().unwrap().id()).set_value({value});
+                return;
+            }}",
+            max = max,
+            value_concept = cfg.value_type.name,
+            value = cfg.property_name,
+        }
+    };
+    AtomicFragment::new(formatdoc! {"
+        let existing = self
+            .deref()
+            .base_wrapper()
+            .outgoing_nodes({attr}::TYPE_ID);
+        {replacement}",
+        attr = cfg.attr.name,
+        replacement = replacement,
+    })
+}
+
 /// Get the getter fragment for the attribute property.
 fn getter_fragment(cfg: &AttributePropertyConfig) -> FunctionFragment {
     let mut f = FunctionFragment::new(getter_name(cfg).to_string());
@@ -168,6 +245,9 @@ fn getter_fragment(cfg: &AttributePropertyConfig) -> FunctionFragment {
     f.add_import("zamm_yin::tao::archetype::ArchetypeTrait".to_owned());
     f.add_import("zamm_yin::tao::form::FormTrait".to_owned());
     f.add_import("zamm_yin::node_wrappers::BaseNodeTrait".to_owned());
+    if let Some(import) = &cfg.rust_primitive_import {
+        f.add_import(import.clone());
+    }
 
     if cfg.public {
         f.mark_as_public();
@@ -201,14 +281,19 @@ fn getter_fragment(cfg: &AttributePropertyConfig) -> FunctionFragment {
         ""
     };
     let collection = if cfg.multi_valued {
-        ".into_iter()"
+        match cfg.max_values {
+            // values beyond the maximum can still come from ancestors or from other code that
+            // adds edges directly, so only the latest ones count
+            Some(max) => format!(".into_iter()\n    .rev()\n    .take({})\n    .rev()", max),
+            None => ".into_iter()".to_owned(),
+        }
     } else {
         // outgoing nodes are sorted by ID, and more specific nodes are created later, resulting in
         // higher IDs. This is how overrides happen.
         //
         // todo: implement this properly, because this forces things to be defined in a certain
         // order in yin.md
-        ".last()"
+        ".last()".to_owned()
     };
     let post_collection = if cfg.multi_valued {
         "\n    .collect()"
@@ -244,6 +329,9 @@ fn test_fragment(cfg: &AttributePropertyConfig) -> FunctionFragment {
         // if some, will use that directly instead of the concept
         f.add_import(cfg.value_type.import.clone());
     }
+    if let Some(import) = &cfg.rust_primitive_import {
+        f.add_import(import.clone());
+    }
     let value_cfg = primitive_config(cfg, &cfg.primitive_test_value, "value");
     let empty_value = if cfg.multi_valued {
         "vec![]".to_owned()
@@ -311,6 +399,9 @@ fn test_inheritance_fragment(cfg: &AttributePropertyConfig) -> FunctionFragment
     f.add_attribute("allow(clippy::clone_double_ref)".to_owned());
     f.add_import("crate::tao::initialize_kb".to_owned());
     f.add_import(cfg.owner_type.import.clone());
+    if let Some(import) = &cfg.rust_primitive_import {
+        f.add_import(import.clone());
+    }
     // todo: only clone when it's not a Copy type, to avoid the clone_on_copy warning. The thing
     // is, this is specific to Rust, so it should be Yang-only knowledge.
     f.append(Rc::new(RefCell::new(AtomicFragment::new(formatdoc! {"
@@ -351,6 +442,9 @@ fn test_multi_set_fragment(cfg: &AttributePropertyConfig) -> Option<FunctionFrag
         // if some, will use that directly instead of the concept
         f.add_import(cfg.value_type.import.clone());
     }
+    if let Some(import) = &cfg.rust_primitive_import {
+        f.add_import(import.clone());
+    }
 
     let default_value_cfg = primitive_config(cfg, &cfg.primitive_test_value, "default");
     let default_value_get = if cfg.multi_valued {
@@ -365,7 +459,9 @@ fn test_multi_set_fragment(cfg: &AttributePropertyConfig) -> Option<FunctionFrag
     };
 
     let new_value_cfg = primitive_config(cfg, &cfg.dummy_test_value, "new_value");
-    let expected_get = if cfg.multi_valued {
+    let expected_get = if cfg.multi_valued && cfg.max_values == Some(1) {
+        format!("vec![{}]", new_value_cfg.value_get)
+    } else if cfg.multi_valued {
         format!(
             "vec![{}, {}]",
             default_value_cfg.value_get, new_value_cfg.value_get
@@ -404,6 +500,92 @@ fn test_multi_set_fragment(cfg: &AttributePropertyConfig) -> Option<FunctionFrag
     Some(f)
 }
 
+/// Test that setting more values than the attribute keeps only leaves the latest primitive values,
+/// or the earliest other values, around. Returns None if the attribute isn't capped, or if it's
+/// single-valued and not a primitive, because then the setter has nothing extra to enforce.
+fn test_max_values_fragment(cfg: &AttributePropertyConfig) -> Option<FunctionFragment> {
+    let max = cfg.max_values?;
+    if cfg.rust_primitive.is_some() && cfg.primitive_test_value.is_none() {
+        return None;
+    }
+    if !cfg.multi_valued && cfg.rust_primitive.is_none() {
+        return None;
+    }
+
+    let mut f = kb_test_function(&format!("test_{}_max_values", cfg.property_name));
+    f.add_attribute("allow(clippy::clone_double_ref)".to_owned());
+    f.add_import(cfg.owner_type.import.clone());
+    if let Some(import) = &cfg.rust_primitive_import {
+        f.add_import(import.clone());
+    }
+    if cfg.rust_primitive.is_none() {
+        // values that aren't primitives need to be distinct to tell which ones were kept
+        f.add_import(cfg.value_type.import.clone());
+        f.append(Rc::new(RefCell::new(AtomicFragment::new(formatdoc! {"
+            let mut new_instance = {owner}::new();
+            let values = (0..{attempts})
+                .map(|_| {value}::new())
+                .collect::<Vec<{value}>>();
+            for value in &values {{
+                new_instance.{setter}(value);
+            }}
+            assert_eq!(new_instance.{getter}(), values[..{max}].to_vec());",
+            owner = cfg.owner_type.name,
+            value = cfg.value_type.name,
+            attempts = max + 1,
+            setter = setter_name(cfg),
+            getter = getter_name(cfg),
+            max = max,
+        }))));
+        return Some(f);
+    }
+    let value_cfg = primitive_config(cfg, &cfg.primitive_test_value, "value");
+    let getter_check = if cfg.multi_valued {
+        format!(
+            "assert_eq!(new_instance.{}().len(), {});",
+            getter_name(cfg),
+            max
+        )
+    } else {
+        format!(
+            "assert_eq!(new_instance.{}(), Some({}));",
+            getter_name(cfg),
+            value_cfg.value_get
+        )
+    };
+    // primitive values get replaced in place instead of piling up
+    f.add_import(cfg.attr.import.clone());
+    f.add_import("std::ops::Deref".to_owned());
+    f.add_import("zamm_yin::tao::archetype::ArchetypeTrait".to_owned());
+    f.add_import("zamm_yin::node_wrappers::BaseNodeTrait".to_owned());
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(formatdoc! {"
+        let mut new_instance = {owner}::new();
+        let value = {value};
+        for _ in 0..{attempts} {{
+            #[allow(clippy::clone_on_copy)]
+            new_instance.{setter}({value_set});
+        }}
+        {getter_check}
+        assert_eq!(
+            new_instance
+                .deref()
+                .base_wrapper()
+                .outgoing_nodes({attr}::TYPE_ID)
+                .len(),
+            {max}
+        );",
+        owner = cfg.owner_type.name,
+        value = value_cfg.value,
+        attempts = max + 1,
+        setter = setter_name(cfg),
+        value_set = value_cfg.value_set,
+        getter_check = getter_check,
+        attr = cfg.attr.name,
+        max = max,
+    }))));
+    Some(f)
+}
+
 /// Add these flags to an implementation and its corresponding test module.
 pub fn add_attr_to_impl(
     cfg: &AttributePropertyConfig,
@@ -417,6 +599,27 @@ pub fn add_attr_to_impl(
     if let Some(f) = test_multi_set_fragment(cfg) {
         file.append_test(Rc::new(RefCell::new(f)));
     }
+    if let Some(f) = test_max_values_fragment(cfg) {
+        file.append_test(Rc::new(RefCell::new(f)));
+    }
+}
+
+/// Add these attributes to an accessor trait as default methods, and to the corresponding test module.
+pub fn add_attr_to_trait(
+    cfg: &AttributePropertyConfig,
+    accessor_trait: &mut TraitFragment,
+    file: &mut FileFragment,
+) {
+    accessor_trait.append(Rc::new(RefCell::new(getter_fragment(cfg))));
+    accessor_trait.append(Rc::new(RefCell::new(setter_fragment(cfg))));
+    file.append_test(Rc::new(RefCell::new(test_fragment(cfg))));
+    file.append_test(Rc::new(RefCell::new(test_inheritance_fragment(cfg))));
+    if let Some(f) = test_multi_set_fragment(cfg) {
+        file.append_test(Rc::new(RefCell::new(f)));
+    }
+    if let Some(f) = test_max_values_fragment(cfg) {
+        file.append_test(Rc::new(RefCell::new(f)));
+    }
 }
 
 #[cfg(test)]
@@ -456,6 +659,17 @@ mod tests {
         }
     }
 
+    fn enum_attr_config() -> AttributePropertyConfig {
+        AttributePropertyConfig {
+            rust_primitive: Some(Rc::from("LightValue")),
+            rust_primitive_unboxed: Some(Rc::from("LightValue")),
+            rust_primitive_import: Some("crate::tao::form::data::LightValue".to_owned()),
+            primitive_test_value: Some(Rc::from("LightValue::Red")),
+            dummy_test_value: Some(Rc::from("LightValue::Green")),
+            ..concept_attr_config()
+        }
+    }
+
     fn multi_valued_config() -> AttributePropertyConfig {
         AttributePropertyConfig {
             doc: Rc::from("the crates associated with the struct."),
@@ -563,6 +777,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_enum_getter_imports() {
+        let getter = getter_fragment(&enum_attr_config());
+        assert!(getter
+            .body(80)
+            .contains("fn associated_crate(&self) -> Option<Rc<LightValue>> {"));
+        assert!(getter
+            .imports()
+            .contains(&"crate::tao::form::data::LightValue".to_owned()));
+        assert!(setter_fragment(&enum_attr_config())
+            .imports()
+            .contains(&"crate::tao::form::data::LightValue".to_owned()));
+    }
+
     #[test]
     fn test_multi_valued_getter_fragment_body() {
         assert_eq!(
@@ -686,4 +914,195 @@ mod tests {
                 }"#}
         );
     }
+
+    #[test]
+    fn test_capped_primitive_setter_fragment_body() {
+        assert_eq!(
+            setter_fragment(&AttributePropertyConfig {
+                max_values: Some(1),
+                ..primitive_attr_config()
+            })
+            .body(80),
+            indoc! {"
+                /// Set the crate associated with the struct.
+                fn set_associated_crate(&mut self, associated_crate: &str) {
+                    let existing = self
+                        .deref()
+                        .base_wrapper()
+                        .outgoing_nodes(AssociatedCrate::TYPE_ID);
+                    if let Some(current) = existing.last() {
+                        // edges can't be removed, so the current value gets overwritten instead
+                        Crate::from(current.id()).set_value(associated_crate);
+                        return;
+                    }
+                    let mut value_concept = Crate::new();
+                    value_concept.set_value(associated_crate);
+                    self.deref_mut().add_outgoing(
+                        AssociatedCrate::TYPE_ID,
+                        value_concept.deref(),
+                    );
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_capped_multi_valued_setter_fragment_body() {
+        assert_eq!(
+            setter_fragment(&AttributePropertyConfig {
+                multi_valued: true,
+                max_values: Some(2),
+                ..primitive_attr_config()
+            })
+            .body(80),
+            indoc! {"
+                /// Add one of the crate associated with the struct. Once there are 2 of them,
+                /// the oldest one gets dropped to make room.
+                fn add_associated_crate(&mut self, associated_crate: &str) {
+                    let existing = self
+                        .deref()
+                        .base_wrapper()
+                        .outgoing_nodes(AssociatedCrate::TYPE_ID);
+                    if existing.len() >= 2 {
+                        // edges can't be removed, so newer values get shifted down instead
+                        for pair in existing.windows(2) {
+                            let mut older = pair[0];
+                            older.set_value(pair[1].value().unwrap());
+                        }
+                        Crate::from(existing.last().unwrap().id()).set_value(associated_crate);
+                        return;
+                    }
+                    let mut value_concept = Crate::new();
+                    value_concept.set_value(associated_crate);
+                    self.deref_mut().add_outgoing(
+                        AssociatedCrate::TYPE_ID,
+                        value_concept.deref(),
+                    );
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_capped_concept_setter_fragment_body() {
+        assert_eq!(
+            setter_fragment(&AttributePropertyConfig {
+                max_values: Some(2),
+                ..multi_valued_config()
+            })
+            .body(80),
+            indoc! {"
+                /// Add one of the crates associated with the struct. Once there are 2 of them,
+                /// any more get ignored.
+                fn add_associated_crate(&mut self, associated_crate: &Crate) {
+                    let existing = self
+                        .deref()
+                        .base_wrapper()
+                        .outgoing_nodes(AssociatedCrate::TYPE_ID);
+                    if existing.len() >= 2 {
+                        // edges can't be removed, and these values might be shared, so there's no room
+                        return;
+                    }
+                    self.deref_mut().add_outgoing(
+                        AssociatedCrate::TYPE_ID,
+                        associated_crate.deref(),
+                    );
+                }"}
+        );
+        // a single value gets overridden instead
+        assert_eq!(
+            setter_fragment(&AttributePropertyConfig {
+                max_values: Some(1),
+                ..concept_attr_config()
+            })
+            .body(80),
+            setter_fragment(&concept_attr_config()).body(80)
+        );
+    }
+
+    #[test]
+    fn test_capped_multi_valued_getter_fragment_body() {
+        assert_eq!(
+            getter_fragment(&AttributePropertyConfig {
+                max_values: Some(2),
+                ..multi_valued_config()
+            })
+            .body(80),
+            indoc! {"
+                /// Get the crates associated with the struct.
+                fn associated_crates(&self) -> Vec<Crate> {
+                    self.deref()
+                        .outgoing_nodes(AssociatedCrate::TYPE_ID)
+                        .into_iter()
+                        .rev()
+                        .take(2)
+                        .rev()
+                        .map(|f| Crate::from(f.id()))
+                        .collect()
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_max_values_fragment_body() {
+        assert!(test_max_values_fragment(&primitive_attr_config()).is_none());
+        assert!(test_max_values_fragment(&AttributePropertyConfig {
+            max_values: Some(1),
+            ..concept_attr_config()
+        })
+        .is_none());
+        assert_eq!(
+            test_max_values_fragment(&AttributePropertyConfig {
+                max_values: Some(1),
+                ..primitive_attr_config()
+            })
+            .unwrap()
+            .body(80),
+            indoc! {r#"
+                #[test]
+                #[allow(clippy::clone_double_ref)]
+                fn test_associated_crate_max_values() {
+                    initialize_kb();
+                    let mut new_instance = Form::new();
+                    let value = "";
+                    for _ in 0..2 {
+                        #[allow(clippy::clone_on_copy)]
+                        new_instance.set_associated_crate(value.clone());
+                    }
+                    assert_eq!(new_instance.associated_crate(), Some(Rc::from(value)));
+                    assert_eq!(
+                        new_instance
+                            .deref()
+                            .base_wrapper()
+                            .outgoing_nodes(AssociatedCrate::TYPE_ID)
+                            .len(),
+                        1
+                    );
+                }"#}
+        );
+    }
+
+    #[test]
+    fn test_multi_valued_max_values_fragment_body() {
+        assert_eq!(
+            test_max_values_fragment(&AttributePropertyConfig {
+                max_values: Some(2),
+                ..multi_valued_config()
+            })
+            .unwrap()
+            .body(80),
+            indoc! {"
+                #[test]
+                #[allow(clippy::clone_double_ref)]
+                fn test_associated_crate_max_values() {
+                    initialize_kb();
+                    let mut new_instance = Form::new();
+                    let values = (0..3)
+                        .map(|_| Crate::new())
+                        .collect::<Vec<Crate>>();
+                    for value in &values {
+                        new_instance.add_associated_crate(value);
+                    }
+                    assert_eq!(new_instance.associated_crates(), values[..2].to_vec());
+                }"}
+        );
+    }
 }
@@ -1,15 +1,24 @@
 use super::util::kb_test_function;
+use super::visitor::{visitor_fragment, visitor_test, VisitorMethod};
 use crate::codegen::docstring::into_docstring;
 use crate::codegen::template::basic::{
     AtomicFragment, FileFragment, FunctionCallFragment, FunctionFragment, ItemDeclarationAPI,
 };
 use crate::codegen::{StructConfig, CODE_WIDTH};
 use crate::tao::form::rust_item::{Crate, CrateExtension};
-use indoc::formatdoc;
+use indoc::{formatdoc, indoc};
 use itertools::Itertools;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// Import path of the error that generated downcasts return. It's defined in the init file, which
+/// every crate with generated concepts has.
+pub const DOWNCAST_ERROR_IMPORT: &str = "crate::tao::auto_init::DowncastError";
+
+/// Import path of the error that generated builders return. Like the downcast error, it's defined
+/// in the init file.
+pub const MISSING_ATTRIBUTE_ERROR_IMPORT: &str = "crate::tao::auto_init::MissingAttributeError";
+
 /// Represents a binary relation between two nodes.
 #[derive(Debug, Default, Eq, PartialEq)]
 pub struct Link {
@@ -32,6 +41,28 @@ impl Link {
     }
 }
 
+/// The value that an individual's attribute points to.
+#[derive(Debug, Eq, PartialEq)]
+pub enum IndividualValue {
+    /// A concept, referred to by its struct.
+    Concept(StructConfig),
+    /// Another individual, referred to by its ID.
+    Individual(usize),
+}
+
+/// Represents a named individual that gets initialized along with the concepts.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct IndividualConfig {
+    /// Internal name of the individual.
+    pub name: String,
+    /// ID of the individual, counted the same way as concept IDs are.
+    pub id: usize,
+    /// The struct name of the archetype that the individual is an instance of.
+    pub archetype: StructConfig,
+    /// Values of the individual's attributes, as pairs of attribute struct and value.
+    pub attributes: Vec<(StructConfig, IndividualValue)>,
+}
+
 /// Configuration values for KB initialization template.
 #[derive(Default)]
 pub struct KBInitConfig {
@@ -39,8 +70,22 @@ pub struct KBInitConfig {
     pub concepts_to_initialize: Vec<StructConfig>,
     /// The list of binary relations between concepts.
     pub attributes: Vec<Link>,
+    /// Named individuals to initialize after the concepts. Their IDs are interspersed with those
+    /// of the concepts.
+    pub individuals: Vec<IndividualConfig>,
+    /// Methods of the generated visitor, one for each concept to be initialized.
+    pub visitor_methods: Vec<VisitorMethod>,
+    /// IDs that no longer belong to any concept, but which still need a node so that the IDs of
+    /// the concepts after them stay the same.
+    pub retired_ids: Vec<usize>,
+    /// Path to the max ID constant of the crate that this crate builds on, which this crate's
+    /// concept IDs are offset from. `None` when building Yin itself.
+    pub id_offset: Option<String>,
     /// If we're building for Yin, or not. False implies that we're building on top of Yin.
     pub yin: bool,
+    /// Whether or not every node created during KB initialization is known, so that the size of
+    /// the initialized graph can be tested against `YIN_MAX_ID`.
+    pub size_test: bool,
 }
 
 fn concept_id_fragment(concept: &StructConfig) -> AtomicFragment {
@@ -50,6 +95,89 @@ fn concept_id_fragment(concept: &StructConfig) -> AtomicFragment {
     }
 }
 
+/// The Rust expression for an individual's ID, which is offset in the same way that concept IDs
+/// are.
+fn individual_id(cfg: &KBInitConfig, id: usize) -> String {
+    match &cfg.id_offset {
+        Some(offset) => format!("{} + {}", offset, id),
+        None => format!("{}", id),
+    }
+}
+
+/// Initialize all concepts with Yin's macro, which only works when concept IDs are contiguous.
+fn init_types_macro(cfg: &KBInitConfig) -> String {
+    let concepts_list = cfg
+        .concepts_to_initialize
+        .iter()
+        .map(|c| &c.name)
+        .format(",\n        ")
+        .to_string();
+
+    formatdoc! {"
+        let mut ig = InjectionGraph::new();
+        #[rustfmt::skip]
+        initialize_type!(
+            ig,
+            (
+                {concepts}
+            )
+        );
+    ", concepts = concepts_list}
+}
+
+/// Initialize all concepts one node at a time, adding unnamed nodes for retired IDs so that the
+/// concepts after them still end up with the right IDs, and named nodes for individuals.
+fn init_types_with_gaps(cfg: &KBInitConfig) -> String {
+    let first_id = if cfg.yin { 0 } else { 1 };
+    let total = cfg.concepts_to_initialize.len() + cfg.retired_ids.len() + cfg.individuals.len();
+    let mut concepts = cfg.concepts_to_initialize.iter();
+    let mut code = "let mut ig = InjectionGraph::new();\n".to_owned();
+    for id in first_id..(first_id + total) {
+        if cfg.retired_ids.contains(&id) {
+            code += &format!("ig.add_node(); // retired ID {}\n", id);
+        } else if let Some(individual) = cfg.individuals.iter().find(|i| i.id == id) {
+            code += &format!(
+                "ig.add_node();\nig.set_node_name({}, \"{}\");\n",
+                individual_id(cfg, id),
+                individual.name
+            );
+        } else if let Some(concept) = concepts.next() {
+            code += &format!(
+                "ig.add_node();\nig.set_node_name({name}::TYPE_ID, {name}::TYPE_NAME);\n",
+                name = concept.name
+            );
+        }
+    }
+    // set edges later, since edges contain references to node names, and that will be impossible
+    // if the nodes themselves don't exist yet
+    for concept in &cfg.concepts_to_initialize {
+        code += &format!(
+            "ig.add_edge({name}::TYPE_ID, Inherits::TYPE_ID, {name}::PARENT_TYPE_ID);\n",
+            name = concept.name
+        );
+    }
+    for individual in &cfg.individuals {
+        let id = individual_id(cfg, individual.id);
+        code += &format!(
+            "ig.add_edge({id}, Inherits::TYPE_ID, {archetype}::TYPE_ID);\n\
+            ig.add_flag({id}, IsIndividual::TYPE_ID);\n",
+            id = id,
+            archetype = individual.archetype.name
+        );
+        for (attribute, value) in &individual.attributes {
+            let value_id = match value {
+                IndividualValue::Concept(concept) => format!("{}::TYPE_ID", concept.name),
+                IndividualValue::Individual(value_id) => individual_id(cfg, *value_id),
+            };
+            code += &format!(
+                "ig.add_edge({}, {}::TYPE_ID, {});\n",
+                id, attribute.name, value_id
+            );
+        }
+    }
+    code
+}
+
 /// Get the function that initializes concept types.
 ///
 /// The caller will still have to choose which graph binding they want to use, and initialize it
@@ -67,24 +195,23 @@ fn init_types_fragment(cfg: &KBInitConfig) -> FunctionFragment {
     for concept in &cfg.concepts_to_initialize {
         init_fn.add_import(concept.import.clone());
     }
+    for individual in &cfg.individuals {
+        init_fn.add_import("zamm_yin::tao::relation::flag::IsIndividual".to_owned());
+        init_fn.add_import(individual.archetype.import.clone());
+        for (attribute, value) in &individual.attributes {
+            init_fn.add_import(attribute.import.clone());
+            if let IndividualValue::Concept(concept) = value {
+                init_fn.add_import(concept.import.clone());
+            }
+        }
+    }
 
-    let concepts_list = cfg
-        .concepts_to_initialize
-        .iter()
-        .map(|c| &c.name)
-        .format(",\n        ")
-        .to_string();
-
-    init_fn.append(Rc::new(RefCell::new(AtomicFragment::new(formatdoc! {"
-        let mut ig = InjectionGraph::new();
-        #[rustfmt::skip]
-        initialize_type!(
-            ig,
-            (
-                {concepts}
-            )
-        );
-    ", concepts = concepts_list}))));
+    let init_code = if cfg.retired_ids.is_empty() && cfg.individuals.is_empty() {
+        init_types_macro(cfg)
+    } else {
+        init_types_with_gaps(cfg)
+    };
+    init_fn.append(Rc::new(RefCell::new(AtomicFragment::new(init_code))));
 
     for attr in &cfg.attributes {
         let mut add_edge = FunctionCallFragment::new(AtomicFragment::new("ig.add_edge".to_owned()));
@@ -99,12 +226,12 @@ fn init_types_fragment(cfg: &KBInitConfig) -> FunctionFragment {
 
 /// Defines the number of concepts generated.
 fn max_id_fragment(cfg: &KBInitConfig) -> AtomicFragment {
-    let max_id_doc = into_docstring("The maximum concept ID inside the types distributed by Yin itself. App-specific type concepts should continue their numbering on top of this.", CODE_WIDTH);
-    let concepts_size = cfg.concepts_to_initialize.len();
-    let max_id = if cfg.yin {
-        format!("{}", concepts_size - 1) // -1 because IDs are zero-indexed
-    } else {
-        format!("zamm_yin::tao::YIN_MAX_ID + {}", concepts_size)
+    let max_id_doc = into_docstring("The maximum concept ID inside the types distributed by this crate and every crate it builds on. Crates that build on this one should continue their numbering on top of this.", CODE_WIDTH);
+    let concepts_size =
+        cfg.concepts_to_initialize.len() + cfg.retired_ids.len() + cfg.individuals.len();
+    let max_id = match &cfg.id_offset {
+        Some(offset) => format!("{} + {}", offset, concepts_size),
+        None => format!("{}", concepts_size - 1), // -1 because IDs are zero-indexed
     };
     AtomicFragment::new(formatdoc! {"
         {doc}
@@ -114,6 +241,220 @@ fn max_id_fragment(cfg: &KBInitConfig) -> AtomicFragment {
     })
 }
 
+/// Defines the error for concepts that can't be downcast into a more specific type.
+fn downcast_error_fragment() -> AtomicFragment {
+    AtomicFragment {
+        imports: vec![
+            "std::error::Error".to_owned(),
+            "std::fmt".to_owned(),
+            "std::fmt::Display".to_owned(),
+            "std::fmt::Formatter".to_owned(),
+        ],
+        atom: indoc! {r#"
+            /// A concept could not be downcast into a more specific type, because it
+            /// doesn't descend from that type's archetype.
+            #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+            pub struct DowncastError {
+                /// ID of the concept that was being downcast.
+                pub id: usize,
+                /// Internal name of the archetype that the concept was being downcast into.
+                pub target: &'static str,
+            }
+
+            impl Display for DowncastError {
+                fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                    write!(f, "Concept {} is not a {}", self.id, self.target)
+                }
+            }
+
+            impl Error for DowncastError {}
+        "#}
+        .to_owned(),
+    }
+}
+
+/// Defines the error for concepts that can't be built because of a missing required attribute.
+fn missing_attribute_error_fragment() -> AtomicFragment {
+    AtomicFragment {
+        imports: vec![
+            "std::error::Error".to_owned(),
+            "std::fmt".to_owned(),
+            "std::fmt::Display".to_owned(),
+            "std::fmt::Formatter".to_owned(),
+        ],
+        atom: indoc! {r#"
+            /// A concept has fewer values than one of its attributes requires, either while
+            /// being built or when validated.
+            #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+            pub struct MissingAttributeError {
+                /// Internal name of the archetype of the concept that is missing values.
+                pub concept: &'static str,
+                /// Internal name of the attribute that the concept doesn't have enough
+                /// values for.
+                pub attribute: &'static str,
+            }
+
+            impl Display for MissingAttributeError {
+                fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                    write!(f, "{} is missing its required {}", self.concept, self.attribute)
+                }
+            }
+
+            impl Error for MissingAttributeError {}
+        "#}
+        .to_owned(),
+    }
+}
+
+/// Defines an enum with a variant for every concept distributed by this crate, so that runtime
+/// nodes can be matched on by type.
+fn any_concept_fragment(cfg: &KBInitConfig) -> AtomicFragment {
+    let mut imports = vec![
+        "std::cmp::Reverse".to_owned(),
+        "std::rc::Rc".to_owned(),
+        "zamm_yin::node_wrappers::CommonNodeTrait".to_owned(),
+        "zamm_yin::node_wrappers::FinalNode".to_owned(),
+        "zamm_yin::node_wrappers::InheritanceNodeTrait".to_owned(),
+        "zamm_yin::tao::archetype::ArchetypeTrait".to_owned(),
+    ];
+    let mut variants = String::new();
+    let mut type_arms = String::new();
+    let mut id_arms = String::new();
+    let mut name_arms = String::new();
+    for concept in &cfg.concepts_to_initialize {
+        imports.push(concept.import.clone());
+        variants += &format!(
+            "    /// A concept of type `{name}`.\n    {name}({name}),\n",
+            name = concept.name
+        );
+        type_arms += &format!(
+            "            {name}::TYPE_ID => Some(AnyConcept::{name}({name}::from(id))),\n",
+            name = concept.name
+        );
+        id_arms += &format!("            AnyConcept::{}(c) => c.id(),\n", concept.name);
+        name_arms += &format!(
+            "            AnyConcept::{}(c) => c.internal_name(),\n",
+            concept.name
+        );
+    }
+    AtomicFragment {
+        imports,
+        atom: formatdoc! {"
+            /// Any one of the concepts distributed by this crate, as the most specific
+            /// generated type that it is an instance of.
+            #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+            pub enum AnyConcept {{
+            {variants}}}
+
+            impl AnyConcept {{
+                /// View the node as an instance of the given type, if that type is one of
+                /// this crate's.
+                fn as_type(type_id: usize, id: usize) -> Option<Self> {{
+                    match type_id {{
+            {type_arms}            _ => None,
+                    }}
+                }}
+
+                /// View a node as the most specific generated type that it descends from,
+                /// or `None` if it doesn't descend from any type distributed by this crate.
+                ///
+                /// If the node inherits from several unrelated types that are equally specific,
+                /// the one with the lowest ID is picked, so that the result doesn't depend on the
+                /// order in which the node's parents were added.
+                pub fn from_node(id: usize) -> Option<Self> {{
+                    // a type has more ancestors than any of its own ancestors do, so the matching type
+                    // with the most ancestors is the most specific one
+                    FinalNode::from(id)
+                        .inheritance_nodes()
+                        .into_iter()
+                        .filter_map(|t| {{
+                            Self::as_type(t.id(), id)
+                                .map(|c| ((t.inheritance_nodes().len(), Reverse(t.id())), c))
+                        }})
+                        .max_by_key(|(specificity, _)| *specificity)
+                        .map(|(_, c)| c)
+                }}
+
+                /// The ID of the underlying node.
+                pub fn id(&self) -> usize {{
+                    match self {{
+            {id_arms}        }}
+                }}
+
+                /// The internal name of the underlying node, if it has one.
+                pub fn internal_name(&self) -> Option<Rc<str>> {{
+                    match self {{
+            {name_arms}        }}
+                }}
+            }}
+        ",
+            variants = variants,
+            type_arms = type_arms,
+            id_arms = id_arms,
+            name_arms = name_arms,
+        },
+    }
+}
+
+/// Test that every concept distributed by this crate can be recovered from its node.
+fn any_concept_test(cfg: &KBInitConfig) -> FunctionFragment {
+    let mut f = kb_test_function("test_any_concept");
+    f.add_import("std::rc::Rc".to_owned());
+    let mut code = String::new();
+    for concept in &cfg.concepts_to_initialize {
+        f.add_import(concept.import.clone());
+        code += &format!(
+            "assert_eq!(\n    AnyConcept::from_node({name}::TYPE_ID),\n    Some(AnyConcept::{name}({name}::from({name}::TYPE_ID)))\n);\n",
+            name = concept.name
+        );
+    }
+    if let Some(last) = cfg.concepts_to_initialize.last() {
+        code += &formatdoc! {"
+
+            let concept = AnyConcept::from_node({name}::TYPE_ID).unwrap();
+            assert_eq!(concept.id(), {name}::TYPE_ID);
+            assert_eq!(concept.internal_name(), Some(Rc::from({name}::TYPE_NAME)));",
+            name = last.name
+        };
+    }
+    if let (Some(first), Some(last)) = (
+        cfg.concepts_to_initialize.first(),
+        cfg.concepts_to_initialize.last(),
+    ) {
+        if first.name != last.name {
+            f.add_import("std::mem::discriminant".to_owned());
+            f.add_import("zamm_yin::node_wrappers::BaseNodeTrait".to_owned());
+            f.add_import("zamm_yin::node_wrappers::CommonNodeTrait".to_owned());
+            f.add_import("zamm_yin::node_wrappers::FinalNode".to_owned());
+            f.add_import("zamm_yin::tao::relation::attribute::Inherits".to_owned());
+            code += &formatdoc! {"
+
+
+                // a node that inherits from two types is viewed the same way no matter which
+                // parent was added first
+                let first = FinalNode::from({first}::TYPE_ID);
+                let last = FinalNode::from({last}::TYPE_ID);
+                let mut both = FinalNode::new();
+                both.add_outgoing(Inherits::TYPE_ID, &first);
+                both.add_outgoing(Inherits::TYPE_ID, &last);
+                let mut reversed = FinalNode::new();
+                reversed.add_outgoing(Inherits::TYPE_ID, &last);
+                reversed.add_outgoing(Inherits::TYPE_ID, &first);
+                assert_eq!(
+                    AnyConcept::from_node(both.id()).map(|c| discriminant(&c)),
+                    AnyConcept::from_node(reversed.id()).map(|c| discriminant(&c))
+                );",
+                first = first.name,
+                last = last.name
+            };
+        }
+    }
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(
+        code.trim_end().to_owned(),
+    ))));
+    f
+}
+
 fn yin_size_test() -> FunctionFragment {
     let mut f = kb_test_function("test_yin_size");
     // node IDs are zero-indexed, so add 1 to YIN_MAX_ID
@@ -128,10 +469,19 @@ pub fn code_init(cfg: &KBInitConfig) -> String {
     let mut file = FileFragment::default();
     file.append(Rc::new(RefCell::new(max_id_fragment(cfg)))); // always define, even if unused
     file.append(Rc::new(RefCell::new(init_types_fragment(cfg))));
-    if cfg.yin {
-        // todo: generate for Yang as well once all individuals are also autogenerated. As of now,
-        // there is no way for Yang to know how many non-archetype nodes are created during
-        // initialization.
+    file.append(Rc::new(RefCell::new(downcast_error_fragment())));
+    file.append(Rc::new(RefCell::new(missing_attribute_error_fragment())));
+    if !cfg.concepts_to_initialize.is_empty() {
+        file.append(Rc::new(RefCell::new(any_concept_fragment(cfg))));
+        file.append_test(Rc::new(RefCell::new(any_concept_test(cfg))));
+    }
+    if !cfg.visitor_methods.is_empty() {
+        file.append(Rc::new(RefCell::new(visitor_fragment(
+            &cfg.visitor_methods,
+        ))));
+        file.append_test(Rc::new(RefCell::new(visitor_test(&cfg.visitor_methods))));
+    }
+    if cfg.size_test {
         file.append_test(Rc::new(RefCell::new(yin_size_test())));
     }
     file.set_current_crate(Crate::current().implementation_name().unwrap());
@@ -143,7 +493,6 @@ mod tests {
     use super::*;
     use crate::codegen::template::basic::CodeFragment;
     use crate::tao::initialize_kb;
-    use indoc::indoc;
 
     #[test]
     fn test_init_one_concept() {
@@ -239,7 +588,7 @@ mod tests {
                         to: StructConfig::new("crate::people::Us".to_owned()),
                     },
                 ],
-                yin: false,
+                ..KBInitConfig::default()
             })
             .body(80),
             indoc! {"
@@ -261,6 +610,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_init_individuals() {
+        assert_eq!(
+            init_types_fragment(&KBInitConfig {
+                concepts_to_initialize: vec![StructConfig {
+                    name: "Lamp".to_owned(),
+                    import: "crate::lamps::Lamp".to_owned(),
+                }],
+                individuals: vec![IndividualConfig {
+                    name: "desk-lamp".to_owned(),
+                    id: 2,
+                    archetype: StructConfig::new("crate::lamps::Lamp".to_owned()),
+                    attributes: vec![(
+                        StructConfig::new("crate::lamps::Shines".to_owned()),
+                        IndividualValue::Concept(StructConfig::new(
+                            "crate::colors::Red".to_owned()
+                        )),
+                    )],
+                }],
+                id_offset: Some("zamm_yin::tao::YIN_MAX_ID".to_owned()),
+                ..KBInitConfig::default()
+            })
+            .body(80),
+            indoc! {r#"
+            /// Adds all concepts to knowledge graph.
+            pub fn initialize_types() {
+                let mut ig = InjectionGraph::new();
+                ig.add_node();
+                ig.set_node_name(Lamp::TYPE_ID, Lamp::TYPE_NAME);
+                ig.add_node();
+                ig.set_node_name(zamm_yin::tao::YIN_MAX_ID + 2, "desk-lamp");
+                ig.add_edge(Lamp::TYPE_ID, Inherits::TYPE_ID, Lamp::PARENT_TYPE_ID);
+                ig.add_edge(zamm_yin::tao::YIN_MAX_ID + 2, Inherits::TYPE_ID, Lamp::TYPE_ID);
+                ig.add_flag(zamm_yin::tao::YIN_MAX_ID + 2, IsIndividual::TYPE_ID);
+                ig.add_edge(zamm_yin::tao::YIN_MAX_ID + 2, Shines::TYPE_ID, Red::TYPE_ID);
+            }"#}
+        );
+    }
+
     #[test]
     fn test_init_file() {
         initialize_kb();
@@ -284,6 +672,178 @@ mod tests {
             ..KBInitConfig::default()
         });
         assert!(code.contains("YIN_MAX_ID: usize = 2"));
+        assert!(code.contains("pub struct DowncastError {"));
+        assert!(code.contains("impl Error for DowncastError {}"));
+        assert!(code.contains("pub struct MissingAttributeError {"));
+        assert!(code.contains("pub enum AnyConcept {"));
+        assert!(code.contains("fn test_any_concept() {"));
         assert!(!code.contains("zamm_yin"));
     }
+
+    #[test]
+    fn test_any_concept_fragment() {
+        assert_eq!(
+            any_concept_fragment(&KBInitConfig {
+                concepts_to_initialize: vec![
+                    StructConfig {
+                        name: "Me".to_owned(),
+                        import: "crate::people::Me".to_owned(),
+                    },
+                    StructConfig {
+                        name: "Us".to_owned(),
+                        import: "crate::groups::Us".to_owned(),
+                    }
+                ],
+                ..KBInitConfig::default()
+            })
+            .body(80),
+            indoc! {"
+            /// Any one of the concepts distributed by this crate, as the most specific
+            /// generated type that it is an instance of.
+            #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+            pub enum AnyConcept {
+                /// A concept of type `Me`.
+                Me(Me),
+                /// A concept of type `Us`.
+                Us(Us),
+            }
+
+            impl AnyConcept {
+                /// View the node as an instance of the given type, if that type is one of
+                /// this crate's.
+                fn as_type(type_id: usize, id: usize) -> Option<Self> {
+                    match type_id {
+                        Me::TYPE_ID => Some(AnyConcept::Me(Me::from(id))),
+                        Us::TYPE_ID => Some(AnyConcept::Us(Us::from(id))),
+                        _ => None,
+                    }
+                }
+
+                /// View a node as the most specific generated type that it descends from,
+                /// or `None` if it doesn't descend from any type distributed by this crate.
+                ///
+                /// If the node inherits from several unrelated types that are equally specific,
+                /// the one with the lowest ID is picked, so that the result doesn't depend on the
+                /// order in which the node's parents were added.
+                pub fn from_node(id: usize) -> Option<Self> {
+                    // a type has more ancestors than any of its own ancestors do, so the matching type
+                    // with the most ancestors is the most specific one
+                    FinalNode::from(id)
+                        .inheritance_nodes()
+                        .into_iter()
+                        .filter_map(|t| {
+                            Self::as_type(t.id(), id)
+                                .map(|c| ((t.inheritance_nodes().len(), Reverse(t.id())), c))
+                        })
+                        .max_by_key(|(specificity, _)| *specificity)
+                        .map(|(_, c)| c)
+                }
+
+                /// The ID of the underlying node.
+                pub fn id(&self) -> usize {
+                    match self {
+                        AnyConcept::Me(c) => c.id(),
+                        AnyConcept::Us(c) => c.id(),
+                    }
+                }
+
+                /// The internal name of the underlying node, if it has one.
+                pub fn internal_name(&self) -> Option<Rc<str>> {
+                    match self {
+                        AnyConcept::Me(c) => c.internal_name(),
+                        AnyConcept::Us(c) => c.internal_name(),
+                    }
+                }
+            }"}
+        );
+    }
+
+    #[test]
+    fn test_any_concept_test_multiple_parents() {
+        let code = any_concept_test(&KBInitConfig {
+            concepts_to_initialize: vec![
+                StructConfig {
+                    name: "Me".to_owned(),
+                    import: "zamm_yin::people::Me".to_owned(),
+                },
+                StructConfig {
+                    name: "Us".to_owned(),
+                    import: "crate::groups::Us".to_owned(),
+                },
+            ],
+            ..KBInitConfig::default()
+        })
+        .body(80);
+        assert!(code.contains("let first = FinalNode::from(Me::TYPE_ID);"));
+        assert!(code.contains("let last = FinalNode::from(Us::TYPE_ID);"));
+        assert!(code.contains(
+            "both.add_outgoing(Inherits::TYPE_ID, &first);\n    \
+             both.add_outgoing(Inherits::TYPE_ID, &last);"
+        ));
+        assert!(code.contains(
+            "reversed.add_outgoing(Inherits::TYPE_ID, &last);\n    \
+             reversed.add_outgoing(Inherits::TYPE_ID, &first);"
+        ));
+    }
+
+    #[test]
+    fn test_any_concept_test_one_concept() {
+        let code = any_concept_test(&KBInitConfig {
+            concepts_to_initialize: vec![StructConfig {
+                name: "Me".to_owned(),
+                import: "zamm_yin::people::Me".to_owned(),
+            }],
+            ..KBInitConfig::default()
+        })
+        .body(80);
+        assert!(!code.contains("reversed"));
+    }
+
+    #[test]
+    fn test_init_layered_max_id() {
+        initialize_kb();
+        Crate::current().set_implementation_name("crate_c");
+        let code = code_init(&KBInitConfig {
+            concepts_to_initialize: vec![StructConfig {
+                name: "Me".to_owned(),
+                import: "crate::people::Me".to_owned(),
+            }],
+            id_offset: Some("crate_b::tao::YIN_MAX_ID".to_owned()),
+            ..KBInitConfig::default()
+        });
+        assert!(code.contains("YIN_MAX_ID: usize = crate_b::tao::YIN_MAX_ID + 1"));
+    }
+
+    #[test]
+    fn test_init_retired_ids() {
+        assert_eq!(
+            init_types_fragment(&KBInitConfig {
+                concepts_to_initialize: vec![
+                    StructConfig {
+                        name: "Me".to_owned(),
+                        import: "crate::people::Me".to_owned(),
+                    },
+                    StructConfig {
+                        name: "Us".to_owned(),
+                        import: "crate::groups::Us".to_owned(),
+                    }
+                ],
+                retired_ids: vec![2],
+                ..KBInitConfig::default()
+            })
+            .body(80),
+            indoc! {"
+            /// Adds all concepts to knowledge graph.
+            pub fn initialize_types() {
+                let mut ig = InjectionGraph::new();
+                ig.add_node();
+                ig.set_node_name(Me::TYPE_ID, Me::TYPE_NAME);
+                ig.add_node(); // retired ID 2
+                ig.add_node();
+                ig.set_node_name(Us::TYPE_ID, Us::TYPE_NAME);
+                ig.add_edge(Me::TYPE_ID, Inherits::TYPE_ID, Me::PARENT_TYPE_ID);
+                ig.add_edge(Us::TYPE_ID, Inherits::TYPE_ID, Us::PARENT_TYPE_ID);
+            }"}
+        );
+    }
 }
@@ -0,0 +1,424 @@
+use super::attribute_property::AttributePropertyConfig;
+use super::auto_init_kb::MISSING_ATTRIBUTE_ERROR_IMPORT;
+use super::util::{kb_test_function, missing_attribute_error};
+use crate::codegen::docstring::into_docstring;
+use crate::codegen::template::basic::{
+    AtomicFragment, FileFragment, FunctionFragment, ImplementationFragment, ItemDeclarationAPI,
+    SelfReference,
+};
+use crate::codegen::{StructConfig, CODE_WIDTH};
+use indoc::formatdoc;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Config values for a single attribute that can be given to a builder.
+pub struct BuilderAttributeConfig {
+    /// The attribute as a property of the concept being built.
+    pub property: AttributePropertyConfig,
+    /// Whether or not the concept can only be built once this attribute has a value.
+    pub required: bool,
+}
+
+/// Config values at the time of builder code generation.
+pub struct BuilderConfig {
+    /// Concept that gets built.
+    pub this: StructConfig,
+    /// Name of the builder struct.
+    pub name: String,
+    /// Every attribute that the concept can have, whether introduced by the concept itself or
+    /// inherited from its ancestors.
+    pub attributes: Vec<BuilderAttributeConfig>,
+}
+
+/// The builder struct, which lives in the same module as the concept it builds.
+fn builder_struct(cfg: &BuilderConfig) -> StructConfig {
+    let module = cfg.this.import.rsplitn(2, "::").last().unwrap();
+    StructConfig {
+        name: cfg.name.clone(),
+        import: format!("{}::{}", module, cfg.name),
+    }
+}
+
+/// Name of the builder method for the attribute.
+fn method_name(cfg: &AttributePropertyConfig) -> String {
+    if cfg.multi_valued {
+        format!("add_{}", cfg.property_name)
+    } else {
+        cfg.property_name.to_string()
+    }
+}
+
+/// Code that checks whether the builder is still missing a value for the attribute.
+fn missing_check(cfg: &AttributePropertyConfig) -> String {
+    if cfg.multi_valued {
+        format!("self.{}.is_empty()", cfg.property_name)
+    } else {
+        format!("self.{}.is_none()", cfg.property_name)
+    }
+}
+
+/// Get the struct that holds the attribute values until the concept gets built.
+fn builder_struct_fragment(cfg: &BuilderConfig) -> AtomicFragment {
+    let mut imports = vec![];
+    let mut fields = String::new();
+    for attr in &cfg.attributes {
+        let property = &attr.property;
+        imports.push(property.value_type.import.clone());
+        let field_type = if property.multi_valued {
+            format!("Vec<{}>", property.value_type.name)
+        } else {
+            format!("Option<{}>", property.value_type.name)
+        };
+        fields += &format!("    {}: {},\n", property.property_name, field_type);
+    }
+    let doc = format!(
+        "Builder for `{this}` concepts, with a method for each attribute that they can have. The \
+        `{this}` itself only gets created once the builder is built.",
+        this = cfg.this.name
+    );
+    AtomicFragment {
+        imports,
+        atom: formatdoc! {"
+            {doc}
+            #[derive(Debug, Default)]
+            pub struct {name} {{
+            {fields}}}",
+            doc = into_docstring(&doc, CODE_WIDTH),
+            name = cfg.name,
+            fields = fields,
+        },
+    }
+}
+
+/// Get the builder method that gives the attribute a value.
+fn builder_method_fragment(cfg: &AttributePropertyConfig) -> FunctionFragment {
+    let mut f = FunctionFragment::new(method_name(cfg));
+    f.add_import(cfg.value_type.import.clone());
+    if let Some(import) = &cfg.rust_primitive_import {
+        f.add_import(import.clone());
+    }
+    f.mark_as_public();
+    if cfg.multi_valued {
+        f.document(format!("Add one of {}", cfg.doc));
+    } else {
+        f.document(format!("Set {}", cfg.doc));
+    }
+    f.set_self_reference(SelfReference::OwnedMutable);
+    f.set_return("Self".to_owned());
+
+    let arg_name = cfg.property_name.to_string();
+    let value = match &cfg.rust_primitive_unboxed {
+        Some(unboxed_primitive) => {
+            f.add_import("zamm_yin::tao::archetype::ArchetypeTrait".to_owned());
+            f.add_arg(arg_name, unboxed_primitive.to_string());
+            f.append(Rc::new(RefCell::new(AtomicFragment::new(formatdoc! {"
+                let mut value_concept = {value_concept}::new();
+                value_concept.set_value({value});",
+                value_concept = cfg.value_type.name,
+                value = cfg.property_name
+            }))));
+            "value_concept".to_owned()
+        }
+        None => {
+            f.add_arg(arg_name, format!("&{}", cfg.value_type.name));
+            format!("*{}", cfg.property_name)
+        }
+    };
+    let store = if cfg.multi_valued {
+        format!("self.{}.push({});", cfg.property_name, value)
+    } else {
+        format!("self.{} = Some({});", cfg.property_name, value)
+    };
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(format!(
+        "{}\nself",
+        store
+    )))));
+    f
+}
+
+/// Get the method that creates the concept with every attribute given to the builder.
+fn build_fragment(cfg: &BuilderConfig) -> FunctionFragment {
+    let mut f = FunctionFragment::new("build".to_owned());
+    f.add_import(cfg.this.import.clone());
+    f.add_import(MISSING_ATTRIBUTE_ERROR_IMPORT.to_owned());
+    f.add_import("std::ops::Deref".to_owned());
+    f.add_import("std::ops::DerefMut".to_owned());
+    f.add_import("zamm_yin::node_wrappers::BaseNodeTrait".to_owned());
+    f.add_import("zamm_yin::tao::archetype::ArchetypeTrait".to_owned());
+    f.mark_as_public();
+    f.document(format!(
+        "Create the `{}` with every attribute given to this builder. Fails if any of its required \
+        attributes were left unset.",
+        cfg.this.name
+    ));
+    f.set_self_reference(SelfReference::Owned);
+    f.set_return(format!("Result<{}, MissingAttributeError>", cfg.this.name));
+
+    let mut body = String::new();
+    for attr in cfg.attributes.iter().filter(|a| a.required) {
+        body += &formatdoc! {"
+            if {missing} {{
+                return Err({error});
+            }}
+            ",
+            missing = missing_check(&attr.property),
+            error = missing_attribute_error(&cfg.this, &attr.property.attr).replace('\n', "\n    "),
+        };
+    }
+    body += &format!("let mut concept = {}::new();\n", cfg.this.name);
+    for attr in &cfg.attributes {
+        let property = &attr.property;
+        f.add_import(property.attr.import.clone());
+        let iteration = if property.multi_valued {
+            format!("for value in self.{}", property.property_name)
+        } else {
+            format!("if let Some(value) = self.{}", property.property_name)
+        };
+        body += &formatdoc! {"
+            {iteration} {{
+                concept.deref_mut().add_outgoing({attr}::TYPE_ID, value.deref());
+            }}
+            ",
+            iteration = iteration,
+            attr = property.attr.name,
+        };
+    }
+    body += "Ok(concept)";
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(body))));
+    f
+}
+
+/// The value to give the attribute when testing the builder, if one is known.
+fn test_value(cfg: &AttributePropertyConfig) -> Option<String> {
+    match &cfg.rust_primitive_unboxed {
+        Some(_) => cfg.primitive_test_value.as_ref().map(|v| v.to_string()),
+        None => Some(format!("&{}::new()", cfg.value_type.name)),
+    }
+}
+
+/// Test that the builder checks for required attributes, and that it sets every attribute it's
+/// given.
+fn test_builder_fragment(cfg: &BuilderConfig) -> FunctionFragment {
+    let mut f = kb_test_function("test_builder");
+
+    let mut code = String::new();
+    if let Some(required) = cfg.attributes.iter().find(|a| a.required) {
+        f.add_import(cfg.this.import.clone());
+        f.add_import(required.property.attr.import.clone());
+        f.add_import(MISSING_ATTRIBUTE_ERROR_IMPORT.to_owned());
+        code += &format!(
+            "assert_eq!(\n    {}::default().build(),\n    Err({})\n);\n\n",
+            cfg.name,
+            missing_attribute_error(&cfg.this, &required.property.attr).replace('\n', "\n    ")
+        );
+    }
+
+    // without a test value, a required attribute can't be set, so the concept can't be built
+    let buildable = cfg
+        .attributes
+        .iter()
+        .all(|a| !a.required || test_value(&a.property).is_some());
+    if buildable {
+        let mut calls = String::new();
+        let mut checks = String::new();
+        for attr in &cfg.attributes {
+            let property = &attr.property;
+            if let Some(value) = test_value(property) {
+                f.add_import(property.attr.import.clone());
+                if property.rust_primitive_unboxed.is_none() {
+                    f.add_import(property.value_type.import.clone());
+                }
+                if let Some(import) = &property.rust_primitive_import {
+                    f.add_import(import.clone());
+                }
+                calls += &format!("\n    .{}({})", method_name(property), value);
+                checks += &format!(
+                    "\nassert_eq!(built.deref().outgoing_nodes({}::TYPE_ID).len(), 1);",
+                    property.attr.name
+                );
+            }
+        }
+        if !checks.is_empty() {
+            f.add_import("std::ops::Deref".to_owned());
+            f.add_import("zamm_yin::node_wrappers::BaseNodeTrait".to_owned());
+            code += &format!(
+                "let built = {}::default(){}\n    .build()\n    .unwrap();{}",
+                cfg.name, calls, checks
+            );
+        }
+    }
+    if !code.is_empty() {
+        f.add_import("zamm_yin::tao::archetype::ArchetypeTrait".to_owned());
+    }
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(
+        code.trim_end().to_owned(),
+    ))));
+    f
+}
+
+/// Add the builder and its test to the file.
+pub fn add_builder_fragment(cfg: &BuilderConfig, file: &mut FileFragment) {
+    file.append(Rc::new(RefCell::new(builder_struct_fragment(cfg))));
+    let builder = builder_struct(cfg);
+    file.add_self_import(builder.import.clone());
+    let mut implementation = ImplementationFragment::new_struct_impl(builder);
+    for attr in &cfg.attributes {
+        implementation.append(Rc::new(RefCell::new(builder_method_fragment(
+            &attr.property,
+        ))));
+    }
+    implementation.append(Rc::new(RefCell::new(build_fragment(cfg))));
+    file.append(Rc::new(RefCell::new(implementation)));
+    file.append_test(Rc::new(RefCell::new(test_builder_fragment(cfg))));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::template::basic::CodeFragment;
+    use indoc::indoc;
+
+    fn lamp_builder_config() -> BuilderConfig {
+        let lamp = StructConfig {
+            name: "Lamp".to_owned(),
+            import: "crate::tao::form::Lamp".to_owned(),
+        };
+        BuilderConfig {
+            this: lamp.clone(),
+            name: "LampBuilder".to_owned(),
+            attributes: vec![
+                BuilderAttributeConfig {
+                    property: AttributePropertyConfig {
+                        property_name: Rc::from("label"),
+                        doc: Rc::from("the label on this lamp."),
+                        attr: StructConfig {
+                            name: "Label".to_owned(),
+                            import: "crate::tao::relation::attribute::Label".to_owned(),
+                        },
+                        owner_type: lamp.clone(),
+                        value_type: StructConfig {
+                            name: "StrConcept".to_owned(),
+                            import: "crate::tao::form::data::StrConcept".to_owned(),
+                        },
+                        rust_primitive: Some(Rc::from("str")),
+                        rust_primitive_unboxed: Some(Rc::from("&str")),
+                        primitive_test_value: Some(Rc::from("\"desk\"")),
+                        ..AttributePropertyConfig::default()
+                    },
+                    required: true,
+                },
+                BuilderAttributeConfig {
+                    property: AttributePropertyConfig {
+                        property_name: Rc::from("bulb"),
+                        doc: Rc::from("the bulbs in this lamp."),
+                        attr: StructConfig {
+                            name: "Bulb".to_owned(),
+                            import: "crate::tao::relation::attribute::Bulb".to_owned(),
+                        },
+                        owner_type: lamp,
+                        value_type: StructConfig {
+                            name: "LightBulb".to_owned(),
+                            import: "crate::tao::form::LightBulb".to_owned(),
+                        },
+                        multi_valued: true,
+                        ..AttributePropertyConfig::default()
+                    },
+                    required: false,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_builder_struct_fragment() {
+        assert_eq!(
+            builder_struct_fragment(&lamp_builder_config()).body(80),
+            indoc! {"
+                /// Builder for `Lamp` concepts, with a method for each attribute that they can
+                /// have. The `Lamp` itself only gets created once the builder is built.
+                #[derive(Debug, Default)]
+                pub struct LampBuilder {
+                    label: Option<StrConcept>,
+                    bulb: Vec<LightBulb>,
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_builder_method_fragments() {
+        let cfg = lamp_builder_config();
+        assert_eq!(
+            builder_method_fragment(&cfg.attributes[0].property).body(80),
+            indoc! {"
+                /// Set the label on this lamp.
+                pub fn label(mut self, label: &str) -> Self {
+                    let mut value_concept = StrConcept::new();
+                    value_concept.set_value(label);
+                    self.label = Some(value_concept);
+                    self
+                }"}
+        );
+        assert_eq!(
+            builder_method_fragment(&cfg.attributes[1].property).body(80),
+            indoc! {"
+                /// Add one of the bulbs in this lamp.
+                pub fn add_bulb(mut self, bulb: &LightBulb) -> Self {
+                    self.bulb.push(*bulb);
+                    self
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_build_fragment() {
+        assert_eq!(
+            build_fragment(&lamp_builder_config()).body(80),
+            indoc! {"
+                /// Create the `Lamp` with every attribute given to this builder. Fails if any
+                /// of its required attributes were left unset.
+                pub fn build(self) -> Result<Lamp, MissingAttributeError> {
+                    if self.label.is_none() {
+                        return Err(MissingAttributeError {
+                            concept: Lamp::TYPE_NAME,
+                            attribute: Label::TYPE_NAME,
+                        });
+                    }
+                    let mut concept = Lamp::new();
+                    if let Some(value) = self.label {
+                        concept.deref_mut().add_outgoing(Label::TYPE_ID, value.deref());
+                    }
+                    for value in self.bulb {
+                        concept.deref_mut().add_outgoing(Bulb::TYPE_ID, value.deref());
+                    }
+                    Ok(concept)
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_builder_test_fragment() {
+        assert_eq!(
+            test_builder_fragment(&lamp_builder_config()).body(80),
+            indoc! {"
+                #[test]
+                fn test_builder() {
+                    initialize_kb();
+                    assert_eq!(
+                        LampBuilder::default().build(),
+                        Err(MissingAttributeError {
+                            concept: Lamp::TYPE_NAME,
+                            attribute: Label::TYPE_NAME,
+                        })
+                    );
+
+                    let built = LampBuilder::default()
+                        .label(\"desk\")
+                        .add_bulb(&LightBulb::new())
+                        .build()
+                        .unwrap();
+                    assert_eq!(built.deref().outgoing_nodes(Label::TYPE_ID).len(), 1);
+                    assert_eq!(built.deref().outgoing_nodes(Bulb::TYPE_ID).len(), 1);
+                }"}
+        );
+    }
+}
@@ -1,9 +1,20 @@
 use super::tao::TaoConfig;
+use crate::codegen::docstring::into_docstring;
 use crate::codegen::template::basic::{AtomicFragment, FileFragment};
+use crate::codegen::CODE_WIDTH;
 use indoc::formatdoc;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// Config values for a single variant of an enumeration data concept.
+#[derive(Clone, Debug, Default)]
+pub struct EnumVariantConfig {
+    /// Name of the variant as it appears in Rust code.
+    pub name: Rc<str>,
+    /// Documentation for the variant.
+    pub doc: Option<Rc<str>>,
+}
+
 /// Config values at the time of Attribute code generation.
 pub struct DataFormatConfig {
     /// Regular concept config.
@@ -15,6 +26,9 @@ pub struct DataFormatConfig {
     pub rust_primitive_boxed_name: Rc<str>,
     /// Rust code representation of the default value of this concept.
     pub default_value: Rc<str>,
+    /// Variants of the enum that this concept represents, if it represents one. The enum itself
+    /// will be named after the boxed Rust primitive.
+    pub enum_variants: Vec<EnumVariantConfig>,
 }
 
 impl Default for DataFormatConfig {
@@ -24,6 +38,7 @@ impl Default for DataFormatConfig {
             rust_primitive_unboxed_name: Rc::from(""),
             rust_primitive_boxed_name: Rc::from(""),
             default_value: Rc::from(""),
+            enum_variants: vec![],
         }
     }
 }
@@ -62,6 +77,55 @@ fn data_concept_fragment(cfg: &DataFormatConfig) -> AtomicFragment {
     }
 }
 
+/// Get the enum fragment for an enumeration data concept.
+fn data_enum_fragment(cfg: &DataFormatConfig) -> AtomicFragment {
+    let mut variants = String::new();
+    for variant in &cfg.enum_variants {
+        if let Some(doc) = &variant.doc {
+            for line in into_docstring(doc, CODE_WIDTH - 4).lines() {
+                variants += &format!("    {}\n", line);
+            }
+        }
+        variants += &format!("    {},\n", variant.name);
+    }
+    AtomicFragment {
+        imports: vec![],
+        atom: format!(
+            "/// All the values that a `{name}` can take on.\n\
+            #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]\n\
+            pub enum {enum_name} {{\n{variants}}}",
+            name = cfg.tao_cfg.this.name,
+            enum_name = cfg.rust_primitive_boxed_name,
+            variants = variants,
+        ),
+    }
+}
+
+/// Get the test fragment that sets an enumeration data concept to each of its variants.
+fn data_enum_test_fragment(cfg: &DataFormatConfig) -> AtomicFragment {
+    let mut checks = String::new();
+    for variant in &cfg.enum_variants {
+        checks += &format!(
+            "    concept.set_value({enum_name}::{variant});\n    \
+            assert_eq!(concept.value(), Some(Rc::from({enum_name}::{variant})));\n",
+            enum_name = cfg.rust_primitive_boxed_name,
+            variant = variant.name,
+        );
+    }
+    AtomicFragment {
+        imports: vec![],
+        atom: format!(
+            "#[test]\n\
+            fn get_value_each_variant() {{\n    \
+                initialize_kb();\n    \
+                let mut concept = {name}::new();\n\
+            {checks}}}",
+            name = cfg.tao_cfg.this.name,
+            checks = checks,
+        ),
+    }
+}
+
 /// Get the data concept test fragment.
 fn data_concept_test_fragment(cfg: &DataFormatConfig) -> AtomicFragment {
     AtomicFragment {
@@ -90,13 +154,20 @@ fn data_concept_test_fragment(cfg: &DataFormatConfig) -> AtomicFragment {
 
 /// Generate code for a Data concept.
 pub fn add_data_fragments(cfg: &DataFormatConfig, file: &mut FileFragment) {
+    if !cfg.enum_variants.is_empty() {
+        file.append(Rc::new(RefCell::new(data_enum_fragment(cfg))));
+    }
     file.append(Rc::new(RefCell::new(data_concept_fragment(cfg))));
     file.append_test(Rc::new(RefCell::new(data_concept_test_fragment(cfg))));
+    if !cfg.enum_variants.is_empty() {
+        file.append_test(Rc::new(RefCell::new(data_enum_test_fragment(cfg))));
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use indoc::indoc;
     use std::rc::Rc;
 
     #[test]
@@ -132,4 +203,48 @@ mod tests {
         assert!(code.contains("i64"));
         assert!(code.contains("set_value"));
     }
+
+    #[test]
+    fn test_enum_output() {
+        let cfg = DataFormatConfig {
+            rust_primitive_unboxed_name: Rc::from("LightValue"),
+            rust_primitive_boxed_name: Rc::from("LightValue"),
+            default_value: Rc::from("LightValue::Red"),
+            enum_variants: vec![
+                EnumVariantConfig {
+                    name: Rc::from("Red"),
+                    doc: Some(Rc::from("Stop.")),
+                },
+                EnumVariantConfig {
+                    name: Rc::from("Green"),
+                    doc: None,
+                },
+            ],
+            ..DataFormatConfig::default()
+        };
+        assert_eq!(
+            data_enum_fragment(&cfg).atom,
+            indoc! {"
+                /// All the values that a `` can take on.
+                #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+                pub enum LightValue {
+                    /// Stop.
+                    Red,
+                    Green,
+                }"}
+        );
+        assert_eq!(
+            data_enum_test_fragment(&cfg).atom,
+            indoc! {"
+                #[test]
+                fn get_value_each_variant() {
+                    initialize_kb();
+                    let mut concept = ::new();
+                    concept.set_value(LightValue::Red);
+                    assert_eq!(concept.value(), Some(Rc::from(LightValue::Red)));
+                    concept.set_value(LightValue::Green);
+                    assert_eq!(concept.value(), Some(Rc::from(LightValue::Green)));
+                }"}
+        );
+    }
 }
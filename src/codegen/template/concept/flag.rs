@@ -1,6 +1,6 @@
 use crate::codegen::template::basic::{
     AtomicFragment, FileFragment, FunctionFragment, ImplementationFragment, ItemDeclarationAPI,
-    SelfReference,
+    SelfReference, TraitFragment,
 };
 use crate::codegen::StructConfig;
 use indoc::formatdoc;
@@ -154,6 +154,18 @@ pub fn add_flag_to_impl(
     file.append_test(Rc::new(RefCell::new(test_inheritance_fragment(cfg))));
 }
 
+/// Add these flags to an accessor trait as default methods, and to the corresponding test module.
+pub fn add_flag_to_trait(
+    cfg: &FlagConfig,
+    accessor_trait: &mut TraitFragment,
+    file: &mut FileFragment,
+) {
+    accessor_trait.append(Rc::new(RefCell::new(getter_fragment(cfg))));
+    accessor_trait.append(Rc::new(RefCell::new(setter_fragment(cfg))));
+    file.append_test(Rc::new(RefCell::new(test_fragment(cfg))));
+    file.append_test(Rc::new(RefCell::new(test_inheritance_fragment(cfg))));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
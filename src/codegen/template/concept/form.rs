@@ -1,5 +1,9 @@
+use super::auto_init_kb::DOWNCAST_ERROR_IMPORT;
 use super::tao::TaoConfig;
-use crate::codegen::template::basic::{AtomicFragment, FileFragment, ImplementationFragment};
+use super::util::kb_test_function;
+use crate::codegen::template::basic::{
+    AtomicFragment, FileFragment, FunctionFragment, ImplementationFragment,
+};
 use crate::codegen::StructConfig;
 use indoc::formatdoc;
 use std::cell::RefCell;
@@ -46,6 +50,67 @@ fn deref_fragment(this_name: &str, ancestor: &StructConfig) -> ImplementationFra
     implementation
 }
 
+/// Checked conversion from an ancestor back down into this concept.
+fn try_from_fragment(this: &StructConfig, ancestor: &StructConfig) -> ImplementationFragment {
+    let mut implementation = ImplementationFragment::new_trait_impl(
+        StructConfig {
+            name: format!("TryFrom<{}>", ancestor.name),
+            import: "std::convert::TryFrom".to_owned(),
+        },
+        this.clone(),
+    );
+    implementation.append(Rc::new(RefCell::new(AtomicFragment {
+        imports: vec![
+            ancestor.import.clone(),
+            DOWNCAST_ERROR_IMPORT.to_owned(),
+            "zamm_yin::tao::archetype::Archetype".to_owned(),
+            "zamm_yin::tao::archetype::ArchetypeTrait".to_owned(),
+            "zamm_yin::tao::form::Form".to_owned(),
+            "zamm_yin::tao::form::FormTrait".to_owned(),
+            "zamm_yin::node_wrappers::CommonNodeTrait".to_owned(),
+        ],
+        atom: formatdoc! {"
+            type Error = DowncastError;
+
+            fn try_from(ancestor: {ancestor}) -> Result<Self, Self::Error> {{
+                if Form::from(ancestor.id()).has_ancestor(Archetype::from(Self::TYPE_ID)) {{
+                    Ok(Self::from(ancestor.id()))
+                }} else {{
+                    Err(DowncastError {{
+                        id: ancestor.id(),
+                        target: Self::TYPE_NAME,
+                    }})
+                }}
+            }}",
+        ancestor = ancestor.name},
+    })));
+    implementation.mark_same_file_as_struct();
+    implementation
+}
+
+/// Test that downcasting only succeeds for concepts that descend from this one.
+fn test_downcast_fragment(this_name: &str, parent: &StructConfig) -> FunctionFragment {
+    let mut f = kb_test_function("test_downcast");
+    f.add_import(parent.import.clone());
+    f.add_import(DOWNCAST_ERROR_IMPORT.to_owned());
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(formatdoc! {"
+        let concept = {this}::new();
+        assert_eq!({this}::try_from({parent}::from(concept)), Ok(concept));
+
+        let parent = {parent}::from({parent}::new().id());
+        assert_eq!(
+            {this}::try_from(parent),
+            Err(DowncastError {{
+                id: parent.id(),
+                target: {this}::TYPE_NAME,
+            }})
+        );",
+        this = this_name,
+        parent = parent.name,
+    }))));
+    f
+}
+
 /// Add the form fragment to a file.
 pub fn add_form_fragment(cfg: &FormFormatConfig, file: &mut FileFragment) {
     file.append(Rc::new(RefCell::new(form_impl_fragment(&cfg.tao_cfg))));
@@ -54,6 +119,16 @@ pub fn add_form_fragment(cfg: &FormFormatConfig, file: &mut FileFragment) {
             &cfg.tao_cfg.this.name,
             &ancestor,
         ))));
+        file.append(Rc::new(RefCell::new(try_from_fragment(
+            &cfg.tao_cfg.this,
+            &ancestor,
+        ))));
+    }
+    if let Some(parent) = cfg.ancestors.last() {
+        file.append_test(Rc::new(RefCell::new(test_downcast_fragment(
+            &cfg.tao_cfg.this.name,
+            parent,
+        ))));
     }
 }
 
@@ -94,4 +169,57 @@ mod tests {
                 }"}
         );
     }
+
+    #[test]
+    fn test_try_from_fragment() {
+        assert_eq!(
+            try_from_fragment(
+                &StructConfig::new("crate::MyConcept".to_owned()),
+                &StructConfig::new("crate::MyParent".to_owned())
+            )
+            .body(80),
+            indoc! {"
+                impl TryFrom<MyParent> for MyConcept {
+                    type Error = DowncastError;
+
+                    fn try_from(ancestor: MyParent) -> Result<Self, Self::Error> {
+                        if Form::from(ancestor.id()).has_ancestor(Archetype::from(Self::TYPE_ID)) {
+                            Ok(Self::from(ancestor.id()))
+                        } else {
+                            Err(DowncastError {
+                                id: ancestor.id(),
+                                target: Self::TYPE_NAME,
+                            })
+                        }
+                    }
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_downcast_fragment_body() {
+        assert_eq!(
+            test_downcast_fragment(
+                "MyConcept",
+                &StructConfig::new("crate::MyParent".to_owned())
+            )
+            .body(80),
+            indoc! {"
+                #[test]
+                fn test_downcast() {
+                    initialize_kb();
+                    let concept = MyConcept::new();
+                    assert_eq!(MyConcept::try_from(MyParent::from(concept)), Ok(concept));
+
+                    let parent = MyParent::from(MyParent::new().id());
+                    assert_eq!(
+                        MyConcept::try_from(parent),
+                        Err(DowncastError {
+                            id: parent.id(),
+                            target: MyConcept::TYPE_NAME,
+                        })
+                    );
+                }"}
+        );
+    }
 }
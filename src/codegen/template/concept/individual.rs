@@ -0,0 +1,87 @@
+use crate::codegen::template::basic::{
+    AtomicFragment, FunctionFragment, ImplementationFragment, ItemDeclarationAPI,
+};
+use crate::codegen::StructConfig;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Config values at the time of individual accessor code generation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IndividualAccessorConfig {
+    /// Name of the accessor function.
+    pub name: String,
+    /// Documentation for the accessor.
+    pub doc: String,
+    /// The Rust expression for the individual's ID.
+    pub id: String,
+}
+
+/// Returns an accessor that retrieves a single named individual.
+fn individual_accessor_fragment(cfg: &IndividualAccessorConfig) -> FunctionFragment {
+    let mut f = FunctionFragment::new(cfg.name.clone());
+    f.mark_as_public();
+    f.document(cfg.doc.clone());
+    f.set_return("Self".to_owned());
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(format!(
+        "Self::from({})",
+        cfg.id
+    )))));
+    f
+}
+
+/// Returns an implementation of the archetype's struct with accessors for each of its named
+/// individuals.
+pub fn individual_accessors_fragment(
+    archetype: &StructConfig,
+    accessors: &[IndividualAccessorConfig],
+) -> ImplementationFragment {
+    let mut implementation = ImplementationFragment::new_struct_impl(archetype.clone());
+    implementation.mark_same_file_as_struct();
+    for accessor in accessors {
+        implementation.append(Rc::new(RefCell::new(individual_accessor_fragment(
+            accessor,
+        ))));
+    }
+    implementation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::template::basic::CodeFragment;
+    use indoc::indoc;
+
+    #[test]
+    fn test_individual_accessors() {
+        let implementation = individual_accessors_fragment(
+            &StructConfig::new("crate::lamps::Lamp".to_owned()),
+            &[
+                IndividualAccessorConfig {
+                    name: "desk_lamp".to_owned(),
+                    doc: "The lamp on the desk.".to_owned(),
+                    id: "YIN_MAX_ID + 2".to_owned(),
+                },
+                IndividualAccessorConfig {
+                    name: "floor_lamp".to_owned(),
+                    doc: "The lamp on the floor.".to_owned(),
+                    id: "YIN_MAX_ID + 3".to_owned(),
+                },
+            ],
+        );
+        assert_eq!(
+            implementation.body(80),
+            indoc! {"
+                impl Lamp {
+                    /// The lamp on the desk.
+                    pub fn desk_lamp() -> Self {
+                        Self::from(YIN_MAX_ID + 2)
+                    }
+
+                    /// The lamp on the floor.
+                    pub fn floor_lamp() -> Self {
+                        Self::from(YIN_MAX_ID + 3)
+                    }
+                }"}
+        );
+    }
+}
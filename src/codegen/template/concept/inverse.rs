@@ -0,0 +1,328 @@
+use super::util::kb_test_function;
+use crate::codegen::template::basic::{
+    AtomicFragment, FileFragment, FunctionFragment, ImplementationFragment, ItemDeclarationAPI,
+    SelfReference,
+};
+use crate::codegen::StructConfig;
+use indoc::formatdoc;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Config values at the time of inverse attribute accessor code generation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InverseAccessorConfig {
+    /// Name of the getter on the value type.
+    pub property_name: Rc<str>,
+    /// Whether or not the getter should be marked public.
+    pub public: bool,
+    /// An explanation of what the getter retrieves. Will be prepended by "Get".
+    pub doc: Rc<str>,
+    /// Concept representing the attribute.
+    pub attr: StructConfig,
+    /// Concept representing the owner of the attribute, which is what the getter returns.
+    pub owner_type: StructConfig,
+    /// Concept representing the value of the attribute, which is what the getter is defined on.
+    pub value_type: StructConfig,
+    /// Whether or not owners of the value's ancestors also count as owners of the value.
+    pub hereditary: bool,
+    /// Whether or not the attribute can contain multiple values. If it can't, only owners whose
+    /// current value is this one count, so that overridden values don't show up.
+    pub multi_valued: bool,
+}
+
+/// Get the getter fragment for the inverse accessor.
+fn inverse_getter_fragment(cfg: &InverseAccessorConfig) -> FunctionFragment {
+    let mut f = FunctionFragment::new(cfg.property_name.to_string());
+
+    f.add_import(cfg.attr.import.clone());
+    f.add_import(cfg.owner_type.import.clone());
+    f.add_import("zamm_yin::tao::archetype::ArchetypeTrait".to_owned());
+    f.add_import("zamm_yin::node_wrappers::BaseNodeTrait".to_owned());
+    f.add_import("zamm_yin::node_wrappers::CommonNodeTrait".to_owned());
+
+    if cfg.public {
+        f.mark_as_public();
+    }
+    f.document(format!("Get {}", cfg.doc));
+    f.set_self_reference(SelfReference::Immutable);
+    f.set_return(format!("Vec<{}>", cfg.owner_type.name));
+
+    let (current_values, nonhereditary_access, current_check) = if cfg.hereditary {
+        f.add_import("zamm_yin::node_wrappers::InheritanceNodeTrait".to_owned());
+        (
+            "let values = self.deref().inheritance_nodes();\n",
+            "",
+            "\n            .map_or(false, |v| values.contains(v))",
+        )
+    } else {
+        (
+            "let value = self.deref().base_wrapper();\n",
+            "\n    .base_wrapper()",
+            " == Some(value)",
+        )
+    };
+    let body = if cfg.multi_valued {
+        formatdoc! {"
+            self.deref(){inheritance}
+                .incoming_nodes({attr}::TYPE_ID)
+                .into_iter()
+                .map(|f| {owner}::from(f.id()))
+                .collect()",
+            inheritance = nonhereditary_access,
+            attr = cfg.attr.name,
+            owner = cfg.owner_type.name,
+        }
+    } else {
+        // outgoing nodes are sorted by ID, and the last one is the owner's current value
+        formatdoc! {"
+            {current_values}self.deref(){inheritance}
+                .incoming_nodes({attr}::TYPE_ID)
+                .into_iter()
+                .filter(|f| {{
+                    f.outgoing_nodes({attr}::TYPE_ID)
+                        .last(){current_check}
+                }})
+                .map(|f| {owner}::from(f.id()))
+                .collect()",
+            current_values = current_values,
+            inheritance = nonhereditary_access,
+            attr = cfg.attr.name,
+            current_check = current_check,
+            owner = cfg.owner_type.name,
+        }
+    };
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(body))));
+    f
+}
+
+/// Test that the inverse getter finds the owners that point to a value.
+fn test_inverse_fragment(cfg: &InverseAccessorConfig) -> FunctionFragment {
+    let mut f = kb_test_function(&format!("test_get_{}", cfg.property_name));
+    f.add_import(cfg.owner_type.import.clone());
+    f.add_import(cfg.value_type.import.clone());
+    f.add_import(cfg.attr.import.clone());
+    let expected = if cfg.multi_valued {
+        "vec![owner, overridden]"
+    } else {
+        // the overridden owner now points somewhere else
+        "vec![owner]"
+    };
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(formatdoc! {"
+        let value = {value}::new();
+        assert_eq!(value.{getter}(), vec![]);
+
+        let mut owner = {owner}::new();
+        owner.deref_mut().add_outgoing({attr}::TYPE_ID, value.deref());
+        let mut overridden = {owner}::new();
+        overridden.deref_mut().add_outgoing({attr}::TYPE_ID, value.deref());
+        overridden
+            .deref_mut()
+            .add_outgoing({attr}::TYPE_ID, {value}::new().deref());
+        assert_eq!(value.{getter}(), {expected});",
+        value = cfg.value_type.name,
+        owner = cfg.owner_type.name,
+        attr = cfg.attr.name,
+        getter = cfg.property_name,
+        expected = expected,
+    }))));
+    f
+}
+
+/// Test that the inverse getter works as intended for descendants of the value.
+fn test_inverse_inheritance_fragment(cfg: &InverseAccessorConfig) -> FunctionFragment {
+    let inheritance_name = if cfg.hereditary {
+        "inheritance"
+    } else {
+        "non_inheritance"
+    };
+    let inheritance_check = if cfg.hereditary {
+        "vec![owner]"
+    } else {
+        "vec![]"
+    };
+    let mut f = kb_test_function(&format!("test_{}_{}", cfg.property_name, inheritance_name));
+    f.add_import(cfg.owner_type.import.clone());
+    f.add_import(cfg.value_type.import.clone());
+    f.add_import(cfg.attr.import.clone());
+    f.add_import("zamm_yin::tao::archetype::ArchetypeFormTrait".to_owned());
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(formatdoc! {"
+        let new_type = {value}::archetype().individuate_as_archetype();
+        let new_instance = {value}::from(new_type.individuate_as_form().id());
+        assert_eq!(new_instance.{getter}(), vec![]);
+
+        let mut owner = {owner}::new();
+        owner
+            .deref_mut()
+            .add_outgoing({attr}::TYPE_ID, {value}::from(new_type.id()).deref());
+        assert_eq!(new_instance.{getter}(), {inheritance});",
+        value = cfg.value_type.name,
+        owner = cfg.owner_type.name,
+        attr = cfg.attr.name,
+        getter = cfg.property_name,
+        inheritance = inheritance_check,
+    }))));
+    f
+}
+
+/// Add this inverse accessor to an implementation and its corresponding test module.
+pub fn add_inverse_to_impl(
+    cfg: &InverseAccessorConfig,
+    implementation: &mut ImplementationFragment,
+    file: &mut FileFragment,
+) {
+    implementation.append(Rc::new(RefCell::new(inverse_getter_fragment(cfg))));
+    file.append_test(Rc::new(RefCell::new(test_inverse_fragment(cfg))));
+    file.append_test(Rc::new(RefCell::new(test_inverse_inheritance_fragment(
+        cfg,
+    ))));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::template::basic::CodeFragment;
+    use indoc::indoc;
+
+    fn member_of_config() -> InverseAccessorConfig {
+        InverseAccessorConfig {
+            property_name: Rc::from("member_of"),
+            public: true,
+            doc: Rc::from("the collections that this is a member of."),
+            attr: StructConfig {
+                name: "Member".to_owned(),
+                import: "crate::tao::relation::attribute::Member".to_owned(),
+            },
+            owner_type: StructConfig {
+                name: "Collection".to_owned(),
+                import: "crate::tao::form::Collection".to_owned(),
+            },
+            value_type: StructConfig {
+                name: "Form".to_owned(),
+                import: "zamm_yin::tao::form::Form".to_owned(),
+            },
+            hereditary: true,
+            multi_valued: true,
+        }
+    }
+
+    #[test]
+    fn test_multi_valued_getter_fragment_body() {
+        assert_eq!(
+            inverse_getter_fragment(&member_of_config()).body(80),
+            indoc! {"
+                /// Get the collections that this is a member of.
+                pub fn member_of(&self) -> Vec<Collection> {
+                    self.deref()
+                        .incoming_nodes(Member::TYPE_ID)
+                        .into_iter()
+                        .map(|f| Collection::from(f.id()))
+                        .collect()
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_single_valued_getter_fragment_body() {
+        assert_eq!(
+            inverse_getter_fragment(&InverseAccessorConfig {
+                multi_valued: false,
+                ..member_of_config()
+            })
+            .body(80),
+            indoc! {"
+                /// Get the collections that this is a member of.
+                pub fn member_of(&self) -> Vec<Collection> {
+                    let values = self.deref().inheritance_nodes();
+                    self.deref()
+                        .incoming_nodes(Member::TYPE_ID)
+                        .into_iter()
+                        .filter(|f| {
+                            f.outgoing_nodes(Member::TYPE_ID)
+                                .last()
+                                .map_or(false, |v| values.contains(v))
+                        })
+                        .map(|f| Collection::from(f.id()))
+                        .collect()
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_nonhereditary_getter_fragment_body() {
+        assert_eq!(
+            inverse_getter_fragment(&InverseAccessorConfig {
+                hereditary: false,
+                multi_valued: false,
+                ..member_of_config()
+            })
+            .body(80),
+            indoc! {"
+                /// Get the collections that this is a member of.
+                pub fn member_of(&self) -> Vec<Collection> {
+                    let value = self.deref().base_wrapper();
+                    self.deref()
+                        .base_wrapper()
+                        .incoming_nodes(Member::TYPE_ID)
+                        .into_iter()
+                        .filter(|f| {
+                            f.outgoing_nodes(Member::TYPE_ID)
+                                .last() == Some(value)
+                        })
+                        .map(|f| Collection::from(f.id()))
+                        .collect()
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_inverse_test_fragment_body() {
+        assert_eq!(
+            test_inverse_fragment(&InverseAccessorConfig {
+                multi_valued: false,
+                ..member_of_config()
+            })
+            .body(80),
+            indoc! {"
+                #[test]
+                fn test_get_member_of() {
+                    initialize_kb();
+                    let value = Form::new();
+                    assert_eq!(value.member_of(), vec![]);
+
+                    let mut owner = Collection::new();
+                    owner.deref_mut().add_outgoing(Member::TYPE_ID, value.deref());
+                    let mut overridden = Collection::new();
+                    overridden.deref_mut().add_outgoing(Member::TYPE_ID, value.deref());
+                    overridden
+                        .deref_mut()
+                        .add_outgoing(Member::TYPE_ID, Form::new().deref());
+                    assert_eq!(value.member_of(), vec![owner]);
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_inverse_inheritance_fragment_body() {
+        assert_eq!(
+            test_inverse_inheritance_fragment(&InverseAccessorConfig {
+                hereditary: false,
+                ..member_of_config()
+            })
+            .body(80),
+            indoc! {"
+                #[test]
+                fn test_member_of_non_inheritance() {
+                    initialize_kb();
+                    let new_type = Form::archetype().individuate_as_archetype();
+                    let new_instance = Form::from(new_type.individuate_as_form().id());
+                    assert_eq!(new_instance.member_of(), vec![]);
+
+                    let mut owner = Collection::new();
+                    owner
+                        .deref_mut()
+                        .add_outgoing(Member::TYPE_ID, Form::from(new_type.id()).deref());
+                    assert_eq!(new_instance.member_of(), vec![]);
+                }"}
+        );
+    }
+}
@@ -1,3 +1,5 @@
+/// Accessor trait code template.
+pub mod accessor_trait;
 /// Archetype code template.
 pub mod archetype;
 /// Archetype-specific module code template.
@@ -9,12 +11,22 @@ pub mod attribute;
 pub mod attribute_property;
 /// KB auto-init template.
 pub mod auto_init_kb;
+/// Builder code template, for creating concepts with all their attributes at once.
+pub mod builder;
 /// Data code template.
 pub mod data;
 /// Flag trait code template.
 pub mod flag;
 /// Form code template.
 pub mod form;
+/// Named individual accessor code template.
+pub mod individual;
+/// Inverse attribute accessor code template, for getting the owners of an attribute from its value.
+pub mod inverse;
 /// Tao code template.
 pub mod tao;
 mod util;
+/// Validation code template, for checking that concepts have enough values for their attributes.
+pub mod validate;
+/// Visitor code template, for dispatching concepts to methods by their most specific type.
+pub mod visitor;
@@ -272,7 +272,7 @@ fn deref_mut_fragment(cfg: &TaoConfig) -> ImplementationFragment {
 /// Returns a file fragment, which may be appended to further.
 pub fn tao_file_fragment(cfg: &TaoConfig) -> FileFragment {
     let mut file = FileFragment::default();
-    file.set_self_import(cfg.this.import.clone());
+    file.add_self_import(cfg.this.import.clone());
     file.append(Rc::new(RefCell::new(tao_fragment(cfg))));
     file.append(Rc::new(RefCell::new(deref_fragment(cfg))));
     file.append(Rc::new(RefCell::new(deref_mut_fragment(cfg))));
@@ -2,6 +2,7 @@ use crate::codegen::template::basic::{
     AppendedFragment, AssertFragment, AtomicFragment, CodeFragment, FunctionCallFragment,
     FunctionFragment,
 };
+use crate::codegen::StructConfig;
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -39,3 +40,11 @@ pub fn add_assert(test_function: &Rc<RefCell<FunctionFragment>>, lhs: String, rh
         Rc::new(RefCell::new(AtomicFragment::new(rhs))),
     );
 }
+
+/// The error for when the concept doesn't have enough values for the attribute.
+pub fn missing_attribute_error(this: &StructConfig, attr: &StructConfig) -> String {
+    format!(
+        "MissingAttributeError {{\n    concept: {}::TYPE_NAME,\n    attribute: {}::TYPE_NAME,\n}}",
+        this.name, attr.name
+    )
+}
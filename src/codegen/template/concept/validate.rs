@@ -0,0 +1,257 @@
+use super::auto_init_kb::MISSING_ATTRIBUTE_ERROR_IMPORT;
+use super::util::{kb_test_function, missing_attribute_error};
+use crate::codegen::template::basic::{
+    AtomicFragment, FileFragment, FunctionFragment, ImplementationFragment, ItemDeclarationAPI,
+    SelfReference,
+};
+use crate::codegen::StructConfig;
+use indoc::{formatdoc, indoc};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Config values for a single attribute that needs a minimum number of values.
+pub struct ValidateAttributeConfig {
+    /// Concept representing the attribute.
+    pub attr: StructConfig,
+    /// Concept representing the value of the attribute.
+    pub value_type: StructConfig,
+    /// Whether or not values set on the owner's ancestors count towards the minimum.
+    pub hereditary: bool,
+    /// The fewest values that the owner needs for this attribute.
+    pub min_values: usize,
+}
+
+/// Config values at the time of `validate` code generation.
+pub struct ValidateConfig {
+    /// Concept that gets validated.
+    pub this: StructConfig,
+    /// Every attribute that the concept needs a minimum number of values for, whether introduced
+    /// by the concept itself or inherited from its ancestors.
+    pub attributes: Vec<ValidateAttributeConfig>,
+}
+
+/// Code that checks whether the concept has too few values for the attribute.
+fn shortfall_check(cfg: &ValidateAttributeConfig) -> String {
+    let nodes = if cfg.hereditary {
+        "self.deref()"
+    } else {
+        "self.deref().base_wrapper()"
+    };
+    if cfg.min_values == 1 {
+        format!(
+            "{}.outgoing_nodes({}::TYPE_ID).is_empty()",
+            nodes, cfg.attr.name
+        )
+    } else {
+        format!(
+            "{}.outgoing_nodes({}::TYPE_ID).len() < {}",
+            nodes, cfg.attr.name, cfg.min_values
+        )
+    }
+}
+
+/// Get the function that reports every attribute the concept doesn't have enough values for.
+fn validate_fragment(cfg: &ValidateConfig) -> FunctionFragment {
+    let mut f = FunctionFragment::new("validate".to_owned());
+    f.add_import(MISSING_ATTRIBUTE_ERROR_IMPORT.to_owned());
+    f.add_import("std::ops::Deref".to_owned());
+    f.add_import("zamm_yin::node_wrappers::BaseNodeTrait".to_owned());
+    f.add_import("zamm_yin::tao::archetype::ArchetypeTrait".to_owned());
+    f.mark_as_public();
+    f.document(format!(
+        "Check that this `{}` has as many values as each of its attributes needs. Every attribute \
+        that falls short gets reported.",
+        cfg.this.name
+    ));
+    f.set_self_reference(SelfReference::Immutable);
+    f.set_return("Result<(), Vec<MissingAttributeError>>".to_owned());
+
+    let mut body = "let mut missing = vec![];\n".to_owned();
+    for attr in &cfg.attributes {
+        f.add_import(attr.attr.import.clone());
+        body += &formatdoc! {"
+            if {check} {{
+                missing.push({error});
+            }}
+            ",
+            check = shortfall_check(attr),
+            error = missing_attribute_error(&cfg.this, &attr.attr).replace('\n', "\n    "),
+        };
+    }
+    body += indoc! {"
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(missing)
+        }"};
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(body))));
+    f
+}
+
+/// Test that `validate` reports every attribute until each one has enough values.
+fn test_validate_fragment(cfg: &ValidateConfig) -> FunctionFragment {
+    let mut f = kb_test_function("test_validate");
+    f.add_import(cfg.this.import.clone());
+    f.add_import(MISSING_ATTRIBUTE_ERROR_IMPORT.to_owned());
+    f.add_import("std::ops::Deref".to_owned());
+    f.add_import("std::ops::DerefMut".to_owned());
+    f.add_import("zamm_yin::node_wrappers::BaseNodeTrait".to_owned());
+    f.add_import("zamm_yin::tao::archetype::ArchetypeTrait".to_owned());
+
+    let mut errors = String::new();
+    let mut additions = String::new();
+    for attr in &cfg.attributes {
+        f.add_import(attr.attr.import.clone());
+        f.add_import(attr.value_type.import.clone());
+        errors += &format!(
+            "\n        {},",
+            missing_attribute_error(&cfg.this, &attr.attr).replace('\n', "\n        ")
+        );
+        for _ in 0..attr.min_values {
+            additions += &format!(
+                "concept\n    .deref_mut()\n    .add_outgoing({}::TYPE_ID, {}::new().deref());\n",
+                attr.attr.name, attr.value_type.name
+            );
+        }
+    }
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(formatdoc! {"
+        let mut concept = {this}::new();
+        assert_eq!(
+            concept.validate(),
+            Err(vec![{errors}
+            ])
+        );
+
+        {additions}assert_eq!(concept.validate(), Ok(()));",
+        this = cfg.this.name,
+        errors = errors,
+        additions = additions,
+    }))));
+    f
+}
+
+/// Add `validate` and its test to the file.
+pub fn add_validate_fragment(cfg: &ValidateConfig, file: &mut FileFragment) {
+    let mut implementation = ImplementationFragment::new_struct_impl(cfg.this.clone());
+    implementation.append(Rc::new(RefCell::new(validate_fragment(cfg))));
+    file.append(Rc::new(RefCell::new(implementation)));
+    file.append_test(Rc::new(RefCell::new(test_validate_fragment(cfg))));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::template::basic::CodeFragment;
+
+    fn lamp_validate_config() -> ValidateConfig {
+        ValidateConfig {
+            this: StructConfig {
+                name: "Lamp".to_owned(),
+                import: "crate::tao::form::Lamp".to_owned(),
+            },
+            attributes: vec![
+                ValidateAttributeConfig {
+                    attr: StructConfig {
+                        name: "Label".to_owned(),
+                        import: "crate::tao::relation::attribute::Label".to_owned(),
+                    },
+                    value_type: StructConfig {
+                        name: "StrConcept".to_owned(),
+                        import: "crate::tao::form::data::StrConcept".to_owned(),
+                    },
+                    hereditary: false,
+                    min_values: 1,
+                },
+                ValidateAttributeConfig {
+                    attr: StructConfig {
+                        name: "Bulb".to_owned(),
+                        import: "crate::tao::relation::attribute::Bulb".to_owned(),
+                    },
+                    value_type: StructConfig {
+                        name: "LightBulb".to_owned(),
+                        import: "crate::tao::form::LightBulb".to_owned(),
+                    },
+                    hereditary: true,
+                    min_values: 2,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_validate_fragment_body() {
+        assert_eq!(
+            validate_fragment(&lamp_validate_config()).body(80),
+            indoc! {"
+                /// Check that this `Lamp` has as many values as each of its attributes needs.
+                /// Every attribute that falls short gets reported.
+                pub fn validate(&self) -> Result<(), Vec<MissingAttributeError>> {
+                    let mut missing = vec![];
+                    if self.deref().base_wrapper().outgoing_nodes(Label::TYPE_ID).is_empty() {
+                        missing.push(MissingAttributeError {
+                            concept: Lamp::TYPE_NAME,
+                            attribute: Label::TYPE_NAME,
+                        });
+                    }
+                    if self.deref().outgoing_nodes(Bulb::TYPE_ID).len() < 2 {
+                        missing.push(MissingAttributeError {
+                            concept: Lamp::TYPE_NAME,
+                            attribute: Bulb::TYPE_NAME,
+                        });
+                    }
+                    if missing.is_empty() {
+                        Ok(())
+                    } else {
+                        Err(missing)
+                    }
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_test_validate_fragment_body() {
+        assert_eq!(
+            test_validate_fragment(&lamp_validate_config()).body(80),
+            indoc! {"
+                #[test]
+                fn test_validate() {
+                    initialize_kb();
+                    let mut concept = Lamp::new();
+                    assert_eq!(
+                        concept.validate(),
+                        Err(vec![
+                            MissingAttributeError {
+                                concept: Lamp::TYPE_NAME,
+                                attribute: Label::TYPE_NAME,
+                            },
+                            MissingAttributeError {
+                                concept: Lamp::TYPE_NAME,
+                                attribute: Bulb::TYPE_NAME,
+                            },
+                        ])
+                    );
+
+                    concept
+                        .deref_mut()
+                        .add_outgoing(Label::TYPE_ID, StrConcept::new().deref());
+                    concept
+                        .deref_mut()
+                        .add_outgoing(Bulb::TYPE_ID, LightBulb::new().deref());
+                    concept
+                        .deref_mut()
+                        .add_outgoing(Bulb::TYPE_ID, LightBulb::new().deref());
+                    assert_eq!(concept.validate(), Ok(()));
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_add_validate_fragment_imports() {
+        let mut file = FileFragment::new();
+        add_validate_fragment(&lamp_validate_config(), &mut file);
+        let code = file.generate_code();
+        assert!(code.contains("impl Lamp {"));
+        assert!(code.contains("use crate::tao::auto_init::MissingAttributeError;"));
+        assert!(code.contains("use crate::tao::form::{Lamp, LightBulb};"));
+    }
+}
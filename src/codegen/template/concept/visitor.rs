@@ -0,0 +1,227 @@
+use super::util::kb_test_function;
+use crate::codegen::docstring::into_docstring;
+use crate::codegen::template::basic::{AtomicFragment, FunctionFragment};
+use crate::codegen::{StructConfig, CODE_WIDTH};
+use indoc::formatdoc;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Config values for a single method of the generated visitor.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VisitorMethod {
+    /// Concept that this method visits.
+    pub concept: StructConfig,
+    /// Name of the method.
+    pub name: String,
+    /// The concept's primary parent and the name of the method that visits it, if the parent has
+    /// a method of its own to delegate to.
+    pub parent: Option<(StructConfig, String)>,
+}
+
+/// Defines the visitor trait with one method per concept, along with the function that walks
+/// nodes into it.
+pub fn visitor_fragment(methods: &[VisitorMethod]) -> AtomicFragment {
+    let mut imports = vec!["zamm_yin::node_wrappers::CommonNodeTrait".to_owned()];
+    let mut trait_methods = vec![];
+    let mut walk_arms = String::new();
+    for method in methods {
+        imports.push(method.concept.import.clone());
+        // trait methods are indented once, which leaves their docs less room
+        let definition = match &method.parent {
+            Some((parent, parent_method)) => {
+                imports.push(parent.import.clone());
+                formatdoc! {"
+                    {doc}
+                    fn {method}(&mut self, concept: {name}) {{
+                        self.{parent_method}({parent}::from(concept.id()));
+                    }}",
+                    doc = into_docstring(
+                        &format!(
+                            "Visit a concept of type `{}`. By default, this visits it as its \
+                            parent type `{}` instead.",
+                            method.concept.name, parent.name
+                        ),
+                        CODE_WIDTH - 4
+                    ),
+                    name = method.concept.name,
+                    method = method.name,
+                    parent = parent.name,
+                    parent_method = parent_method,
+                }
+            }
+            None => formatdoc! {"
+                {doc}
+                fn {method}(&mut self, _concept: {name}) {{}}",
+                doc = into_docstring(
+                    &format!(
+                        "Visit a concept of type `{}`. Does nothing by default.",
+                        method.concept.name
+                    ),
+                    CODE_WIDTH - 4
+                ),
+                name = method.concept.name,
+                method = method.name,
+            },
+        };
+        trait_methods.push(definition.replace('\n', "\n    "));
+        walk_arms += &format!(
+            "        Some(AnyConcept::{name}(c)) => visitor.{method}(c),\n",
+            name = method.concept.name,
+            method = method.name,
+        );
+    }
+    AtomicFragment {
+        imports,
+        atom: formatdoc! {"
+            /// Visits the concepts distributed by this crate. Each method visits the
+            /// concept as its parent type by default, so implementations only need to
+            /// override the methods for the types that they treat differently.
+            pub trait Visitor {{
+                {methods}
+            }}
+
+            /// Visit a node with the method for the most specific generated type that it
+            /// descends from. Nodes that don't descend from any of the types distributed by
+            /// this crate are skipped.
+            pub fn walk<V: Visitor + ?Sized>(visitor: &mut V, id: usize) {{
+                match AnyConcept::from_node(id) {{
+            {walk_arms}        None => {{}}
+                }}
+            }}
+        ",
+            methods = trait_methods.join("\n\n    "),
+            walk_arms = walk_arms,
+        },
+    }
+}
+
+/// The method that a visit of this concept ends up in when no methods are overridden.
+fn root_method<'a>(methods: &'a [VisitorMethod], method: &'a VisitorMethod) -> &'a VisitorMethod {
+    let mut current = method;
+    while let Some((_, parent_method)) = &current.parent {
+        match methods.iter().find(|m| &m.name == parent_method) {
+            Some(parent) => current = parent,
+            None => break,
+        }
+    }
+    current
+}
+
+/// Test that visits default to the visitor methods of the concept's ancestors.
+pub fn visitor_test(methods: &[VisitorMethod]) -> FunctionFragment {
+    let mut f = kb_test_function("test_visitor_delegation");
+    let visited = match methods.last() {
+        Some(visited) => visited,
+        None => return f,
+    };
+    let root = root_method(methods, visited);
+    f.add_import("zamm_yin::node_wrappers::CommonNodeTrait".to_owned());
+    f.add_import("zamm_yin::tao::archetype::ArchetypeTrait".to_owned());
+    f.add_import(visited.concept.import.clone());
+    f.add_import(root.concept.import.clone());
+    f.append(Rc::new(RefCell::new(AtomicFragment::new(formatdoc! {"
+        struct Recorder {{
+            visited: Vec<usize>,
+        }}
+
+        impl Visitor for Recorder {{
+            fn {root_method}(&mut self, concept: {root}) {{
+                self.visited.push(concept.id());
+            }}
+        }}
+
+        let mut recorder = Recorder {{ visited: vec![] }};
+        walk(&mut recorder, {visited}::TYPE_ID);
+        assert_eq!(recorder.visited, vec![{visited}::TYPE_ID]);",
+        root_method = root.name,
+        root = root.concept.name,
+        visited = visited.concept.name,
+    }))));
+    f
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::template::basic::CodeFragment;
+    use indoc::indoc;
+
+    fn methods() -> Vec<VisitorMethod> {
+        let animal = StructConfig {
+            name: "Animal".to_owned(),
+            import: "crate::tao::form::Animal".to_owned(),
+        };
+        vec![
+            VisitorMethod {
+                concept: animal.clone(),
+                name: "visit_animal".to_owned(),
+                parent: None,
+            },
+            VisitorMethod {
+                concept: StructConfig {
+                    name: "Dog".to_owned(),
+                    import: "crate::tao::form::animal::Dog".to_owned(),
+                },
+                name: "visit_dog".to_owned(),
+                parent: Some((animal, "visit_animal".to_owned())),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_visitor_fragment() {
+        assert_eq!(
+            visitor_fragment(&methods()).body(80),
+            indoc! {"
+                /// Visits the concepts distributed by this crate. Each method visits the
+                /// concept as its parent type by default, so implementations only need to
+                /// override the methods for the types that they treat differently.
+                pub trait Visitor {
+                    /// Visit a concept of type `Animal`. Does nothing by default.
+                    fn visit_animal(&mut self, _concept: Animal) {}
+
+                    /// Visit a concept of type `Dog`. By default, this visits it as its parent
+                    /// type `Animal` instead.
+                    fn visit_dog(&mut self, concept: Dog) {
+                        self.visit_animal(Animal::from(concept.id()));
+                    }
+                }
+
+                /// Visit a node with the method for the most specific generated type that it
+                /// descends from. Nodes that don't descend from any of the types distributed by
+                /// this crate are skipped.
+                pub fn walk<V: Visitor + ?Sized>(visitor: &mut V, id: usize) {
+                    match AnyConcept::from_node(id) {
+                        Some(AnyConcept::Animal(c)) => visitor.visit_animal(c),
+                        Some(AnyConcept::Dog(c)) => visitor.visit_dog(c),
+                        None => {}
+                    }
+                }"}
+        );
+    }
+
+    #[test]
+    fn test_visitor_test() {
+        assert_eq!(
+            visitor_test(&methods()).body(80),
+            indoc! {"
+                #[test]
+                fn test_visitor_delegation() {
+                    initialize_kb();
+                    struct Recorder {
+                        visited: Vec<usize>,
+                    }
+
+                    impl Visitor for Recorder {
+                        fn visit_animal(&mut self, concept: Animal) {
+                            self.visited.push(concept.id());
+                        }
+                    }
+
+                    let mut recorder = Recorder { visited: vec![] };
+                    walk(&mut recorder, Dog::TYPE_ID);
+                    assert_eq!(recorder.visited, vec![Dog::TYPE_ID]);
+                }"}
+        );
+    }
+}
@@ -0,0 +1,149 @@
+/// Where a node in the exported graph comes from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DotNodeKind {
+    /// A concept newly defined as part of the current build.
+    NewlyDefined,
+    /// A concept imported from another build.
+    Imported,
+    /// A concept that was already initialized before the current build started.
+    Existing,
+    /// A Rust module that concepts get generated into.
+    Module,
+}
+
+impl Default for DotNodeKind {
+    fn default() -> Self {
+        DotNodeKind::Existing
+    }
+}
+
+/// A single node in the exported graph.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DotNode {
+    /// ID of the node in the knowledge graph.
+    pub id: usize,
+    /// Human-readable name of the node.
+    pub label: String,
+    /// Where the node comes from, which determines how it gets styled.
+    pub kind: DotNodeKind,
+    /// Names of the knowledge graph flags that are set on this node.
+    pub flags: Vec<String>,
+}
+
+/// A single edge in the exported graph.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DotEdge {
+    /// ID of the node the edge starts from.
+    pub from: usize,
+    /// ID of the node the edge ends at.
+    pub to: usize,
+    /// Name of the relation that the edge represents.
+    pub label: String,
+}
+
+/// Configuration values for the Graphviz DOT template.
+#[derive(Clone, Debug, Default)]
+pub struct DotConfig {
+    /// All nodes in the graph.
+    pub nodes: Vec<DotNode>,
+    /// All edges in the graph.
+    pub edges: Vec<DotEdge>,
+}
+
+/// Escape a string for use inside a quoted DOT label.
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// DOT attributes that style a node by its kind.
+fn node_style(kind: DotNodeKind) -> &'static str {
+    match kind {
+        DotNodeKind::NewlyDefined => "style=filled, fillcolor=palegreen",
+        DotNodeKind::Imported => "style=filled, fillcolor=lightblue",
+        DotNodeKind::Existing => "style=dashed",
+        DotNodeKind::Module => "shape=folder",
+    }
+}
+
+/// Generate a Graphviz DOT file for the graph.
+pub fn code_dot(cfg: &DotConfig) -> String {
+    let mut dot = "digraph knowledge_graph {\n    node [shape=box];\n".to_owned();
+    for node in &cfg.nodes {
+        let mut label = escape(&node.label);
+        if !node.flags.is_empty() {
+            label = format!("{}\\n({})", label, escape(&node.flags.join(", ")));
+        }
+        dot += &format!(
+            "    n{} [label=\"{}\", {}];\n",
+            node.id,
+            label,
+            node_style(node.kind)
+        );
+    }
+    for edge in &cfg.edges {
+        dot += &format!(
+            "    n{} -> n{} [label=\"{}\"];\n",
+            edge.from,
+            edge.to,
+            escape(&edge.label)
+        );
+    }
+    dot += "}\n";
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_code_dot() {
+        assert_eq!(
+            code_dot(&DotConfig {
+                nodes: vec![
+                    DotNode {
+                        id: 2,
+                        label: "form".to_owned(),
+                        kind: DotNodeKind::Existing,
+                        flags: vec![],
+                    },
+                    DotNode {
+                        id: 40,
+                        label: "my-\"attr\"".to_owned(),
+                        kind: DotNodeKind::NewlyDefined,
+                        flags: vec!["newly-defined".to_owned(), "attribute-analogue".to_owned()],
+                    },
+                    DotNode {
+                        id: 41,
+                        label: "mod my_attr".to_owned(),
+                        kind: DotNodeKind::Module,
+                        flags: vec![],
+                    },
+                ],
+                edges: vec![
+                    DotEdge {
+                        from: 40,
+                        to: 2,
+                        label: "inherits".to_owned(),
+                    },
+                    DotEdge {
+                        from: 41,
+                        to: 40,
+                        label: "most-prominent-member".to_owned(),
+                    },
+                ],
+            }),
+            indoc! {r#"
+                digraph knowledge_graph {
+                    node [shape=box];
+                    n2 [label="form", style=dashed];
+                    n40 [label="my-\"attr\"\n(newly-defined, attribute-analogue)", style=filled, fillcolor=palegreen];
+                    n41 [label="mod my_attr", shape=folder];
+                    n40 -> n2 [label="inherits"];
+                    n41 -> n40 [label="most-prominent-member"];
+                }
+            "#}
+        );
+    }
+}
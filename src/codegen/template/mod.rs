@@ -2,6 +2,8 @@
 pub mod basic;
 /// Templates for concepts.
 pub mod concept;
+/// Graphviz DOT export of the knowledge graph.
+pub mod dot;
 /// Import-related code generation.
 pub mod imports;
 /// Main file code generation.
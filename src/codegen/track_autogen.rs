@@ -1,37 +1,257 @@
-use path_abs::PathAbs;
-use std::cell::RefCell;
+use crate::codegen::filesystem::{OutputStatus, PlannedFile};
+use crate::codegen::postprocessing::mark_autogen::AUTOGENERATION_MARKER;
+use crate::codegen::postprocessing::user_regions::has_user_code;
+use crate::codegen::session::with_default_session;
+use crate::codegen::CodegenError;
+use path_abs::{PathAbs, PathInfo, PathOps};
 use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
 use std::fs::{File, OpenOptions};
 use std::io::{BufRead, BufReader, Result, Write};
-
-thread_local! {
-    static AUTOGEN_FILES: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
-}
+use std::path::Path;
 
 /// The path to the file that tracks autogenerated files.
 pub const AUTOGEN_TRACKER: &str = ".autogen.txt";
 
-/// Track a file as being autogenerated.
+/// What happened to the files left over from a previous run when they got cleaned up.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CleanedFiles {
+    /// Files that were deleted.
+    pub removed: Vec<String>,
+    /// Files that would have been deleted, but were kept because they have hand-written code in
+    /// them. They are no longer ignored by git, so that the hand-written code can be committed.
+    pub kept: Vec<String>,
+}
+
+/// Everything that was output during a single code generation session.
+#[derive(Clone, Debug, Default)]
+pub struct AutogenTracker {
+    files: HashSet<String>,
+    statuses: Vec<OutputStatus>,
+}
+
+impl AutogenTracker {
+    /// Track a file as being autogenerated.
+    pub fn track(&mut self, filename: String) {
+        self.files.insert(filename);
+    }
+
+    /// Track what happened to a generated file when it was output.
+    pub fn track_status(&mut self, status: OutputStatus) {
+        self.statuses.push(status);
+    }
+
+    /// All files tracked so far.
+    pub fn files(&self) -> &HashSet<String> {
+        &self.files
+    }
+
+    /// Track a planned file that has been output, along with the `.gitignore` it was added to.
+    /// Files that aren't generated code only count towards the output summary.
+    pub fn record(&mut self, planned: &PlannedFile) -> std::result::Result<(), CodegenError> {
+        if !planned.autogen {
+            self.track_status(planned.status);
+            return Ok(());
+        }
+        let file_pathabs = PathAbs::new(Path::new(&planned.path))
+            .map_err(|e| CodegenError::io(&planned.path, e.into()))?;
+        self.track(file_pathabs.as_path().to_str().unwrap().to_owned());
+        self.track_status(planned.status);
+        if let Some(gitignore) = &planned.git_ignore {
+            self.track(gitignore.clone());
+        }
+        Ok(())
+    }
+
+    /// Output all autogenerated files to the tracker file, after cleaning up any files that were
+    /// generated in a previous run but not in this one.
+    pub fn save(&self) -> std::result::Result<(), CodegenError> {
+        let tracker_error = |e: std::io::Error| CodegenError::io(AUTOGEN_TRACKER, e);
+        let tracker_path = PathAbs::new(AUTOGEN_TRACKER).map_err(|e| tracker_error(e.into()))?;
+        let previous_files = read_lines(&tracker_path);
+        let cleaned = clean_stale_files(&previous_files, &self.files, false)?;
+        for removed in cleaned.removed {
+            println!("Removed stale autogenerated file {}", removed);
+        }
+        for kept in cleaned.kept {
+            println!(
+                "Kept stale autogenerated file {} because it has hand-written code",
+                kept
+            );
+        }
+        let mut tracked = self.files.iter().cloned().collect::<Vec<String>>();
+        tracked.sort();
+        write_lines(&tracker_path, &tracked).map_err(tracker_error)?;
+        // add AUTOGEN_TRACKER to project-level .gitignore
+        let ignore_error = |e: std::io::Error| CodegenError::io(".gitignore", e);
+        let top_level_ignore = PathAbs::new(".gitignore").map_err(|e| ignore_error(e.into()))?;
+        add_to_file(&top_level_ignore, AUTOGEN_TRACKER).map_err(ignore_error)?;
+        println!("{}", output_summary(&self.statuses));
+        Ok(())
+    }
+}
+
+/// Track a file as being autogenerated as part of the default session.
 pub fn track_autogen(filename: String) {
-    AUTOGEN_FILES.with(|f| f.borrow_mut().insert(filename));
+    with_default_session(|s| s.autogen_mut().track(filename));
+}
+
+/// Track what happened to a generated file when it was output as part of the default session.
+pub fn track_output_status(status: OutputStatus) {
+    with_default_session(|s| s.autogen_mut().track_status(status));
+}
+
+/// Summarize what happened to all the files that were output.
+fn output_summary(statuses: &[OutputStatus]) -> String {
+    let count = |status: OutputStatus| statuses.iter().filter(|s| **s == status).count();
+    format!(
+        "Generated {} files in total: {} created, {} updated, {} unchanged.",
+        statuses.len(),
+        count(OutputStatus::Created),
+        count(OutputStatus::Updated),
+        count(OutputStatus::Unchanged)
+    )
+}
+
+/// Save everything tracked by the default session. See `CodegenSession::save_autogen`.
+pub fn save_autogen() -> std::result::Result<(), CodegenError> {
+    with_default_session(|s| s.save_autogen())
+}
+
+/// Every file that the tracker file lists as generated by a previous run.
+pub fn tracked_files() -> std::result::Result<Vec<String>, CodegenError> {
+    let tracker_path =
+        PathAbs::new(AUTOGEN_TRACKER).map_err(|e| CodegenError::io(AUTOGEN_TRACKER, e.into()))?;
+    Ok(read_lines(&tracker_path))
+}
+
+/// Delete every file listed in the tracker file, along with the tracker file itself. Returns the
+/// files that were deleted, and the ones that were kept for having hand-written code in them.
+///
+/// As with stale files, tracked files that no longer carry the autogeneration marker are assumed
+/// to now be maintained by hand, and are left alone. Release builds don't mark the files they
+/// generate, so use `clean_release_autogen` to clean up after those instead.
+pub fn clean_autogen() -> std::result::Result<CleanedFiles, CodegenError> {
+    clean_tracked(AUTOGEN_TRACKER, false)
+}
+
+/// Delete every file listed in the tracker file, whether or not it carries the autogeneration
+/// marker, along with the tracker file itself. Returns the files that were deleted, and the ones
+/// that were kept for having hand-written code in them.
+///
+/// This is meant for files generated in release mode, which never get marked. Any hand-written
+/// changes outside of user code regions are lost.
+pub fn clean_release_autogen() -> std::result::Result<CleanedFiles, CodegenError> {
+    clean_tracked(AUTOGEN_TRACKER, true)
 }
 
-/// Output all autogenerated files to the tracker file.
-pub fn save_autogen() {
-    let tracker_path = PathAbs::new(AUTOGEN_TRACKER).expect("Cannot open autogen tracker");
-    AUTOGEN_FILES.with(|f| {
-        for filename in f.borrow().iter() {
-            // todo: cut down on the number of file reads here
-            add_to_file(&tracker_path, filename).expect("Cannot add to autogen tracker");
+/// Delete every file listed in the given tracker file, and then the tracker file itself. Unmarked
+/// files only get deleted if `include_unmarked` is set.
+fn clean_tracked(
+    tracker: &str,
+    include_unmarked: bool,
+) -> std::result::Result<CleanedFiles, CodegenError> {
+    let tracker_error = |e: std::io::Error| CodegenError::io(tracker, e);
+    let tracker_path = PathAbs::new(tracker).map_err(|e| tracker_error(e.into()))?;
+    let cleaned = clean_stale_files(
+        &read_lines(&tracker_path),
+        &HashSet::new(),
+        include_unmarked,
+    )?;
+    if tracker_path.as_path().exists() {
+        fs::remove_file(tracker_path.as_path()).map_err(tracker_error)?;
+    }
+    Ok(cleaned)
+}
+
+/// Delete files that were previously autogenerated, but that are no longer being generated.
+///
+/// Unless `include_unmarked` is set, files that no longer carry the autogeneration marker are
+/// assumed to now be maintained by hand, and are left alone. Files with hand-written code in their
+/// user code regions are kept as well, but are no longer ignored by git. `.gitignore` files are
+/// only ever emptied out as the files they ignore get deleted.
+fn clean_stale_files(
+    previous_files: &[String],
+    current_files: &HashSet<String>,
+    include_unmarked: bool,
+) -> std::result::Result<CleanedFiles, CodegenError> {
+    let mut cleaned = CleanedFiles::default();
+    for stale in previous_files
+        .iter()
+        .filter(|p| !current_files.contains(*p))
+    {
+        if Path::new(stale).file_name() == Some(OsStr::new(".gitignore")) {
+            continue;
+        }
+        let content = match fs::read_to_string(stale) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        if !include_unmarked && !content.contains(AUTOGENERATION_MARKER) {
+            continue;
+        }
+        if has_user_code(&content) {
+            unignore(stale).map_err(|e| CodegenError::io(stale, e))?;
+            cleaned.kept.push(stale.clone());
+            continue;
         }
-    });
-    // add AUTOGEN_TRACKER to project-level .gitignore
-    add_to_file(&PathAbs::new(".gitignore").unwrap(), AUTOGEN_TRACKER)
-        .expect("Cannot ignore autogen tracker in top-level .gitignore");
-    println!(
-        "Generated {} files in total.",
-        AUTOGEN_FILES.with(|f| f.borrow().len())
-    );
+        fs::remove_file(stale).map_err(|e| CodegenError::io(stale, e))?;
+        unignore(stale).map_err(|e| CodegenError::io(stale, e))?;
+        cleaned.removed.push(stale.clone());
+    }
+    Ok(cleaned)
+}
+
+/// Remove a file from the `.gitignore` in the same directory. If nothing else is left to ignore
+/// there, the `.gitignore` itself gets deleted as well.
+pub fn unignore(file: &str) -> Result<()> {
+    let file_pathabs = PathAbs::new(Path::new(file))?;
+    let gitignore = file_pathabs.with_file_name(".gitignore");
+    if !gitignore.as_path().exists() {
+        return Ok(());
+    }
+    let filename = file_pathabs.file_name().unwrap().to_str().unwrap();
+    let content = fs::read_to_string(gitignore.as_path())?;
+    let is_filename = |line: &str| line.trim_end_matches(&['\r', '\n'][..]) == filename;
+    if !content.split_inclusive('\n').any(is_filename) {
+        // leave hand-maintained ignore files exactly as they are
+        return Ok(());
+    }
+    // keep everything else byte for byte, including blank lines used for grouping
+    let remaining = content
+        .split_inclusive('\n')
+        .filter(|l| !is_filename(l))
+        .collect::<String>();
+    if remaining
+        .lines()
+        .all(|l| l.trim().is_empty() || l == ".gitignore")
+    {
+        fs::remove_file(gitignore.as_path())
+    } else {
+        fs::write(gitignore.as_path(), remaining)
+    }
+}
+
+/// Read all non-empty lines in a file. A file that doesn't exist has no lines.
+fn read_lines(file: &PathAbs) -> Vec<String> {
+    match File::open(file.as_path()) {
+        Ok(existing_file) => BufReader::new(existing_file)
+            .lines()
+            .filter_map(|l| l.ok())
+            .filter(|l| !l.is_empty())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Overwrite the file so that it contains exactly the given lines.
+fn write_lines(file: &PathAbs, lines: &[String]) -> Result<()> {
+    let mut new_file = File::create(file.as_path())?;
+    for line in lines {
+        writeln!(new_file, "{}", line)?;
+    }
+    Ok(())
 }
 
 /// Ensures that the given line will be in the file.
@@ -58,3 +278,165 @@ pub fn add_to_file(file: &PathAbs, content: &str) -> Result<()> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::scratch::{scratch_dir, scratch_file};
+
+    #[test]
+    fn test_output_summary() {
+        assert_eq!(
+            output_summary(&[
+                OutputStatus::Created,
+                OutputStatus::Unchanged,
+                OutputStatus::Unchanged
+            ]),
+            "Generated 3 files in total: 1 created, 0 updated, 2 unchanged."
+        );
+    }
+
+    #[test]
+    fn test_clean_stale_files() {
+        let dir = scratch_dir("stale");
+        let marked = format!("pub struct Old; {}\n", AUTOGENERATION_MARKER);
+        let stale = scratch_file(&dir, "old_form.rs", &marked);
+        let kept = scratch_file(&dir, "new_form.rs", &marked);
+        let gitignore = scratch_file(&dir, ".gitignore", ".gitignore\nold_form.rs\nnew_form.rs\n");
+
+        let mut current = HashSet::new();
+        current.insert(kept.clone());
+        current.insert(gitignore.clone());
+        let cleaned = clean_stale_files(
+            &[stale.clone(), kept.clone(), gitignore.clone()],
+            &current,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(cleaned.removed, vec![stale.clone()]);
+        assert!(!Path::new(&stale).exists());
+        assert!(Path::new(&kept).exists());
+        assert_eq!(
+            fs::read_to_string(&gitignore).unwrap(),
+            ".gitignore\nnew_form.rs\n"
+        );
+        fs::remove_dir_all(dir.as_path()).unwrap();
+    }
+
+    #[test]
+    fn test_clean_stale_files_hand_edited() {
+        let dir = scratch_dir("hand-edited");
+        let edited = scratch_file(&dir, "old_form.rs", "pub struct Old;\n");
+        let cleaned = clean_stale_files(&[edited.clone()], &HashSet::new(), false).unwrap();
+        assert_eq!(cleaned, CleanedFiles::default());
+        assert!(Path::new(&edited).exists());
+        fs::remove_dir_all(dir.as_path()).unwrap();
+    }
+
+    #[test]
+    fn test_clean_stale_files_with_user_code() {
+        let dir = scratch_dir("stale-user-code");
+        let filled = format!(
+            "pub struct Old; {}\n\
+                // BEGIN USER CODE: items\n\
+                impl Old {{}}\n\
+                // END USER CODE: items\n",
+            AUTOGENERATION_MARKER
+        );
+        let stale = scratch_file(&dir, "old_form.rs", &filled);
+        let gitignore = scratch_file(&dir, ".gitignore", ".gitignore\nold_form.rs\n/target\n");
+
+        let cleaned = clean_stale_files(&[stale.clone()], &HashSet::new(), false).unwrap();
+        assert_eq!(
+            cleaned,
+            CleanedFiles {
+                removed: Vec::new(),
+                kept: vec![stale.clone()],
+            }
+        );
+        assert_eq!(fs::read_to_string(&stale).unwrap(), filled);
+        assert_eq!(
+            fs::read_to_string(&gitignore).unwrap(),
+            ".gitignore\n/target\n"
+        );
+        fs::remove_dir_all(dir.as_path()).unwrap();
+    }
+
+    #[test]
+    fn test_clean_tracked() {
+        let dir = scratch_dir("clean");
+        let marked = format!("pub struct Old; {}\n", AUTOGENERATION_MARKER);
+        let generated = scratch_file(&dir, "old_form.rs", &marked);
+        let edited = scratch_file(&dir, "edited_form.rs", "pub struct Edited;\n");
+        let tracker = scratch_file(&dir, "autogen.txt", &format!("{}\n{}\n", generated, edited));
+
+        assert_eq!(
+            clean_tracked(&tracker, false).unwrap().removed,
+            vec![generated.clone()]
+        );
+        assert!(!Path::new(&generated).exists());
+        assert!(Path::new(&edited).exists());
+        assert!(!Path::new(&tracker).exists());
+        fs::remove_dir_all(dir.as_path()).unwrap();
+    }
+
+    #[test]
+    fn test_clean_tracked_release() {
+        let dir = scratch_dir("clean-release");
+        let released = scratch_file(&dir, "released_form.rs", "pub struct Released;\n");
+        let gitignore = scratch_file(&dir, ".gitignore", ".gitignore\n/target\n");
+        let tracker = scratch_file(
+            &dir,
+            "autogen.txt",
+            &format!("{}\n{}\n", released, gitignore),
+        );
+
+        assert_eq!(
+            clean_tracked(&tracker, true).unwrap().removed,
+            vec![released.clone()]
+        );
+        assert!(!Path::new(&released).exists());
+        assert!(Path::new(&gitignore).exists());
+        fs::remove_dir_all(dir.as_path()).unwrap();
+    }
+
+    #[test]
+    fn test_clean_stale_files_removes_empty_gitignore() {
+        let dir = scratch_dir("empty-ignore");
+        let marked = format!("pub struct Old; {}\n", AUTOGENERATION_MARKER);
+        let stale = scratch_file(&dir, "old_form.rs", &marked);
+        let gitignore = scratch_file(&dir, ".gitignore", ".gitignore\nold_form.rs\n");
+        clean_stale_files(&[stale, gitignore.clone()], &HashSet::new(), false).unwrap();
+        assert!(!Path::new(&gitignore).exists());
+        fs::remove_dir_all(dir.as_path()).unwrap();
+    }
+
+    #[test]
+    fn test_unignore_keeps_blank_lines() {
+        let dir = scratch_dir("blank-ignore");
+        let stale = scratch_file(&dir, "old_form.rs", "pub struct Old;\n");
+        let gitignore = scratch_file(
+            &dir,
+            ".gitignore",
+            "# generated\n.gitignore\nold_form.rs\n\n# build output\n/target\n\n",
+        );
+        unignore(&stale).unwrap();
+        assert_eq!(
+            fs::read_to_string(&gitignore).unwrap(),
+            "# generated\n.gitignore\n\n# build output\n/target\n\n"
+        );
+        fs::remove_dir_all(dir.as_path()).unwrap();
+    }
+
+    #[test]
+    fn test_unignore_leaves_unrelated_gitignore() {
+        let dir = scratch_dir("unrelated-ignore");
+        let kept = scratch_file(&dir, "kept_form.rs", "pub struct Kept;\n");
+        let content = "# hand-written\n\n/target\n";
+        let gitignore = scratch_file(&dir, ".gitignore", content);
+        unignore(&kept).unwrap();
+        assert_eq!(fs::read_to_string(&gitignore).unwrap(), content);
+        fs::remove_dir_all(dir.as_path()).unwrap();
+    }
+}
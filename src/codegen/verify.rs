@@ -0,0 +1,253 @@
+use crate::codegen::filesystem::CodegenPlan;
+use crate::codegen::manifest::{BuildManifest, BUILD_MANIFEST};
+use crate::codegen::postprocessing::mark_autogen::AUTOGENERATION_MARKER;
+use crate::codegen::CodegenError;
+use path_abs::PathAbs;
+use similar::TextDiff;
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// How a file on disk differs from what code generation would output.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DriftKind {
+    /// The file would be generated, but doesn't exist.
+    Missing,
+    /// The file exists, but with different contents. This covers both generated files that were
+    /// never regenerated after the concepts changed, and generated lines that were edited by hand.
+    OutOfDate,
+    /// The file was generated by a previous run, but nothing generates it anymore.
+    NoLongerGenerated,
+}
+
+/// A single file that has drifted from what code generation would output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Drift {
+    /// The drifted file.
+    pub path: String,
+    /// How the file has drifted.
+    pub kind: DriftKind,
+    /// Unified diff from the file on disk to what would be generated.
+    pub diff: String,
+}
+
+/// Every generated file that has drifted from what code generation would output.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DriftReport {
+    /// The drifted files, in the order they were found.
+    pub drifted: Vec<Drift>,
+}
+
+impl DriftReport {
+    /// Whether or not every generated file is exactly what code generation would output.
+    pub fn is_clean(&self) -> bool {
+        self.drifted.is_empty()
+    }
+}
+
+impl Display for DriftReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.is_clean() {
+            return writeln!(f, "All generated files are up to date.");
+        }
+        writeln!(f, "{} generated files have drifted:", self.drifted.len())?;
+        for drift in &self.drifted {
+            let description = match drift.kind {
+                DriftKind::Missing => "missing",
+                DriftKind::OutOfDate => "out of date",
+                DriftKind::NoLongerGenerated => "no longer generated",
+            };
+            writeln!(f, "  [{}] {}", description, drift.path)?;
+        }
+        for drift in &self.drifted {
+            write!(f, "\n{}", drift.diff)?;
+        }
+        Ok(())
+    }
+}
+
+/// Unified diff from the existing contents of a file to its generated contents.
+pub fn unified_diff(path: &str, existing: &str, generated: &str) -> String {
+    TextDiff::from_lines(existing, generated)
+        .unified_diff()
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string()
+}
+
+/// Get the absolute version of a path, so that relative and absolute paths can be compared.
+fn absolute(path: &str) -> Result<String, CodegenError> {
+    let path_abs = PathAbs::new(Path::new(path)).map_err(|e| CodegenError::io(path, e.into()))?;
+    Ok(path_abs.as_path().to_str().unwrap().to_owned())
+}
+
+/// Read a file, treating a file that doesn't exist as having no contents at all.
+fn read_existing(path: &str) -> Result<Option<String>, CodegenError> {
+    match fs::read_to_string(path) {
+        Ok(existing) => Ok(Some(existing)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(CodegenError::io(path, e)),
+    }
+}
+
+/// Every file that the build manifest on disk lists as generated by a previous run. A missing
+/// manifest lists nothing.
+pub fn manifest_files() -> Result<Vec<String>, CodegenError> {
+    match read_existing(BUILD_MANIFEST)? {
+        Some(content) => Ok(BuildManifest::from_toml(&content)?.generated_files),
+        None => Ok(vec![]),
+    }
+}
+
+/// Compare every planned file, and every file that a previous run generated, against what is
+/// actually on disk.
+///
+/// Previously generated files come from two places. Files in `tracked` come from the autogen
+/// tracker, which is never committed, and are only reported if they still carry the
+/// autogeneration marker, so that `.gitignore` files and files that are now maintained by hand are
+/// left alone. Files in `manifested` come from the build manifest, which does get committed in
+/// release mode, and are reported regardless of markers because release builds don't mark
+/// anything.
+pub fn find_drift(
+    plan: &CodegenPlan,
+    tracked: &[String],
+    manifested: &[String],
+) -> Result<DriftReport, CodegenError> {
+    let mut report = DriftReport::default();
+    let mut planned = HashSet::new();
+    for file in &plan.files {
+        planned.insert(absolute(&file.path)?);
+        match read_existing(&file.path)? {
+            Some(existing) if existing == file.code => (),
+            Some(existing) => report.drifted.push(Drift {
+                path: file.path.clone(),
+                kind: DriftKind::OutOfDate,
+                diff: unified_diff(&file.path, &existing, &file.code),
+            }),
+            None => report.drifted.push(Drift {
+                path: file.path.clone(),
+                kind: DriftKind::Missing,
+                diff: unified_diff(&file.path, "", &file.code),
+            }),
+        }
+    }
+
+    let previous = tracked
+        .iter()
+        .map(|path| (path, true))
+        .chain(manifested.iter().map(|path| (path, false)));
+    for (path, needs_marker) in previous {
+        let path_abs = absolute(path)?;
+        if planned.contains(&path_abs) {
+            continue;
+        }
+        if let Some(existing) = read_existing(path)? {
+            if !needs_marker || existing.contains(AUTOGENERATION_MARKER) {
+                // don't report files listed by both the tracker and the manifest twice
+                planned.insert(path_abs);
+                report.drifted.push(Drift {
+                    path: path.clone(),
+                    kind: DriftKind::NoLongerGenerated,
+                    diff: unified_diff(path, &existing, ""),
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::filesystem::{OutputStatus, PlannedFile};
+    use crate::codegen::scratch::{scratch_dir, scratch_file};
+    use path_abs::PathInfo;
+
+    fn planned(path: String, code: &str) -> PlannedFile {
+        PlannedFile {
+            path,
+            code: code.to_owned(),
+            git_ignore: None,
+            cargo_track: false,
+            autogen: true,
+            status: OutputStatus::Unchanged,
+        }
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(
+            unified_diff("src/a.rs", "one\ntwo\n", "one\nthree\n"),
+            "--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1,2 +1,2 @@\n one\n-two\n+three\n"
+        );
+    }
+
+    #[test]
+    fn test_find_drift() {
+        let dir = scratch_dir("drift");
+        let marked = format!("pub struct A; {}\n", AUTOGENERATION_MARKER);
+        let current = scratch_file(&dir, "current_form.rs", &marked);
+        let edited = scratch_file(&dir, "edited_form.rs", "pub struct B; // edited by hand\n");
+        let missing = format!("{}/missing_form.rs", dir.as_path().to_str().unwrap());
+        let removed = scratch_file(&dir, "removed_form.rs", &marked);
+        let gitignore = scratch_file(&dir, ".gitignore", ".gitignore\n");
+
+        let mut plan = CodegenPlan::default();
+        plan.add(planned(current.clone(), &marked));
+        plan.add(planned(edited.clone(), &marked));
+        plan.add(planned(missing.clone(), &marked));
+        let tracked = vec![current, edited.clone(), removed.clone(), gitignore];
+        let report = find_drift(&plan, &tracked, &[]).unwrap();
+
+        assert_eq!(
+            report
+                .drifted
+                .iter()
+                .map(|d| (d.path.as_str(), d.kind))
+                .collect::<Vec<(&str, DriftKind)>>(),
+            vec![
+                (edited.as_str(), DriftKind::OutOfDate),
+                (missing.as_str(), DriftKind::Missing),
+                (removed.as_str(), DriftKind::NoLongerGenerated),
+            ]
+        );
+        assert!(report.drifted[0]
+            .diff
+            .contains("-pub struct B; // edited by hand"));
+        assert!(!report.is_clean());
+        fs::remove_dir_all(dir.as_path()).unwrap();
+    }
+
+    #[test]
+    fn test_find_drift_release() {
+        let dir = scratch_dir("drift-release");
+        let unmarked = "pub struct A;\n";
+        let kept = scratch_file(&dir, "kept_form.rs", unmarked);
+        let removed = scratch_file(&dir, "removed_form.rs", unmarked);
+
+        let mut plan = CodegenPlan::default();
+        plan.add(planned(kept.clone(), unmarked));
+        let previous = vec![kept, removed.clone()];
+        assert!(find_drift(&plan, &previous, &[]).unwrap().is_clean());
+        let report = find_drift(&plan, &previous, &previous).unwrap();
+        assert_eq!(
+            report
+                .drifted
+                .iter()
+                .map(|d| (d.path.as_str(), d.kind))
+                .collect::<Vec<(&str, DriftKind)>>(),
+            vec![(removed.as_str(), DriftKind::NoLongerGenerated)]
+        );
+        fs::remove_dir_all(dir.as_path()).unwrap();
+    }
+
+    #[test]
+    fn test_clean_report() {
+        assert_eq!(
+            DriftReport::default().to_string(),
+            "All generated files are up to date.\n"
+        );
+    }
+}
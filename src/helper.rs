@@ -1,39 +1,35 @@
+use crate::codegen::manifest::BuildManifest;
+use crate::codegen::planning::load_manifest;
+use crate::codegen::session::with_default_session;
+use crate::codegen::spec::Spec;
+use crate::codegen::CodegenError;
 use crate::tao::archetype::rust_item_archetype::DataArchetype;
-use crate::tao::callbacks::implements;
+use crate::tao::form::rust_item::EnumVariant;
 use crate::tao::perspective::KnowledgeGraphNode;
-use std::cell::Cell;
+use std::fs;
 use zamm_yin::node_wrappers::CommonNodeTrait;
 use zamm_yin::tao::archetype::*;
-use zamm_yin::tao::form::FormTrait;
+use zamm_yin::tao::form::{Form, FormTrait};
 use zamm_yin::tao::relation::attribute::Attribute;
 
-thread_local! {
-    static IMPORT_IN_PROGRESS: Cell<bool> = Cell::new(false);
-}
-
-/// Returns whether or not we're currently in the process of importing another file.
+/// Returns whether or not the default session is currently in the process of importing another
+/// file.
 pub fn import_in_progress() -> bool {
-    IMPORT_IN_PROGRESS.with(|i| i.get())
+    with_default_session(|s| s.import_in_progress())
 }
 
-fn mark_import(status: bool) {
-    IMPORT_IN_PROGRESS.with(|i| i.set(status));
+/// Defines a new concept with the given name directly under Tao, in the default session. See
+/// `CodegenSession::define_concept`.
+pub fn define_concept(name: &str) -> Archetype {
+    with_default_session(|s| s.define_concept(name))
 }
 
 /// Defines a new concept with the given name.
 #[macro_export]
 macro_rules! define {
     ($name:ident) => {
-        let mut $name = Tao::archetype().individuate_as_archetype();
-        $name.set_internal_name(stringify!($name));
-        {
-            let mut kgn = zamm_yang::tao::perspective::KnowledgeGraphNode::from($name.id());
-            if zamm_yang::helper::import_in_progress() {
-                kgn.mark_imported();
-            } else {
-                kgn.mark_newly_defined();
-            }
-        }
+        #[allow(unused_mut)]
+        let mut $name = zamm_yang::helper::define_concept(stringify!($name));
     };
     ($name:ident, $doc:expr) => {
         define!($name);
@@ -152,19 +148,60 @@ pub fn da(archetype: Archetype) -> DataArchetype {
     DataArchetype::from(archetype.id())
 }
 
-/// Start interpreting new information as imported. New concepts will not be marked as new, but
-/// instead as imported.
+/// Add a documented variant to an enumeration data concept.
+///
+/// Data with variants doesn't need a Rust primitive or a default value. Instead, an enum named
+/// after the concept gets generated right next to it, with one variant for each one added here in
+/// order. The first variant serves as the default, and attributes that take the concept as their
+/// value get getters and setters that use the enum.
+pub fn add_variant(data: Archetype, name: &str, doc: &str) -> EnumVariant {
+    let mut variant = EnumVariant::new();
+    variant.set_internal_name(name);
+    variant.set_variant_documentation(doc);
+    da(data).add_variant(&variant);
+    variant
+}
+
+/// Defines a new named individual of the given archetype. The individual gets initialized along
+/// with the generated concepts, and can be retrieved through an accessor on its archetype.
+///
+/// The individual is marked as imported if the default session is importing. See
+/// `CodegenSession::define_individual` for other sessions.
+pub fn define_individual(archetype: Archetype, name: &str, doc: &str) -> Form {
+    with_default_session(|s| s.define_individual(archetype, name, doc))
+}
+
+/// Start interpreting new information as imported in the default session. See
+/// `CodegenSession::start_imports`.
 pub fn start_imports() {
-    mark_import(true);
+    with_default_session(|s| s.start_imports());
 }
 
-/// Mark all concepts introduced thus far as imported ones. Imports are informational-only and will
-/// not be acted on.
+/// Mark all concepts introduced thus far as imported ones in the default session. See
+/// `CodegenSession::end_imports`.
 pub fn end_imports() {
-    mark_import(false);
-    for i in implements() {
-        KnowledgeGraphNode::from(i.id()).mark_imported();
-    }
+    with_default_session(|s| s.end_imports());
+}
+
+/// Import the build manifest that another crate saved during its own code generation, so that its
+/// concepts and modules don't have to be defined all over again.
+pub fn import_manifest(path: &str) -> Result<(), CodegenError> {
+    let content = fs::read_to_string(path).map_err(|e| CodegenError::io(path, e))?;
+    let manifest = BuildManifest::from_toml(&content)?;
+    start_imports();
+    let loaded = load_manifest(&manifest);
+    end_imports();
+    loaded
+}
+
+/// Define the concepts, attributes, flags, and modules listed in a TOML or JSON specification
+/// file, just as the equivalent macro calls would. See `Spec::read` for how the file gets read.
+///
+/// Everything gets marked as imported if the default session is importing. See
+/// `CodegenSession::load_spec` for other sessions.
+pub fn load_spec_file(path: &str) -> Result<(), CodegenError> {
+    let spec = Spec::read(path)?;
+    with_default_session(|s| s.load_spec(&spec))
 }
 
 /// Backwards compatibility trait to handle API changes for this yang-0.x.* branch.
@@ -0,0 +1,200 @@
+//! Command-line interface for generating code out of concept specifications, without having to
+//! write a build script.
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use colored::Colorize;
+use std::process;
+use zamm_yang::codegen::track_autogen::{clean_autogen, clean_release_autogen};
+use zamm_yang::codegen::{CodegenConfig, CodegenError};
+use zamm_yang::helper::load_spec_file;
+use zamm_yang::tao::callbacks::{explain_concept, handle_all_implementations, verify_generated};
+use zamm_yang::tao::form::rust_item::{Crate, CrateExtension};
+use zamm_yang::tao::initialize_kb;
+
+/// Spec that gets loaded when none is given on the command line.
+const DEFAULT_SPEC: &str = "yin.md";
+
+/// Arguments shared by every subcommand that needs to load concepts.
+fn spec_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("SPEC")
+            .help("TOML, JSON, or Markdown file to load concepts from")
+            .default_value(DEFAULT_SPEC),
+        Arg::with_name("crate")
+            .long("crate")
+            .takes_value(true)
+            .help("Name of the crate that code is being generated for"),
+    ]
+}
+
+/// Arguments that map onto fields of `CodegenConfig`.
+fn config_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("yin")
+            .long("yin")
+            .help("Generate code for Yin itself"),
+        Arg::with_name("release")
+            .long("release")
+            .help("Generate code for release, to be committed instead of ignored"),
+        Arg::with_name("no-comment-autogen")
+            .long("no-comment-autogen")
+            .help("Don't mark generated lines with autogeneration comments"),
+        Arg::with_name("no-rustfmt-attributes")
+            .long("no-rustfmt-attributes")
+            .help("Don't add rustfmt attributes to generated code"),
+        Arg::with_name("track-autogen")
+            .long("track-autogen")
+            .help("Have Cargo rebuild when generated files change"),
+        Arg::with_name("accessor-traits")
+            .long("accessor-traits")
+            .help("Put attribute and flag accessors into traits shared by descendants"),
+        Arg::with_name("tao-root")
+            .long("tao-root")
+            .help("Also generate the root tao module and its initialize_kb function"),
+        Arg::with_name("builders")
+            .long("builders")
+            .help("Generate a builder for each archetype with attributes"),
+    ]
+}
+
+/// Code generation options as specified on the command line.
+fn codegen_config(args: &ArgMatches) -> CodegenConfig {
+    CodegenConfig {
+        comment_autogen: !args.is_present("no-comment-autogen"),
+        add_rustfmt_attributes: !args.is_present("no-rustfmt-attributes"),
+        track_autogen: args.is_present("track-autogen"),
+        yin: args.is_present("yin"),
+        release: args.is_present("release"),
+        dry_run: false,
+        accessor_traits: args.is_present("accessor-traits"),
+        tao_root: args.is_present("tao-root"),
+        builders: args.is_present("builders"),
+    }
+}
+
+/// Initialize the KB, and then load the spec into it.
+fn load(args: &ArgMatches) -> Result<(), CodegenError> {
+    initialize_kb();
+    if let Some(crate_name) = args.value_of("crate") {
+        Crate::current().set_implementation_name(crate_name);
+    }
+    load_spec_file(args.value_of("SPEC").unwrap())
+}
+
+/// Generate code for everything in the spec.
+fn generate(args: &ArgMatches) -> Result<(), CodegenError> {
+    load(args)?;
+    handle_all_implementations(&codegen_config(args))?;
+    Ok(())
+}
+
+/// Make sure that code can be generated for everything in the spec, without touching the
+/// filesystem.
+fn check(args: &ArgMatches) -> Result<(), CodegenError> {
+    load(args)?;
+    let codegen_cfg = CodegenConfig {
+        dry_run: true,
+        ..codegen_config(args)
+    };
+    print!("{}", handle_all_implementations(&codegen_cfg)?);
+    Ok(())
+}
+
+/// Make sure that every generated file on disk is exactly what would be generated now. Returns
+/// whether or not that is the case.
+fn verify(args: &ArgMatches) -> Result<bool, CodegenError> {
+    load(args)?;
+    let report = verify_generated(&codegen_config(args))?;
+    print!("{}", report);
+    Ok(report.is_clean())
+}
+
+/// Remove every file generated by a previous run, except for those with hand-written code in them.
+/// Files generated for release carry no autogeneration marker, so they only get removed along with
+/// everything else when `--release` is passed.
+fn clean(args: &ArgMatches) -> Result<(), CodegenError> {
+    let cleaned = if args.is_present("release") {
+        clean_release_autogen()?
+    } else {
+        clean_autogen()?
+    };
+    for removed in cleaned.removed {
+        println!("Removed autogenerated file {}", removed);
+    }
+    for kept in cleaned.kept {
+        println!(
+            "Kept autogenerated file {} because it has hand-written code",
+            kept
+        );
+    }
+    Ok(())
+}
+
+/// Describe where and how a concept in the spec will be generated.
+fn explain(args: &ArgMatches) -> Result<(), CodegenError> {
+    load(args)?;
+    let concept = args.value_of("CONCEPT").unwrap();
+    print!("{}", explain_concept(concept, &codegen_config(args))?);
+    Ok(())
+}
+
+fn main() {
+    let args = App::new("yang")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about(env!("CARGO_PKG_DESCRIPTION"))
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("generate")
+                .about("Generates code for every concept in the spec")
+                .args(&spec_args())
+                .args(&config_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about("Checks that code can be generated, without writing anything")
+                .args(&spec_args())
+                .args(&config_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Fails if generated files are out of date or were edited by hand")
+                .args(&spec_args())
+                .args(&config_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("clean")
+                .about("Removes all files generated by a previous run")
+                .arg(Arg::with_name("release").long("release").help(
+                    "Also remove files without autogeneration markers, as generated for release",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("explain")
+                .about("Explains how a concept will be generated")
+                .arg(
+                    Arg::with_name("CONCEPT")
+                        .help("Internal name of the concept")
+                        .required(true),
+                )
+                .args(&spec_args())
+                .args(&config_args()),
+        )
+        .get_matches();
+
+    let result = match args.subcommand() {
+        ("generate", Some(sub_args)) => generate(sub_args),
+        ("check", Some(sub_args)) => check(sub_args),
+        ("verify", Some(sub_args)) => match verify(sub_args) {
+            Ok(true) => Ok(()),
+            Ok(false) => process::exit(1),
+            Err(e) => Err(e),
+        },
+        ("clean", Some(sub_args)) => clean(sub_args),
+        ("explain", Some(sub_args)) => explain(sub_args),
+        _ => unreachable!("a subcommand is required"),
+    };
+    if let Err(e) = result {
+        eprintln!("{}{}", "Error: ".red().bold(), e);
+        process::exit(1);
+    }
+}
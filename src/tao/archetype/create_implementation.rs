@@ -5,7 +5,7 @@ use crate::tao::relation::attribute::Target;
 use heck::SnakeCase;
 use zamm_yin::node_wrappers::{BaseNodeTrait, CommonNodeTrait};
 use zamm_yin::tao::archetype::{Archetype, ArchetypeTrait, AttributeArchetype};
-use zamm_yin::tao::form::FormTrait;
+use zamm_yin::tao::form::{Form, FormTrait};
 
 /// Convenience trait for creating a new implementation of a concept.
 pub trait CreateImplementation: FormTrait + CommonNodeTrait {
@@ -75,6 +75,7 @@ pub trait CreateImplementation: FormTrait + CommonNodeTrait {
 
 impl CreateImplementation for Archetype {}
 impl CreateImplementation for AttributeArchetype {}
+impl CreateImplementation for Form {}
 
 #[cfg(test)]
 mod tests {
@@ -1,12 +1,24 @@
+use crate::codegen::filesystem::{CodegenPlan, OutputConfig, PlannedFile};
+use crate::codegen::id_lock::{IdLock, ID_LOCK};
+use crate::codegen::manifest::BUILD_MANIFEST;
 use crate::codegen::planning::{
-    archetype_file_path, code_archetype, code_module, handle_init, module_file_path,
+    adopt_imported_modules, archetype_file_path, build_manifest, code_archetype, code_module,
+    complete_module_tree, dot_config, explain, handle_init, import_crate_individuals, init_config,
+    is_individual_request, module_file_path, module_target, validate_implementations, Explanation,
+    IdAllocator,
 };
-use crate::codegen::track_autogen::save_autogen;
-use crate::codegen::{output_code, CodegenConfig};
+use crate::codegen::session::with_default_session;
+use crate::codegen::template::dot::code_dot;
+use crate::codegen::track_autogen::tracked_files;
+use crate::codegen::verify::{find_drift, manifest_files, DriftReport};
+use crate::codegen::{output_code, output_planned, CodegenConfig, CodegenError};
 use crate::tao::action::Implement;
 use crate::tao::form::rust_item::{Concept, Crate, CrateExtension, Module};
 use crate::tao::perspective::KnowledgeGraphNode;
 use colored::*;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
 use zamm_yin::node_wrappers::CommonNodeTrait;
 use zamm_yin::tao::archetype::{Archetype, ArchetypeFormTrait, ArchetypeTrait};
 use zamm_yin::tao::form::FormTrait;
@@ -22,14 +34,27 @@ pub fn implements() -> Box<dyn Iterator<Item = Implement>> {
     )
 }
 
+/// Retrieve implementation requests that pertain to concepts, whether archetypes or named
+/// individuals.
+fn concepts_to_implement() -> Box<dyn Iterator<Item = Implement>> {
+    Box::new(implements().filter(|i| {
+        i.embodiment()
+            .unwrap()
+            .has_ancestor(Concept::archetype().into())
+    }))
+}
+
 /// Retrieve implementation requests that pertain to archetypes.
-fn archetypes_to_implement() -> Vec<Implement> {
-    implements()
-        .filter(|i| {
-            i.embodiment()
-                .unwrap()
-                .has_ancestor(Concept::archetype().into())
-        })
+pub fn archetypes_to_implement() -> Vec<Implement> {
+    concepts_to_implement()
+        .filter(|i| !is_individual_request(i))
+        .collect()
+}
+
+/// Retrieve implementation requests that pertain to named individuals.
+fn individuals_to_implement() -> Vec<Implement> {
+    concepts_to_implement()
+        .filter(is_individual_request)
         .collect()
 }
 
@@ -45,22 +70,95 @@ fn modules_to_implement() -> Vec<Implement> {
 }
 
 /// Handle the implementation request for a new archetype.
-fn handle_archetype_implementation(request: Implement, codegen_cfg: &CodegenConfig) {
-    let code = code_archetype(request, codegen_cfg);
+fn handle_archetype_implementation(
+    request: Implement,
+    codegen_cfg: &CodegenConfig,
+) -> Result<PlannedFile, CodegenError> {
+    let code = code_archetype(request, codegen_cfg)?;
     let target_type = Archetype::from(request.target().unwrap().id());
-    output_code(&code, &archetype_file_path(&target_type), codegen_cfg);
+    output_code(&code, &archetype_file_path(&target_type)?, codegen_cfg)
 }
 
 /// Handle the implementation request for a new module.
-fn handle_module_implementation(request: Implement, codegen_cfg: &CodegenConfig) {
-    let target_module = Module::from(request.embodiment().unwrap().id());
-    let primary_archetype = Archetype::from(target_module.most_prominent_member().unwrap().id());
-    let code = code_module(request, target_module, primary_archetype);
-    output_code(&code, &module_file_path(&primary_archetype), codegen_cfg);
+fn handle_module_implementation(
+    request: Implement,
+    codegen_cfg: &CodegenConfig,
+) -> Result<PlannedFile, CodegenError> {
+    let (target_module, primary_archetype) = module_target(&request)?;
+    let code = code_module(request, target_module, primary_archetype, codegen_cfg)?;
+    output_code(&code, &module_file_path(&primary_archetype)?, codegen_cfg)
 }
 
-/// Handle all defined implementation requests.
-pub fn handle_all_implementations(codegen_cfg: &CodegenConfig) {
+/// Save build information for everything generated, so that downstream crates can import it.
+/// Every generated file planned so far gets listed in it as well.
+fn handle_manifest(
+    archetype_requests: &[Implement],
+    module_requests: &[Implement],
+    individual_requests: &[Implement],
+    plan: &CodegenPlan,
+    codegen_cfg: &CodegenConfig,
+) -> Result<PlannedFile, CodegenError> {
+    let mut manifest = build_manifest(
+        archetype_requests,
+        module_requests,
+        individual_requests,
+        codegen_cfg,
+    )?;
+    manifest.generated_files = plan
+        .files
+        .iter()
+        .filter(|f| f.autogen)
+        .map(|f| f.path.clone())
+        .collect();
+    let output_cfg = OutputConfig {
+        code: &manifest.to_toml()?,
+        file_path: BUILD_MANIFEST,
+        git_ignore: !codegen_cfg.release,
+        cargo_track: false,
+        autogen: false,
+    };
+    output_planned(&output_cfg, codegen_cfg)
+}
+
+/// Save the IDs handed out during this build, so that the next build hands out the same ones.
+fn handle_id_lock(
+    ids: &IdAllocator,
+    codegen_cfg: &CodegenConfig,
+) -> Result<PlannedFile, CodegenError> {
+    let output_cfg = OutputConfig {
+        code: &ids.lock().to_toml()?,
+        file_path: ID_LOCK,
+        // like Cargo.lock, this should be committed so that IDs stay stable for everyone
+        git_ignore: false,
+        cargo_track: false,
+        autogen: false,
+    };
+    output_planned(&output_cfg, codegen_cfg)
+}
+
+/// Export the graph of newly defined and imported concepts, and how they relate to each other and
+/// to this crate's modules, as a Graphviz DOT file at the given destination.
+///
+/// Unlike generated code, the export is a plain file write: it isn't tracked for cleanup, and it
+/// doesn't get added to `.gitignore`.
+pub fn export_knowledge_graph(destination: &str) -> Result<(), CodegenError> {
+    let modules = modules_to_implement()
+        .into_iter()
+        .map(|i| Module::from(i.embodiment().unwrap().id()))
+        .collect::<Vec<Module>>();
+    let path = Path::new(destination);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| CodegenError::io(destination, e))?;
+    }
+    fs::write(path, code_dot(&dot_config(&modules))).map_err(|e| CodegenError::io(destination, e))
+}
+
+/// Plan and output all defined implementation requests, without tracking the output for stale
+/// file cleanup.
+pub(crate) fn generate_all(
+    codegen_cfg: &CodegenConfig,
+    ids: &mut IdAllocator,
+) -> Result<CodegenPlan, CodegenError> {
     let mut current_build = Crate::current();
     if current_build.implementation_name().is_none() {
         println!(
@@ -73,19 +171,185 @@ pub fn handle_all_implementations(codegen_cfg: &CodegenConfig) {
             current_build.set_implementation_name("DUMMY-CUSTOM-CRATE");
         }
     }
+    import_crate_individuals();
 
+    let mut plan = CodegenPlan::default();
     let mut initial_archetype_requests = archetypes_to_implement();
-    // handle initialization first to ensure all concepts land with the right concept IDs, and to
+    // catch every problem up front, instead of one at a time halfway through generation
+    validate_implementations(
+        &initial_archetype_requests,
+        &modules_to_implement(),
+        codegen_cfg,
+    )?;
+    // plan initialization first to ensure all concepts land with the right concept IDs, and to
     // make sure all implement commands get created, even the ones that are implicitly defined
-    handle_init(&mut initial_archetype_requests, codegen_cfg);
-    // handle_init might create new implement commands
+    let init_cfg = init_config(
+        &mut initial_archetype_requests,
+        &individuals_to_implement(),
+        ids,
+        codegen_cfg,
+    )?;
+    // init_config might create new implement commands
     let final_archetype_requests = archetypes_to_implement();
-    for implement_command in final_archetype_requests {
-        handle_archetype_implementation(implement_command, codegen_cfg);
+    // modules imported from other crates still need to declare the new concepts defined here
+    adopt_imported_modules(&final_archetype_requests)?;
+    if codegen_cfg.tao_root && !codegen_cfg.yin {
+        // everything else that leads down to the new concepts, so that no glue is hand-written
+        complete_module_tree(&final_archetype_requests)?;
+    }
+    let module_requests = modules_to_implement();
+    // the implement commands created since then need checking too, before anything gets output
+    validate_implementations(&final_archetype_requests, &module_requests, codegen_cfg)?;
+    plan.add(handle_init(&init_cfg, codegen_cfg)?);
+    plan.add(handle_id_lock(ids, codegen_cfg)?);
+    for implement_command in &final_archetype_requests {
+        plan.add(handle_archetype_implementation(
+            *implement_command,
+            codegen_cfg,
+        )?);
     }
-    for implement_command in modules_to_implement() {
-        handle_module_implementation(implement_command, codegen_cfg);
+    for implement_command in &module_requests {
+        plan.add(handle_module_implementation(
+            *implement_command,
+            codegen_cfg,
+        )?);
     }
+    let manifest = handle_manifest(
+        &final_archetype_requests,
+        &module_requests,
+        &individuals_to_implement(),
+        &plan,
+        codegen_cfg,
+    )?;
+    plan.add(manifest);
+    Ok(plan)
+}
 
-    save_autogen();
+/// Generate everything with the default session's ID allocator. The allocator gets taken out of
+/// the session for the duration, so that the session isn't borrowed while generating, and anything
+/// that generation does to it still works.
+fn generate_with_default_session(codegen_cfg: &CodegenConfig) -> Result<CodegenPlan, CodegenError> {
+    let mut ids = with_default_session(|session| session.take_ids())?;
+    let plan = generate_all(codegen_cfg, &mut ids);
+    with_default_session(|session| session.restore_ids(ids));
+    plan
+}
+
+/// Handle all defined implementation requests as part of the default session. See
+/// `CodegenSession::handle_all_implementations`.
+pub fn handle_all_implementations(
+    codegen_cfg: &CodegenConfig,
+) -> Result<CodegenPlan, CodegenError> {
+    with_default_session(|session| session.config = *codegen_cfg);
+    let plan = generate_with_default_session(codegen_cfg)?;
+    with_default_session(|session| session.record_plan(&plan))?;
+    Ok(plan)
+}
+
+/// Explain how code gets generated for the concept with the given name, as of the IDs recorded in
+/// the ID lock.
+pub fn explain_concept(
+    name: &str,
+    codegen_cfg: &CodegenConfig,
+) -> Result<Explanation, CodegenError> {
+    let target = Archetype::try_from(name).map_err(|_| CodegenError::UnknownConcept {
+        name: name.to_owned(),
+    })?;
+    explain(&target, &IdLock::read(ID_LOCK)?, codegen_cfg)
+}
+
+/// Check generated files for drift as part of the default session. See
+/// `CodegenSession::verify`.
+pub fn verify_generated(codegen_cfg: &CodegenConfig) -> Result<DriftReport, CodegenError> {
+    with_default_session(|session| session.config = *codegen_cfg);
+    let plan = generate_with_default_session(&CodegenConfig {
+        dry_run: true,
+        ..*codegen_cfg
+    })?;
+    find_drift(&plan, &tracked_files()?, &manifest_files()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::define_individual;
+    use crate::tao::archetype::CreateImplementation;
+    use crate::tao::initialize_kb;
+    use std::path::Path;
+    use zamm_yin::tao::form::Form;
+
+    #[test]
+    fn test_dry_run_plan() {
+        initialize_kb();
+        Crate::current().set_implementation_name("dry_run_crate");
+        let mut new_concept = Form::archetype().individuate_as_archetype();
+        new_concept.set_internal_name("dry-run-concept");
+        KnowledgeGraphNode::from(new_concept.id()).mark_newly_defined();
+        new_concept.implement_with_doc("A concept that never hits the disk.");
+
+        let plan = handle_all_implementations(&CodegenConfig {
+            dry_run: true,
+            ..CodegenConfig::default()
+        })
+        .unwrap();
+        let concept_file = plan.file("src/tao/form/dry_run_concept_form.rs").unwrap();
+        assert!(concept_file.code.contains("pub struct DryRunConcept"));
+        assert!(concept_file.git_ignore.is_some());
+        assert!(!Path::new(&concept_file.path).exists());
+        assert!(plan.file("src/tao/auto_init.rs").is_some());
+        assert!(plan.file(ID_LOCK).unwrap().code.contains("dry-run-concept"));
+        let manifest = plan.file(BUILD_MANIFEST).unwrap();
+        assert!(manifest.code.contains("name = \"dry-run-concept\""));
+    }
+
+    #[test]
+    fn test_tao_root_plan() {
+        initialize_kb();
+        Crate::current().set_implementation_name("tao_root_crate");
+        let mut new_concept = Form::archetype().individuate_as_archetype();
+        new_concept.set_internal_name("tao-root-concept");
+        KnowledgeGraphNode::from(new_concept.id()).mark_newly_defined();
+        new_concept.implement_with_doc("A concept that needs no hand-written glue.");
+
+        let plan = handle_all_implementations(&CodegenConfig {
+            dry_run: true,
+            tao_root: true,
+            ..CodegenConfig::default()
+        })
+        .unwrap();
+        let form_module = plan.file("src/tao/form/mod.rs").unwrap();
+        assert!(form_module.code.contains("mod tao_root_concept_form;"));
+        let root_module = plan.file("src/tao/mod.rs").unwrap();
+        assert!(root_module.code.contains("pub mod form;"));
+        assert!(root_module.code.contains("mod auto_init;"));
+        assert!(root_module.code.contains(
+            "pub use auto_init::{walk, AnyConcept, DowncastError, MissingAttributeError, Visitor, YIN_MAX_ID};"
+        ));
+        assert!(root_module.code.contains("zamm_yin::tao::initialize_kb();"));
+        assert!(root_module.code.contains("auto_init::initialize_types();"));
+    }
+
+    #[test]
+    fn test_individuals_plan() {
+        initialize_kb();
+        Crate::current().set_implementation_name("individuals_crate");
+        let mut lamp = Form::archetype().individuate_as_archetype();
+        lamp.set_internal_name("plan-lamp");
+        KnowledgeGraphNode::from(lamp.id()).mark_newly_defined();
+        lamp.implement_with_doc("A source of light.");
+        define_individual(lamp, "plan-desk-lamp", "The lamp on the desk.");
+
+        let plan = handle_all_implementations(&CodegenConfig {
+            dry_run: true,
+            ..CodegenConfig::default()
+        })
+        .unwrap();
+        // individuals don't get files of their own
+        assert!(plan.file("src/tao/form/plan_desk_lamp_form.rs").is_none());
+        let lamp_file = plan.file("src/tao/form/plan_lamp_form.rs").unwrap();
+        assert!(lamp_file.code.contains("pub fn plan_desk_lamp() -> Self {"));
+        let init_file = plan.file("src/tao/auto_init.rs").unwrap();
+        assert!(init_file.code.contains("\"plan-desk-lamp\""));
+        assert!(plan.file(ID_LOCK).unwrap().code.contains("plan-desk-lamp"));
+    }
 }
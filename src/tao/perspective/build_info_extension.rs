@@ -29,6 +29,8 @@ pub trait BuildInfoExtension: FormTrait + CommonNodeTrait {
     /// Retrieve crate which the object was built as a part of. This is called `crate_name` instead
     /// of just `crate` because `crate` is a reserved keyword in Rust.
     fn crate_name(&self) -> Option<Rc<str>> {
+        // todo: replace with a generated inverse accessor for Member once yin.md is built with a
+        // version of Yang that generates them, and that can generate them for Yin's types
         self.inheritance_wrapper()
             .base_wrapper()
             .incoming_nodes(Member::TYPE_ID)
@@ -60,6 +62,8 @@ pub trait BuildInfoExtension: FormTrait + CommonNodeTrait {
 
     /// Get the module that represents this archetype as its primary concept.
     fn representative_module(&self) -> Option<Module> {
+        // todo: replace with a generated inverse accessor for MostProminentMember, as with
+        // crate_name
         self.inheritance_wrapper()
             .base_wrapper()
             .incoming_nodes(MostProminentMember::TYPE_ID)